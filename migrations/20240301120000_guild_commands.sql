@@ -0,0 +1,7 @@
+create table "guild_commands" (
+  "guild" integer,
+  "command" text, -- full command path, e.g. '/deezer as-file'
+  "enabled" integer not null,
+
+  primary key ("guild", "command")
+) strict, without rowid;
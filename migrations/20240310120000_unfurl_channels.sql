@@ -0,0 +1,4 @@
+create table "unfurl_channels" (
+  "channel" integer primary key,
+  "enabled" integer not null
+) strict, without rowid;
@@ -0,0 +1,7 @@
+create table "components" (
+  "id" integer primary key,
+  "time" integer not null default (unixepoch()),
+  "user" integer not null, -- the only one who can interact with the components
+  "command" text not null, -- full path of the command the flow has started from, e.g. '/deezer as-file'
+  "state" text not null -- json, depends on the route
+) strict;
@@ -0,0 +1,11 @@
+create table "errors" (
+  "id" integer primary key, -- id of the interaction, it's shown to users mixed up as `ERROR ID`
+  "time" integer not null default (unixepoch()),
+  "command" text not null, -- the command with all of its options, e.g. '/deezer as-file query:…'
+  "user" integer not null,
+  "guild" integer,
+  "display" text,
+  "debug" text,
+  "panic" text,
+  "traceback" text -- only for python errors
+) strict;
@@ -0,0 +1,6 @@
+create table "responses" (
+  "key" text primary key, -- method and normalized url, e.g. 'GET https://api.urbandictionary.com/v0/define?page=1&term=rust'
+  "source" text not null, -- what the request is counted under, e.g. 'weather/geo'
+  "body" blob not null,
+  "expires" integer not null -- unix time
+) strict;
@@ -0,0 +1,7 @@
+create table "guild_command_roles" (
+  "guild" integer,
+  "command" text, -- full command path, e.g. '/deezer as-file'
+  "role" integer,
+
+  primary key ("guild", "command", "role")
+) strict, without rowid;
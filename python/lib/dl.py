@@ -16,10 +16,19 @@ Format = dict[str, Any]
 FormatId = str
 FormatSelectorContext = dict[str, Any]
 FormatSelector = Callable[[FormatSelectorContext], list[FormatId]]
-
-def download(url: str, out_dir: str, fs: Optional[FormatSelector] = None, **kwargs: Any) -> Info:
+Progress = dict[str, Any]
+ProgressHook = Callable[[Progress], None]
+
+def download(
+  url: str,
+  out_dir: str,
+  fs: Optional[FormatSelector] = None,
+  progress: Optional[ProgressHook] = None,
+  **kwargs: Any,
+) -> Info:
   format = _wrap_format_selector(fs) if fs else {}
-  params = _defaults() | {'paths': {'home': out_dir}} | format | kwargs
+  hooks = _wrap_progress_hook(progress) if progress else {}
+  params = _defaults() | {'paths': {'home': out_dir}} | format | hooks | kwargs
   with YoutubeDL(params) as ydl:
     return ydl.extract_info(url) # type: ignore
 
@@ -59,6 +68,19 @@ def _wrap_format_selector(fs: FormatSelector) -> Params:
 
   return {'match_filter': match_filter, 'format': format}
 
+def _wrap_progress_hook(progress: ProgressHook) -> Params:
+  # the hook is allowed to raise, that's how a download gets cancelled
+  def hook(d: dict[str, Any]):
+    if d['status'] == 'downloading':
+      progress({
+        'downloaded': d.get('downloaded_bytes'),
+        'total': d.get('total_bytes') or d.get('total_bytes_estimate'),
+        'speed': d.get('speed'),
+        'eta': d.get('eta'),
+      })
+
+  return {'progress_hooks': [hook]}
+
 def _merge_formats(formats: list[Format]) -> Format:
   # https://github.com/yt-dlp/yt-dlp#use-a-custom-format-selector
   return {
@@ -1,7 +1,6 @@
 import functools
 import logging
-import os
-from typing import Any
+from typing import Any, Callable, Optional
 
 from deemix import generateDownloadObject # type: ignore
 from deemix.downloader import Downloader
@@ -15,9 +14,20 @@ logger = logging.getLogger(__name__)
 
 # ---
 
+Progress = dict[str, Any]
+ProgressHook = Callable[[Progress], None]
+
 class DzException(Exception):
   pass
 
+_credentials: dict[str, Any] = {}
+
+# has to be called before anything else, spotify links only work when its credentials are given
+def configure(arl: str, spotify: Optional[tuple[str, str]] = None) -> None:
+  _credentials['arl'] = arl
+  _credentials['spotify'] = spotify
+  _dz.cache_clear()
+
 def search(query: str) -> dict[str, Any]:
   dz = _dz()
   return dz.api.search_track(query) # type: ignore
@@ -27,12 +37,13 @@ def generate_download_object(url: str, bitrate: str = 'flac') -> Any:
   br = getBitrateNumberFromText(bitrate)
   return generateDownloadObject(dz, url, br, dz.plugins, dz.listener) # type: ignore
 
-def download(dl_obj: Any, out_dir: str) -> None:
+def download(dl_obj: Any, out_dir: str, progress: Optional[ProgressHook] = None) -> None:
   if not isinstance(dl_obj, Single):
     raise DzException("single tracks only")
   dz = _dz()
   settings = DEFAULTS | {'downloadLocation': out_dir}
-  Downloader(dz, dl_obj, settings, dz.listener).start()
+  listener = _ProgressListener(progress) if progress else dz.listener
+  Downloader(dz, dl_obj, settings, listener).start()
 
 # ---
 
@@ -44,15 +55,19 @@ class _Deezer(Deezer):
   def __init__(self):
     super().__init__()
     self.listener = _LogListener()
-    self.plugins = {'spotify': _Spotify()}
-    self.login_via_arl(os.environ['DEEZER_ARL']) # type: ignore
+    self.plugins = {}
+    if 'arl' not in _credentials:
+      raise DzException("not configured")
+    if spotify := _credentials['spotify']:
+      self.plugins['spotify'] = _Spotify(*spotify)
+    self.login_via_arl(_credentials['arl']) # type: ignore
 
 class _Spotify(Spotify):
-  def __init__(self):
+  def __init__(self, app_id: str, app_secret: str):
     super().__init__() # type: ignore
     self.credentials = {
-      'clientId': os.environ['SPOTIFY_APP_ID'],
-      'clientSecret': os.environ['SPOTIFY_APP_SECRET'],
+      'clientId': app_id,
+      'clientSecret': app_secret,
     }
     self.configFolder.mkdir(parents=True, exist_ok=True)
     self.checkCredentials()
@@ -60,3 +75,13 @@ class _Spotify(Spotify):
 class _LogListener:
   def send(self, key: str, value: Any = None):
     logger.debug(f'{key} {value}')
+
+class _ProgressListener(_LogListener):
+  def __init__(self, progress: ProgressHook):
+    self.progress = progress
+
+  # the hook is allowed to raise, that's how a download gets cancelled
+  def send(self, key: str, value: Any = None):
+    super().send(key, value)
+    if key == 'updateQueue' and isinstance(value, dict) and 'progress' in value:
+      self.progress({'fraction': value['progress'] / 100})
@@ -1,3 +1,7 @@
+import os
+
+import pytest
+
 from ..lib import dz
 
 def test_search():
@@ -15,3 +19,9 @@ def test_spotify_plugin():
   dl_obj = dz.generate_download_object(url)
   assert dl_obj.artist == 'Skeler'
   assert dl_obj.title == 'Pale Light'
+
+# the bot passes these in from its config, here they're taken straight from the env
+@pytest.fixture(autouse=True, scope='module')
+def configure():
+  spotify = (os.environ['SPOTIFY_APP_ID'], os.environ['SPOTIFY_APP_SECRET'])
+  dz.configure(os.environ['DEEZER_ARL'], spotify)
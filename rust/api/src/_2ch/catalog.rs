@@ -1,7 +1,13 @@
+use std::time::Duration;
+
 use serde::Deserialize;
+use util::network::{self, Http};
 
 use crate::{random, regex};
 
+// threads move around all the time, this only spares rerolls
+const TTL: Duration = Duration::from_secs(30);
+
 #[derive(Debug, Deserialize)]
 pub struct Catalog {
   pub threads: Vec<Thread>,
@@ -19,9 +25,10 @@ pub struct Thread {
 }
 
 impl Catalog {
-  pub async fn get(board_id: &str) -> reqwest::Result<Self> {
+  pub async fn get(http: &Http, board_id: &str) -> network::Result<Self> {
     let url = format!("https://2ch.hk/{board_id}/catalog.json");
-    let resp = reqwest::get(url).await?.error_for_status()?;
+    let get = http.get(url).cached("2ch/catalog", TTL);
+    let resp = get.send().await?.error_for_status()?;
     resp.json().await
   }
 
@@ -45,3 +52,24 @@ pub fn thread_filter(include: Option<&str>, exclude: Option<&str>) -> regex::Res
     included && !excluded
   })
 }
+
+#[cfg(test)]
+mod tests {
+  use stub::Stub;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn get() {
+    let stub = Stub::start();
+    stub.serve("2ch.hk/pr/catalog.json", "2ch/catalog.json");
+
+    let catalog = Catalog::get(&stub.http(), "pr").await.unwrap();
+    assert_eq!(catalog.threads.len(), 2);
+
+    let filter = thread_filter(Some("rust"), None).unwrap();
+    let (thread, file_index) = catalog.random(filter, |t| t.files_count as usize).unwrap();
+    assert_eq!((thread.id, thread.posts_count), (3000001, 120));
+    assert!(file_index < 15);
+  }
+}
@@ -1,6 +1,7 @@
 use ego_tree::NodeRef;
 use scraper::{CaseSensitivity::*, Html, Node};
 use serde::Deserialize;
+use util::network::{self, Http};
 
 #[derive(Debug, Deserialize)]
 pub struct Threads {
@@ -28,9 +29,9 @@ pub struct File {
 }
 
 impl Thread {
-  pub async fn get(domain: &str, board: &str, thread: u64) -> reqwest::Result<Self> {
+  pub async fn get(http: &Http, domain: &str, board: &str, thread: u64) -> network::Result<Self> {
     let url = format!("https://{domain}/{board}/res/{thread}.json");
-    let resp = reqwest::get(url).await?.error_for_status()?;
+    let resp = http.get(url).send().await?.error_for_status()?;
     let Threads { mut threads } = resp.json().await?;
     Ok(threads.swap_remove(0))
   }
@@ -141,3 +142,43 @@ impl Post {
     visit(acc, domain, root);
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use stub::Stub;
+
+  use super::*;
+
+  async fn thread() -> Thread {
+    let stub = Stub::start();
+    stub.serve("2ch.hk/pr/res/3000001.json", "2ch/thread.json");
+    Thread::get(&stub.http(), "2ch.hk", "pr", 3000001).await.unwrap()
+  }
+
+  #[tokio::test]
+  async fn replies() {
+    let thread = thread().await;
+    let (post, index) = thread.get_post_by_id(3000005).unwrap();
+    assert_eq!(index, 1);
+    assert_eq!(post.files.as_ref().map(Vec::len), Some(0));
+
+    let replies = |id| thread.find_replies_to(id).map(|p| p.id).collect::<Vec<_>>();
+    assert_eq!(replies(3000001), [3000005]);
+    assert_eq!(replies(3000005), [3000007]);
+  }
+
+  #[tokio::test]
+  async fn render() {
+    let thread = thread().await;
+    let render = |id| thread.get_post_by_id(id).unwrap().0.render("2ch.hk");
+
+    assert_eq!(
+      render(3000001),
+      "Обсуждаем **Rust**.\nПрошлый: [>>2999999](https://2ch.hk/pr/res/2999999.html)"
+    );
+    assert_eq!(
+      render(3000005),
+      "[>>3000001](https://2ch.hk/pr/res/3000001.html#3000001)\n> Обсуждаем\n||борроу чекер|| *не нужен*"
+    );
+  }
+}
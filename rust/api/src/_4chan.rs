@@ -2,6 +2,7 @@ use std::sync::OnceLock;
 
 use regex::Regex;
 
+pub mod boards;
 pub mod catalog;
 pub mod thread;
 
@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+use util::network::{self, Http};
+
+// boards hardly ever change
+const TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Deserialize)]
+pub struct Boards {
+  pub boards: Vec<Board>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Board {
+  #[serde(rename = "board")]
+  pub id: String,
+  pub title: String,
+  // 0 for the nsfw ones
+  #[serde(rename = "ws_board")]
+  pub worksafe: u8,
+}
+
+impl Boards {
+  pub async fn get(http: &Http) -> network::Result<Self> {
+    let url = "https://a.4cdn.org/boards.json";
+    let get = http.get(url).cached("4chan/boards", TTL);
+    let resp = get.send().await?.error_for_status()?;
+    let json = resp.json().await?;
+    Ok(json)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use stub::Stub;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn get() {
+    let stub = Stub::start();
+    stub.serve("a.4cdn.org/boards.json", "4chan/boards.json");
+
+    let boards = Boards::get(&stub.http()).await.unwrap().boards;
+    let ids = boards.iter().map(|b| (&*b.id, &*b.title)).collect::<Vec<_>>();
+    assert_eq!(ids[1], ("g", "Technology"));
+    assert_eq!(ids.len(), 4);
+    assert!(boards.iter().all(|b| b.worksafe == 1));
+  }
+}
@@ -1,7 +1,13 @@
+use std::time::Duration;
+
 use serde::Deserialize;
+use util::network::{self, Http};
 
 use crate::{random, regex};
 
+// threads move around all the time, this only spares rerolls
+const TTL: Duration = Duration::from_secs(30);
+
 #[derive(Debug, Deserialize)]
 pub struct Catalog {
   pub pages: Vec<Page>,
@@ -26,9 +32,10 @@ pub struct Thread {
 }
 
 impl Catalog {
-  pub async fn get(board_id: &str) -> reqwest::Result<Self> {
+  pub async fn get(http: &Http, board_id: &str) -> network::Result<Self> {
     let url = format!("https://a.4cdn.org/{board_id}/catalog.json");
-    let resp = reqwest::get(url).await?.error_for_status()?;
+    let get = http.get(url).cached("4chan/catalog", TTL);
+    let resp = get.send().await?.error_for_status()?;
     let pages = resp.json().await?;
     Ok(Self { pages })
   }
@@ -55,3 +62,38 @@ pub fn thread_filter(include: Option<&str>, exclude: Option<&str>) -> regex::Res
     included && !excluded
   })
 }
+
+#[cfg(test)]
+mod tests {
+  use stub::Stub;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn get() {
+    let stub = Stub::start();
+    stub.serve("a.4cdn.org/g/catalog.json", "4chan/catalog.json");
+
+    let catalog = Catalog::get(&stub.http(), "g").await.unwrap();
+    let threads = catalog.pages.iter().flat_map(|p| &p.threads).collect::<Vec<_>>();
+    assert_eq!(threads.len(), 3);
+    assert_eq!(threads[1].subject.as_deref(), Some("/dpt/ - Daily Programming Thread"));
+    assert_eq!((threads[1].replies, threads[1].images), (3, 2));
+    assert_eq!(threads[2].subject, None);
+  }
+
+  #[tokio::test]
+  async fn random() {
+    let stub = Stub::start();
+    stub.serve("a.4cdn.org/g/catalog.json", "4chan/catalog.json");
+    let catalog = Catalog::get(&stub.http(), "g").await.unwrap();
+
+    let filter = thread_filter(Some("DPT"), None).unwrap();
+    let (thread, file_index) = catalog.random(filter, |t| t.images as usize).unwrap();
+    assert_eq!(thread.id, 99123456);
+    assert!(file_index < 2);
+
+    let filter = thread_filter(None, Some("dpt|wiki")).unwrap();
+    assert!(catalog.random(filter, |t| t.images as usize).is_none());
+  }
+}
@@ -2,6 +2,7 @@ use ego_tree::NodeRef;
 use scraper::{CaseSensitivity::*, Html, Node};
 use serde::Deserialize;
 use url::Url;
+use util::network::{self, Http};
 
 #[derive(Debug, Deserialize)]
 pub struct Thread {
@@ -30,9 +31,9 @@ pub struct File {
 }
 
 impl Thread {
-  pub async fn get(board: &str, thread: u64) -> reqwest::Result<Self> {
+  pub async fn get(http: &Http, board: &str, thread: u64) -> network::Result<Self> {
     let url = format!("https://a.4cdn.org/{board}/thread/{thread}.json");
-    let resp = reqwest::get(url).await?.error_for_status()?;
+    let resp = http.get(url).send().await?.error_for_status()?;
     let json = resp.json().await?;
     Ok(json)
   }
@@ -124,3 +125,47 @@ impl Post {
     visit(acc, base, root);
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use stub::Stub;
+
+  use super::*;
+
+  async fn thread() -> Thread {
+    let stub = Stub::start();
+    stub.serve("a.4cdn.org/g/thread/99123456.json", "4chan/thread.json");
+    Thread::get(&stub.http(), "g", 99123456).await.unwrap()
+  }
+
+  #[tokio::test]
+  async fn replies() {
+    let thread = thread().await;
+    let (post, index) = thread.get_post_by_id(99123501).unwrap();
+    assert_eq!(index, 1);
+    assert_eq!(
+      post.file.as_ref().map(|f| (f.id, &*f.ext)),
+      Some((1709291100456, ".jpg"))
+    );
+
+    let replies = |id| thread.find_replies_to(id).map(|p| p.id).collect::<Vec<_>>();
+    assert_eq!(replies(99123456), [99123501]);
+    assert_eq!(replies(99123501), [99123503]);
+    assert!(thread.get_post_by_id(1).is_none());
+  }
+
+  #[tokio::test]
+  async fn render() {
+    let thread = thread().await;
+    let base = Url::parse("https://boards.4chan.org/g/thread/99123456").unwrap();
+    let render = |id| thread.get_post_by_id(id).unwrap().0.render(&base);
+
+    assert_eq!(
+      render(99123501),
+      "[>>99123456](https://boards.4chan.org/g/thread/99123456#p99123456)\n\
+       a borrow checker ||that actually works||\n\
+       > implying"
+    );
+    assert_eq!(render(99123502), "```fn main() {}```~~>>99000000~~");
+  }
+}
@@ -3,6 +3,7 @@ use std::ffi::OsString;
 use std::hash::{DefaultHasher, Hasher};
 use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::{fs, io};
 
@@ -22,12 +23,19 @@ pub enum Name {
 
 #[derive(Debug)]
 pub struct LruFileCache {
-  bytes_limit: u64,
+  bytes_limit: AtomicU64,
   base_url: Url,
   working_dir: PathBuf,
   state: Mutex<State>,
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct Stats {
+  pub files: usize,
+  pub bytes_stored: u64,
+  pub bytes_limit: u64,
+}
+
 #[derive(Debug)]
 struct State {
   bytes_stored: u64,
@@ -59,7 +67,7 @@ impl LruFileCache {
   pub fn new_blocking(base_url: Url, working_dir: PathBuf, bytes_limit: u64) -> io::Result<Self> {
     let cache = Self {
       state: State::new(&working_dir)?.into(),
-      bytes_limit,
+      bytes_limit: bytes_limit.into(),
       base_url,
       working_dir,
     };
@@ -69,6 +77,12 @@ impl LruFileCache {
     Ok(cache)
   }
 
+  // takes effect on the next stored file, that's when the files over the limit get removed.
+  pub fn set_bytes_limit(&self, bytes_limit: u64) {
+    self.bytes_limit.store(bytes_limit, Ordering::Relaxed);
+    self.log_stats();
+  }
+
   pub fn watch_blocking(&self) -> io::Result<()> {
     let mut inotify = Inotify::init()?;
     let mut buffer = [0; 1 << 10];
@@ -141,16 +155,30 @@ impl LruFileCache {
 }
 
 impl LruFileCache {
+  pub fn stats(&self) -> Stats {
+    let state = self.state.lock();
+    Stats {
+      files: state.files.len(),
+      bytes_stored: state.bytes_stored,
+      bytes_limit: self.bytes_limit(),
+    }
+  }
+
+  fn bytes_limit(&self) -> u64 {
+    self.bytes_limit.load(Ordering::Relaxed)
+  }
+
   fn reserve(&self, bytes: u64) -> io::Result<bool> {
-    let fits = bytes <= self.bytes_limit;
+    let limit = self.bytes_limit();
+    let fits = bytes <= limit;
 
     if fits {
       let mut state = self.state.lock();
 
-      let overshoot = (state.bytes_stored + bytes).saturating_sub(self.bytes_limit);
+      let overshoot = (state.bytes_stored + bytes).saturating_sub(limit);
       tracing::debug!("reserving {}B (overshoot: {}B)", bytes.iec(), overshoot.iec());
 
-      while state.bytes_stored + bytes > self.bytes_limit {
+      while state.bytes_stored + bytes > limit {
         let (name, file) = state.pop().unwrap();
         tracing::debug!(?name, "removing a {}B file…", file.size.iec());
         fs::remove_file(&self.working_dir.join(&name))?;
@@ -164,8 +192,9 @@ impl LruFileCache {
     let state = self.state.lock();
     let files = state.files.len();
     let stored = state.bytes_stored.iec();
-    let limit = self.bytes_limit.iec();
-    let ratio = state.bytes_stored as f64 / self.bytes_limit as f64 * 100.0;
+    let limit = self.bytes_limit();
+    let ratio = state.bytes_stored as f64 / limit as f64 * 100.0;
+    let limit = limit.iec();
     tracing::debug!("cache: {} files ({}B/{}B, {:.0}%)", files, stored, limit, ratio);
   }
 
@@ -1,16 +1,25 @@
 use std::mem;
 
 use darling::export::NestedMeta;
-use darling::{FromMeta, Result};
+use darling::{Error, FromMeta, Result};
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{Data, DataEnum, DeriveInput};
+use quote::{format_ident, quote};
+use syn::{Data, DataEnum, DeriveInput, Ident, Lit};
+
+use crate::limits;
 
 #[derive(Debug, FromMeta)]
 struct VariantArgs {
-  #[darling(default, rename = "default")]
-  _default: bool,
+  #[darling(default)]
+  default: bool,
   name: Option<String>,
+  value: Option<Lit>,
+}
+
+struct Variant {
+  ident: Ident,
+  name: String,
+  value: Option<Lit>,
 }
 
 pub fn expand(input: TokenStream) -> Result<TokenStream> {
@@ -20,7 +29,8 @@ pub fn expand(input: TokenStream) -> Result<TokenStream> {
     unimplemented!()
   };
 
-  let choice_names = variants
+  let mut default = None;
+  let variants = variants
     .iter_mut()
     .map(|v| {
       let args = VariantArgs::from_list({
@@ -29,32 +39,132 @@ pub fn expand(input: TokenStream) -> Result<TokenStream> {
           .map(|attr| NestedMeta::Meta(attr.meta))
           .collect::<Vec<_>>()
       })?;
-      Ok(args.name.unwrap_or_else(|| v.ident.to_string()))
+      if args.default && default.replace(v.ident.clone()).is_some() {
+        return Err(Error::custom("only one variant can be the default").with_span(&v.ident));
+      }
+      Ok(Variant {
+        ident: v.ident.clone(),
+        name: args.name.unwrap_or_else(|| v.ident.to_string()),
+        value: args.value,
+      })
     })
     .collect::<Result<Vec<_>>>()?;
 
   let enum_ident = input.ident;
+  if variants.len() > limits::CHOICES {
+    let msg = format!("{} choices, at most {} are allowed", variants.len(), limits::CHOICES);
+    return Err(Error::custom(msg).with_span(&enum_ident));
+  }
+  for v in &variants {
+    let value = match &v.value {
+      Some(Lit::Str(value)) => Some(value.value()),
+      _ => None,
+    };
+    if v.name.is_empty() {
+      return Err(Error::custom("choice names can't be empty").with_span(&v.ident));
+    }
+    limits::len("choice names", Some(&v.name), limits::CHOICE_LEN, &v.ident)?;
+    limits::len("choice values", value.as_deref(), limits::CHOICE_LEN, &v.ident)?;
+  }
+
+  let (ty, values) = values(&variants)?;
+  let choice_names = variants.iter().map(|v| &v.name);
   let variant_idents = variants.iter().map(|v| &v.ident).collect::<Vec<_>>();
-  let variant_idents_str = variants.iter().map(|v| v.ident.to_string()).collect::<Vec<_>>();
 
+  let choice_values = values.iter().map(|value| match ty {
+    "String" => quote! { crate::client::ChoiceValue::String(#value) },
+    "Integer" => quote! { crate::client::ChoiceValue::Integer(#value) },
+    _ => quote! { crate::client::ChoiceValue::Number(#value) },
+  });
+
+  // floats can't be matched on
+  let extract_value = match ty {
+    "String" => quote! { <&str as crate::client::CommandOptionTrait>::extract(value)? },
+    "Integer" => quote! { <i64 as crate::client::CommandOptionTrait>::extract(value)? },
+    _ => quote! { <f64 as crate::client::CommandOptionTrait>::extract(value)? },
+  };
+  let extract = match ty {
+    "Number" => quote! {
+      let value = #extract_value;
+      #(if value == #values { return Ok(Self::#variant_idents); })*
+      unreachable!()
+    },
+    _ => quote! {
+      match #extract_value {
+        #(#values => Ok(Self::#variant_idents),)*
+        _ => unreachable!()
+      }
+    },
+  };
+
+  // an option with a default can be left out
+  let (required, extract_default) = match &default {
+    Some(default) => (false, quote! { if value.is_none() { return Ok(Self::#default); } }),
+    None => (true, quote! {}),
+  };
+
+  let ty = format_ident!("{}", ty);
   let trait_impl = quote! {
     impl<'a> crate::client::CommandOptionTrait<'a> for #enum_ident {
-      const TYPE: ::serenity::all::CommandOptionType = ::serenity::all::CommandOptionType::String;
+      const TYPE: ::serenity::all::CommandOptionType = ::serenity::all::CommandOptionType::#ty;
       const CHOICES: Option<&'static [crate::client::CommandOptionChoice]> = Some(&[
         #(crate::client::CommandOptionChoice {
           name: #choice_names,
-          value: #variant_idents_str
+          value: #choice_values,
         }),*
       ]);
+      const REQUIRED: bool = #required;
 
-      fn extract(value: Option<&::serenity::all::ResolvedOption<'a>>) -> Self {
-        match crate::client::CommandOptionTrait::extract(value) {
-          #(#variant_idents_str => Self::#variant_idents,)*
-          _ => unreachable!()
-        }
+      fn extract(value: Option<&'a ::serenity::all::ResolvedOption<'_>>) -> crate::client::Result<Self> {
+        #extract_default
+        #extract
       }
     }
   };
 
   Ok(trait_impl.into())
 }
+
+// either every variant has a value of the same type, or none do and they're strings named after the variants
+fn values(variants: &[Variant]) -> Result<(&'static str, Vec<Lit>)> {
+  if variants.iter().all(|v| v.value.is_none()) {
+    let values = variants.iter().map(|v| {
+      let value = v.ident.to_string();
+      Lit::Str(syn::LitStr::new(&value, v.ident.span()))
+    });
+    return Ok(("String", values.collect()));
+  }
+
+  let mut ty = None;
+  let mut errors = Error::accumulator();
+  let values = variants.iter().filter_map(|v| {
+    let Some(value) = &v.value else {
+      errors.push(Error::custom("either every variant has a value, or none do").with_span(&v.ident));
+      return None;
+    };
+    let value_ty = match value {
+      Lit::Str(_) => "String",
+      // what discord takes as integer choices is limited to `i32` in serenity
+      Lit::Int(int) => match int.base10_parse::<i32>() {
+        Ok(_) => "Integer",
+        Err(err) => {
+          errors.push(err.into());
+          return None;
+        }
+      },
+      Lit::Float(_) => "Number",
+      _ => {
+        errors.push(Error::custom("expected a string, an integer or a float").with_span(value));
+        return None;
+      }
+    };
+    if *ty.get_or_insert(value_ty) != value_ty {
+      errors.push(Error::custom("all the values have to be of the same type").with_span(value));
+    }
+    Some(value.clone())
+  });
+  let values = values.collect::<Vec<_>>();
+  errors.finish()?;
+
+  Ok((ty.unwrap(), values))
+}
@@ -4,23 +4,53 @@ use darling::export::NestedMeta;
 use darling::{FromMeta, Result};
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
-use syn::{parse_quote, Expr, FnArg, ItemFn, Lit, Pat};
+use syn::{parse_quote, Expr, FnArg, GenericArgument, ItemFn, Lit, LitStr, Pat, Path, PathArguments, Type};
 
-use crate::util;
+use crate::l10n::{self, Localizations};
+use crate::{limits, ratelimit, util};
 
 #[derive(Debug, FromMeta)]
 struct FnArgs {
   #[darling(default)]
   owner_only: bool,
+  #[darling(default)]
+  guild_only: bool,
+  #[darling(default)]
+  nsfw: bool,
+  #[darling(default)]
+  context_menu: bool,
+  permissions: Option<LitStr>,
   desc: Option<String>,
+  l10n: Option<Localizations>,
+  register: Option<LitStr>,
+  requires: Option<LitStr>,
+  ratelimits: Option<Expr>,
+  rate: Option<LitStr>,
+  cost: Option<Lit>,
+  scope: Option<LitStr>,
 }
 
 #[derive(Debug, FromMeta)]
 struct FnInputArgs {
   name: Option<String>,
   desc: Option<String>,
+  l10n: Option<Localizations>,
   min: Option<Lit>,
   max: Option<Lit>,
+  min_length: Option<u16>,
+  max_length: Option<u16>,
+  channel_types: Option<LitStr>,
+  repeat: Option<usize>,
+  autocomplete: Option<Path>,
+}
+
+// how many options a `Vec<T>` turns into if it doesn't say otherwise
+const DEFAULT_REPEAT: usize = 5;
+
+// a parameter registers either one option, or several if it's a `Vec<T>`
+struct ParamOptions {
+  options: Vec<proc_macro2::TokenStream>,
+  repeated: bool,
 }
 
 pub fn expand(args: TokenStream, input: TokenStream) -> Result<TokenStream> {
@@ -29,9 +59,46 @@ pub fn expand(args: TokenStream, input: TokenStream) -> Result<TokenStream> {
   let meta_list = NestedMeta::parse_meta_list(args.into())?;
   let fn_args = FnArgs::from_list(&meta_list)?;
 
+  let desc_len = limits::DESCRIPTION_LEN;
+  limits::len("descriptions", fn_args.desc.as_deref(), desc_len, &function.sig.ident)?;
   let cmd_desc = util::option(fn_args.desc);
-  let cmd_options = command_options(&mut function)?;
+  let cmd_l10n = l10n::expand(fn_args.l10n, fn_args.context_menu)?;
+  let cmd_target = context_menu_target(&function, fn_args.context_menu)?;
+  let cmd_options = match cmd_target {
+    Some(_) => Vec::new(),
+    None => command_options(&mut function)?,
+  };
+  let n_options = cmd_options.iter().map(|param| param.options.len()).sum::<usize>();
+  if n_options > limits::OPTIONS {
+    let msg = format!("{n_options} options, at most {} are allowed", limits::OPTIONS);
+    return Err(darling::Error::custom(msg).with_span(&function.sig.ident));
+  }
+  let cmd_kind: Expr = match &cmd_target {
+    Some(ty) => parse_quote! { <#ty as CommandTargetTrait>::TYPE },
+    None => parse_quote! { ::serenity::all::CommandType::ChatInput },
+  };
   let cmd_owner_only = fn_args.owner_only;
+  let cmd_permissions = permissions(fn_args.permissions)?;
+  let cmd_guild_only = fn_args.guild_only;
+  let cmd_nsfw = fn_args.nsfw;
+  let cmd_registration = registration(fn_args.register)?;
+  let cmd_requires = requires(fn_args.requires)?;
+  let cmd_ratelimits = match (
+    fn_args.ratelimits,
+    ratelimit::expand(fn_args.rate, fn_args.cost, fn_args.scope)?,
+  ) {
+    (Some(expr), None) => expr,
+    (None, Some(ratelimit)) => parse_quote! {{
+      // fn calls don't get promoted to `'static`, but consts do
+      const RATELIMITS: &[Ratelimit] = &[#ratelimit];
+      RATELIMITS
+    }},
+    (None, None) => parse_quote! { &[] },
+    (Some(expr), Some(_)) => {
+      let msg = "`ratelimits` can't be used together with `rate`";
+      return Err(darling::Error::custom(msg).with_span(&expr));
+    }
+  };
 
   let fn_vis = &function.vis;
   let fn_async = &function.sig.asyncness;
@@ -57,6 +124,7 @@ pub fn expand(args: TokenStream, input: TokenStream) -> Result<TokenStream> {
 
   let cmd_option_names_static = cmd_option_names
     .iter()
+    .take(cmd_options.len())
     .map(|name| match &**name {
       Pat::Ident(ident) => {
         let span = ident.ident.span();
@@ -67,30 +135,79 @@ pub fn expand(args: TokenStream, input: TokenStream) -> Result<TokenStream> {
     })
     .collect::<Vec<_>>();
 
+  let cmd_extract = match cmd_target {
+    Some(_) => quote! {
+      let #(#cmd_option_names)* = CommandTargetTrait::extract(#cmd_context.target.as_ref());
+    },
+    None => {
+      let extract = cmd_options.iter().zip(&cmd_option_names_static).map(|(param, name)| {
+        if param.repeated {
+          quote! {
+            #name
+              .iter()
+              .filter_map(|opt| #cmd_context.options.iter().find(|o| o.name == opt.name))
+              .map(|o| CommandOptionTrait::extract(Some(o)))
+              .collect::<Result<_>>()?
+          }
+        } else {
+          quote! {
+            CommandOptionTrait::extract({
+              #cmd_context.options.iter().find(|o| o.name == #name.name)
+            })?
+          }
+        }
+      });
+      quote! { #(let #cmd_option_names = #extract;)* }
+    }
+  };
+
+  let cmd_option_statics = cmd_options.iter().zip(&cmd_option_names_static).map(|(param, name)| {
+    let options = &param.options;
+    if param.repeated {
+      let n = options.len();
+      quote! { static #name: [CommandOption; #n] = [#(#options),*]; }
+    } else {
+      quote! { static #name: CommandOption = #(#options)*; }
+    }
+  });
+  let cmd_option_refs = cmd_options
+    .iter()
+    .zip(&cmd_option_names_static)
+    .flat_map(|(param, name)| match param.repeated {
+      true => (0..param.options.len()).map(|i| quote! { &#name[#i] }).collect(),
+      false => vec![quote! { &#name }],
+    });
+
   let command = quote! {
     #fn_async fn #fn_inner_ident(#fn_inputs) #fn_output #fn_block
 
     #fn_vis fn #fn_ident(name: &'static str) -> crate::client::Command {
       use crate::client::*;
 
-      #(static #cmd_option_names_static: CommandOption = #cmd_options;)*
+      #(#cmd_option_statics)*
 
       fn run<'a>(#cmd_context: &'a Context<'_>) -> RunFuture<'a> {
         Box::pin(async {
-          #(let #cmd_option_names = CommandOptionTrait::extract({
-            #cmd_context.options.iter().find(|o| o.name == #cmd_option_names_static.name)
-          });)*
+          #cmd_extract
           #fn_inner_ident(#cmd_context, #(#cmd_option_names),*).await
         })
       }
 
       Command {
         name,
+        kind: #cmd_kind,
         description: #cmd_desc,
+        localizations: #cmd_l10n,
         owner_only: #cmd_owner_only,
+        permissions: #cmd_permissions,
+        guild_only: #cmd_guild_only,
+        nsfw: #cmd_nsfw,
+        registration: #cmd_registration,
+        requires: #cmd_requires,
+        ratelimits: #cmd_ratelimits,
 
         run,
-        options: [#(&#cmd_option_names_static),*].into(),
+        options: [#(#cmd_option_refs),*].into(),
       }
     }
   };
@@ -98,7 +215,73 @@ pub fn expand(args: TokenStream, input: TokenStream) -> Result<TokenStream> {
   Ok(command.into())
 }
 
-fn command_options(function: &mut ItemFn) -> Result<Vec<proc_macro2::TokenStream>> {
+fn registration(register: Option<LitStr>) -> Result<Expr> {
+  match register.as_ref().map(LitStr::value).as_deref() {
+    Some("global") | None => Ok(parse_quote! { Registration::Global }),
+    Some("guild") => Ok(parse_quote! { Registration::Guild }),
+    Some("dev") => Ok(parse_quote! { Registration::Dev }),
+    Some(_) => {
+      let msg = "expected one of: `global`, `guild`, `dev`";
+      Err(darling::Error::custom(msg).with_span(&register))
+    }
+  }
+}
+
+// comma separated names of `serenity::Permissions` flags, in any case (e.g.: "manage_guild, ban_members")
+fn permissions(permissions: Option<LitStr>) -> Result<Expr> {
+  let Some(permissions) = permissions else {
+    return Ok(parse_quote! { None });
+  };
+
+  let value = permissions.value();
+  let flags = value.split(',').map(str::trim).map(|name| {
+    if name.is_empty() || !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
+      let msg = format!("expected a permission name, got `{name}`");
+      return Err(darling::Error::custom(msg).with_span(&permissions));
+    }
+    // unknown ones are reported by the compiler, pointing at the string
+    Ok(format_ident!("{}", name.to_uppercase(), span = permissions.span()))
+  });
+  let flags = flags.collect::<Result<Vec<_>>>()?;
+
+  Ok(parse_quote! {
+    Some(::serenity::all::Permissions::from_bits_truncate(
+      0 #(| ::serenity::all::Permissions::#flags.bits())*
+    ))
+  })
+}
+
+fn requires(requires: Option<LitStr>) -> Result<Expr> {
+  match requires.as_ref().map(LitStr::value).as_deref() {
+    None => Ok(parse_quote! { None }),
+    Some("deezer") => Ok(parse_quote! { Some(Integration::Deezer) }),
+    Some("spotify") => Ok(parse_quote! { Some(Integration::Spotify) }),
+    Some("imgur") => Ok(parse_quote! { Some(Integration::Imgur) }),
+    Some("omdb") => Ok(parse_quote! { Some(Integration::Omdb) }),
+    Some("openweathermap") => Ok(parse_quote! { Some(Integration::OpenWeatherMap) }),
+    Some(_) => {
+      let msg = "expected one of: `deezer`, `spotify`, `imgur`, `omdb`, `openweathermap`";
+      Err(darling::Error::custom(msg).with_span(&requires))
+    }
+  }
+}
+
+// context menu commands get invoked on a message or a user, and can't have any options
+fn context_menu_target(function: &ItemFn, context_menu: bool) -> Result<Option<Type>> {
+  if !context_menu {
+    return Ok(None);
+  }
+
+  match function.sig.inputs.iter().collect::<Vec<_>>()[..] {
+    [_, FnArg::Typed(target)] => Ok(Some((*target.ty).clone())),
+    _ => {
+      let msg = "context menu commands take exactly one argument besides the context: a message or a user";
+      Err(darling::Error::custom(msg).with_span(&function.sig))
+    }
+  }
+}
+
+fn command_options(function: &mut ItemFn) -> Result<Vec<ParamOptions>> {
   let inputs = function.sig.inputs.iter_mut().skip(1);
 
   inputs
@@ -118,28 +301,143 @@ fn command_options(function: &mut ItemFn) -> Result<Vec<proc_macro2::TokenStream
         Pat::Ident(ident) => ident.ident.to_string(),
         _ => unimplemented!(),
       });
+      let desc_len = limits::DESCRIPTION_LEN;
+      limits::len("descriptions", fn_input_args.desc.as_deref(), desc_len, &fn_input.pat)?;
+      let lengths = [fn_input_args.min_length, fn_input_args.max_length];
+      if lengths.into_iter().flatten().any(|n| n > limits::STRING_LEN) {
+        let msg = format!("lengths can't be greater than {}", limits::STRING_LEN);
+        return Err(darling::Error::custom(msg).with_span(&fn_input.pat));
+      }
       let desc = util::option(fn_input_args.desc);
+      let localized = fn_input_args.l10n.is_some();
+      let l10n = l10n::expand(fn_input_args.l10n, false)?;
       let min = min_max(fn_input_args.min);
       let max = min_max(fn_input_args.max);
-      let ty = &*fn_input.ty;
-
-      let option = quote! {
-        CommandOption {
-          name: #name,
-          description: #desc,
-          min: #min,
-          max: #max,
-          choices: <#ty as CommandOptionTrait>::CHOICES,
-          required: <#ty as CommandOptionTrait>::REQUIRED,
-          ty: <#ty as CommandOptionTrait>::TYPE,
+      let min_length = util::option(fn_input_args.min_length);
+      let max_length = util::option(fn_input_args.max_length);
+      let channel_types = channel_types(fn_input_args.channel_types)?;
+      let autocomplete = autocomplete(fn_input_args.autocomplete);
+
+      let option = |name: &str, ty: &Type, required: Expr| {
+        quote! {
+          CommandOption {
+            name: #name,
+            description: #desc,
+            localizations: #l10n,
+            min: #min,
+            max: #max,
+            min_length: #min_length,
+            max_length: #max_length,
+            channel_types: #channel_types,
+            choices: <#ty as CommandOptionTrait>::CHOICES,
+            required: #required,
+            ty: <#ty as CommandOptionTrait>::TYPE,
+            autocomplete: #autocomplete,
+          }
+        }
+      };
+
+      // discord has no lists, so `Vec<T>` becomes `name1`, `name2`, etc., only the first one is required
+      let Some(item) = vec_item(&fn_input.ty) else {
+        if fn_input_args.repeat.is_some() {
+          let msg = "`repeat` only works with `Vec<T>`";
+          return Err(darling::Error::custom(msg).with_span(&fn_input.ty));
         }
+        limits::name(&name, &fn_input.pat)?;
+        let ty = &*fn_input.ty;
+        let required = parse_quote! { <#ty as CommandOptionTrait>::REQUIRED };
+        let options = vec![option(&name, ty, required)];
+        return Ok(ParamOptions {
+          options,
+          repeated: false,
+        });
       };
 
-      Ok(option)
+      if localized {
+        let msg = "repeated options can't be localized, their names would clash";
+        return Err(darling::Error::custom(msg).with_span(&fn_input.ty));
+      }
+      let repeat = fn_input_args.repeat.unwrap_or(DEFAULT_REPEAT);
+      if repeat == 0 {
+        return Err(darling::Error::custom("`repeat` can't be zero").with_span(&fn_input.ty));
+      }
+      let options = (1..=repeat).map(|i| {
+        let name = format!("{name}{i}");
+        limits::name(&name, &fn_input.pat)?;
+        let required = match i {
+          1 => parse_quote! { <#item as CommandOptionTrait>::REQUIRED },
+          _ => parse_quote! { false },
+        };
+        Ok(option(&name, item, required))
+      });
+      let options = options.collect::<Result<_>>()?;
+      Ok(ParamOptions {
+        options,
+        repeated: true,
+      })
     })
     .collect()
 }
 
+fn vec_item(ty: &Type) -> Option<&Type> {
+  let Type::Path(path) = ty else {
+    return None;
+  };
+  let segment = path.path.segments.last().filter(|s| s.ident == "Vec")?;
+  let PathArguments::AngleBracketed(args) = &segment.arguments else {
+    return None;
+  };
+  match args.args.iter().collect::<Vec<_>>()[..] {
+    [GenericArgument::Type(item)] => Some(item),
+    _ => None,
+  }
+}
+
+// names as in `ChannelType::name`, comma separated, e.g. `channel_types = "text, news"`
+fn channel_types(types: Option<LitStr>) -> Result<Expr> {
+  let Some(types) = types else {
+    return Ok(parse_quote! { &[] });
+  };
+
+  let value = types.value();
+  let variants = value.split(',').map(str::trim).map(|name| {
+    let variant = match name {
+      "text" => "Text",
+      "private" => "Private",
+      "voice" => "Voice",
+      "group_dm" => "GroupDm",
+      "category" => "Category",
+      "news" => "News",
+      "news_thread" => "NewsThread",
+      "public_thread" => "PublicThread",
+      "private_thread" => "PrivateThread",
+      "stage" => "Stage",
+      "directory" => "Directory",
+      "forum" => "Forum",
+      _ => {
+        let msg = format!("unknown channel type `{name}`");
+        return Err(darling::Error::custom(msg).with_span(&types));
+      }
+    };
+    Ok(format_ident!("{}", variant))
+  });
+  let variants = variants.collect::<Result<Vec<_>>>()?;
+
+  Ok(parse_quote! { &[#(::serenity::all::ChannelType::#variants),*] })
+}
+
+fn autocomplete(path: Option<Path>) -> Expr {
+  match path {
+    Some(path) => parse_quote! {{
+      fn autocomplete<'a>(ctx: &'a Context<'_>, value: &'a str) -> AutocompleteFuture<'a> {
+        Box::pin(#path(ctx, value))
+      }
+      Some(autocomplete as AutocompleteFn)
+    }},
+    None => parse_quote! { None },
+  }
+}
+
 fn min_max(lit: Option<Lit>) -> Expr {
   match lit {
     Some(Lit::Int(lit)) => parse_quote! { Some({ let n: i64 = #lit; n as f64 }) },
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use darling::{Error, FromMeta, Result};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Ident;
+
+use crate::{limits, util};
+
+// https://discord.com/developers/docs/reference#locales
+static LOCALES: &[&str] = &[
+  "id", "da", "de", "en-GB", "en-US", "es-ES", "es-419", "fr", "hr", "it", "lt", "hu", "nl", "no", "pl", "pt-BR", "ro",
+  "fi", "sv-SE", "vi", "tr", "cs", "el", "bg", "ru", "uk", "hi", "th", "zh-CN", "ja", "zh-TW", "ko",
+];
+
+#[derive(Debug, FromMeta)]
+pub struct Localization {
+  name: Option<String>,
+  desc: Option<String>,
+}
+
+pub type Localizations = HashMap<Ident, Localization>;
+
+// locales are written as idents with underscores, e.g. `l10n(ru(..), pt_BR(..))`,
+// localized names follow the same rules as the names themselves, which context menus don't have
+pub fn expand(l10n: Option<Localizations>, context_menu: bool) -> Result<TokenStream> {
+  let mut l10n = l10n.unwrap_or_default().into_iter().collect::<Vec<_>>();
+  l10n.sort_unstable_by_key(|(ident, _)| ident.to_string());
+
+  let mut errors = Error::accumulator();
+  let localizations = l10n.into_iter().filter_map(|(ident, l10n)| {
+    let locale = ident.to_string().replace('_', "-");
+    if !LOCALES.contains(&&*locale) {
+      let msg = format!("unknown locale `{locale}`, expected one of: {}", LOCALES.join(", "));
+      errors.push(Error::custom(msg).with_span(&ident));
+      return None;
+    }
+
+    let name_len = l10n.name.as_ref().map(|name| name.chars().count());
+    match &l10n.name {
+      Some(name) if !context_menu => {
+        if let Err(err) = limits::name(name, &ident) {
+          errors.push(err);
+        }
+      }
+      _ if name_len.is_some_and(|n| n == 0 || n > limits::NAME_LEN) => {
+        let msg = format!("names have to be from 1 to {} characters long", limits::NAME_LEN);
+        errors.push(Error::custom(msg).with_span(&ident));
+      }
+      _ => {}
+    }
+    let desc_len = limits::DESCRIPTION_LEN;
+    if let Err(err) = limits::len("descriptions", l10n.desc.as_deref(), desc_len, &ident) {
+      errors.push(err);
+    }
+
+    let name = util::option(l10n.name);
+    let desc = util::option(l10n.desc);
+    Some(quote! {
+      Localization {
+        locale: #locale,
+        name: #name,
+        description: #desc,
+      }
+    })
+  });
+  let localizations = localizations.collect::<Vec<_>>();
+  errors.finish()?;
+
+  Ok(quote! { &[#(#localizations),*] })
+}
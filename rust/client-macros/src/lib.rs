@@ -2,6 +2,9 @@ use proc_macro::TokenStream;
 
 mod choice;
 mod command;
+mod l10n;
+mod limits;
+mod ratelimit;
 mod util;
 
 /// # Examples
@@ -21,6 +24,13 @@ mod util;
 /// ) -> Result<()> {
 ///   todo!()
 /// }
+///
+/// // only on servers, and for members who can ban (`nsfw` only lets it run in NSFW channels),
+/// // discord enforces these for top-level commands, they're checked again on every invocation
+/// #[command(desc = "Bans someone", guild_only, permissions = "ban_members")]
+/// async fn ban(ctx: &Context<'_>, user: &User) -> Result<()> {
+///   todo!()
+/// }
 /// ```
 #[proc_macro_attribute]
 pub fn command(args: TokenStream, input: TokenStream) -> TokenStream {
@@ -48,8 +58,33 @@ pub fn command(args: TokenStream, input: TokenStream) -> TokenStream {
 /// async fn math_op(ctx: &Context<'_>, op: Op, x: f64, y: f64) -> Result<()> {
 ///   todo!()
 /// }
+///
+/// // integer (or float, or string) values, `#[default]` makes the option optional
+/// #[derive(macros::Choice)]
+/// enum Precision {
+///   #[name = "whole numbers"]
+///   #[value = 0]
+///   None,
+///   #[name = "2 decimal places"]
+///   #[value = 2]
+///   #[default]
+///   Two,
+/// }
+///
+/// // `Vec<T>` is registered as `image1`, `image2`, `image3`
+/// #[command]
+/// async fn collage(
+///   ctx: &Context<'_>,
+///   #[repeat = 3] image: Vec<&Attachment>,
+///   #[channel_types = "text, news"] channel: Option<&PartialChannel>,
+///   #[min_length = 1]
+///   #[max_length = 100]
+///   caption: Option<&str>,
+/// ) -> Result<()> {
+///   todo!()
+/// }
 /// ```
-#[proc_macro_derive(Choice, attributes(name))]
+#[proc_macro_derive(Choice, attributes(name, value, default))]
 pub fn choice(input: TokenStream) -> TokenStream {
   choice::expand(input).unwrap_or_else(|e| e.write_errors().into())
 }
@@ -0,0 +1,31 @@
+use darling::{Error, Result};
+use quote::ToTokens;
+
+pub use discord::limits::*;
+
+// `client::validate_commands` checks the whole tree once it's built,
+// what can be checked here is too, so that errors point at the code that breaks them
+
+// of options, lowercase where there's a lowercase
+pub fn name(name: &str, span: impl ToTokens) -> Result<()> {
+  if name.is_empty() || name.chars().count() > NAME_LEN {
+    let msg = format!("`{name}`: names have to be from 1 to {NAME_LEN} characters long");
+    return Err(Error::custom(msg).with_span(&span));
+  }
+  let valid = |c: char| (c.is_alphanumeric() || c == '-' || c == '_') && !c.is_uppercase();
+  if !name.chars().all(valid) {
+    let msg = format!("`{name}`: names can only have lowercase letters, digits, `-` and `_`");
+    return Err(Error::custom(msg).with_span(&span));
+  }
+  Ok(())
+}
+
+pub fn len(what: &str, value: Option<&str>, max: usize, span: impl ToTokens) -> Result<()> {
+  match value {
+    Some(value) if value.chars().count() > max => {
+      let msg = format!("{what} can't be longer than {max} characters");
+      Err(Error::custom(msg).with_span(&span))
+    }
+    _ => Ok(()),
+  }
+}
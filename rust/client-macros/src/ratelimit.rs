@@ -0,0 +1,120 @@
+use std::result;
+
+use darling::{Error, Result};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Lit, LitStr};
+
+#[rustfmt::skip]
+static UNITS: &[(&[&str], u64)] = &[
+  (&["ns", "nanos"],                            1),
+  (&["us", "micros"],                       1_000),
+  (&["ms", "millis"],                   1_000_000),
+  (&["s", "sec", "secs"],           1_000_000_000),
+  (&["m", "min", "mins"],          60_000_000_000),
+  (&["h", "hour", "hours"],     3_600_000_000_000),
+  (&["d", "day", "days"],      86_400_000_000_000),
+  (&["w", "week", "weeks"],   604_800_000_000_000),
+];
+
+pub fn expand(rate: Option<LitStr>, cost_lit: Option<Lit>, scope: Option<LitStr>) -> Result<Option<TokenStream>> {
+  let Some(rate) = rate else {
+    return match (cost_lit, scope) {
+      (Some(cost), _) => Err(Error::custom("`cost` requires `rate`").with_span(&cost)),
+      (_, Some(scope)) => Err(Error::custom("`scope` requires `rate`").with_span(&scope)),
+      (None, None) => Ok(None),
+    };
+  };
+
+  let (quota, period) = parse_rate(&rate.value()).map_err(|e| Error::custom(e).with_span(&rate))?;
+
+  let cost = match &cost_lit {
+    Some(Lit::Int(lit)) => lit.base10_parse::<f64>()?,
+    Some(Lit::Float(lit)) => lit.base10_parse::<f64>()?,
+    Some(lit) => return Err(Error::unexpected_lit_type(lit)),
+    None => 1.0,
+  };
+
+  if !(cost > 0.0 && cost <= quota) {
+    let msg = format!("`cost` must be positive and not exceed the quota ({quota})");
+    return Err(Error::custom(msg).with_span(&cost_lit));
+  }
+
+  let scope = match scope.as_ref().map(LitStr::value).as_deref() {
+    Some("global") => quote! { Scope::Global },
+    Some("guild") => quote! { Scope::Guild },
+    Some("user") | None => quote! { Scope::User },
+    Some(_) => {
+      let msg = "expected one of: `global`, `guild`, `user`";
+      return Err(Error::custom(msg).with_span(&scope));
+    }
+  };
+
+  let ratelimit = quote! {
+    Ratelimit::new(#scope, ::gcra::Rate::new(#quota, ::std::time::Duration::from_nanos(#period))).cost(#cost)
+  };
+
+  Ok(Some(ratelimit))
+}
+
+// parses strings like `5/min`, `10/15m`, `0.5/h` into quota and period (ns)
+fn parse_rate(input: &str) -> result::Result<(f64, u64), String> {
+  let (quota, period) = input
+    .split_once('/')
+    .ok_or_else(|| format!("expected `<quota>/<period>` (e.g. `5/min`), got `{input}`"))?;
+
+  let quota = match quota.trim().parse::<f64>() {
+    Ok(n) if n.is_finite() && n > 0.0 => n,
+    _ => return Err(format!("invalid quota `{quota}`, should be a positive number")),
+  };
+
+  let period = period.trim();
+  let split = period.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(period.len());
+  let (n, unit) = period.split_at(split);
+
+  let n = match n.trim() {
+    "" => 1,
+    n => match n.parse::<u64>() {
+      Ok(n) if n > 0 => n,
+      _ => return Err(format!("invalid period `{period}`, should be a positive integer")),
+    },
+  };
+
+  let Some(&(_, ns)) = UNITS.iter().find(|(names, _)| names.contains(&unit)) else {
+    return Err(format!("unknown time unit `{unit}`"));
+  };
+
+  let period = n
+    .checked_mul(ns)
+    .ok_or_else(|| format!("period `{period}` is too long"))?;
+
+  Ok((quota, period))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn valid() {
+    assert_eq!(parse_rate("5/min"), Ok((5.0, 60_000_000_000)));
+    assert_eq!(parse_rate("5/10m"), Ok((5.0, 600_000_000_000)));
+    assert_eq!(parse_rate("0.5 / 2h"), Ok((0.5, 7_200_000_000_000)));
+    assert_eq!(parse_rate("100/day"), Ok((100.0, 86_400_000_000_000)));
+    assert_eq!(parse_rate("1/500ms"), Ok((1.0, 500_000_000)));
+  }
+
+  #[test]
+  fn invalid() {
+    assert!(parse_rate("5").is_err());
+    assert!(parse_rate("5/").is_err());
+    assert!(parse_rate("0/min").is_err());
+    assert!(parse_rate("-1/min").is_err());
+    assert!(parse_rate("inf/min").is_err());
+    assert!(parse_rate("five/min").is_err());
+    assert!(parse_rate("5/0min").is_err());
+    assert!(parse_rate("5/1.5min").is_err());
+    assert!(parse_rate("5/fortnight").is_err());
+    assert!(parse_rate("5/99999999999w").is_err());
+  }
+}
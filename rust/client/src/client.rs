@@ -1,33 +1,64 @@
+use std::future::Future;
 use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::{error, result};
 
 use ::serenity::all as serenity;
+use ::util::network::{Cache, Http};
 use cache::LruFileCache;
 use discord::colors;
-use futures::{FutureExt, TryFutureExt};
-use pyo3::{PyErr, Python};
+use futures::{future, FutureExt, TryFutureExt};
+use pyo3::PyErr;
+use python::gil;
 use tokio::signal::{self, unix::*};
 
-use crate::commands::tree as commands;
+use crate::commands::{components, tree as commands};
 use crate::db;
 
+pub use self::access::{denial, Invoker, Owners};
 pub use self::command::*;
 pub use self::command_error::*;
-pub use self::commands::{CommandTree, Commands};
+pub use self::commands::{walk, CommandTree, Commands};
+pub use self::config::{Config, Integration};
 pub use self::context::*;
-pub use self::env::*;
+pub use self::docs::{details, notes, to_json, to_markdown, type_name};
+pub use self::from_option::FromOption;
+pub use self::limits::{validate_commands, validate_name};
+pub use self::metrics::Metrics;
+pub use self::queue::{Job, Queue, Stats as QueueStats};
+pub use self::ratelimit::{Ratelimit, Scope};
+pub use self::reply::Reply;
+pub use self::router::{Component, ComponentFn, ComponentId, Components};
+pub use self::shutdown::Shutdown;
 pub use self::traits::*;
 
 pub(crate) use self::commands::commands;
+pub(crate) use self::l10n::tr;
+pub(crate) use self::router::components;
 
+mod access;
 mod command;
 mod command_error;
 mod commands;
+mod config;
 mod context;
-mod env;
+mod docs;
+mod from_option;
+mod l10n;
+mod limits;
+mod metrics;
+mod queue;
+mod ratelimit;
+mod registration;
+mod reply;
+mod router;
+mod shutdown;
+#[cfg(test)]
+pub mod testing;
+mod text;
 mod traits;
+mod unfurl;
 mod util;
 
 pub type Error = Box<dyn error::Error + Send + Sync>;
@@ -38,39 +69,96 @@ pub type Result<T> = result::Result<T, Error>;
 
 #[derive(Debug)]
 pub struct Client {
-  pub env: Env,
+  pub config: Arc<Config>,
+  pub http: Http,
   pub commands: Commands,
+  pub components: Components,
+  pub queue: Queue,
+  pub shutdown: Arc<Shutdown>,
   pub cache: Arc<LruFileCache>,
   pub db: db::Pool,
+  pub metrics: Arc<Metrics>,
+  pub owners: Owners,
 }
 
 impl Client {
   pub async fn start() -> Result<()> {
     c::fontconfig::add_dir("assets/fonts")?;
 
-    let env = Env::load();
-    let db = db::init(&env.database_url).await?;
+    let config = Config::load().inspect_err(|err| tracing::error!("{err}"))?;
+    let config = Arc::new(config);
+
+    if let Some(arl) = &config.deezer_arl {
+      let spotify = config
+        .spotify_app_id
+        .as_deref()
+        .zip(config.spotify_app_secret.as_deref());
+      python::lib::dz::configure(arl, spotify)?;
+    }
+
+    let db = db::init(&config.database_url).await?;
     let cache = {
-      let base_url = env.cache_base_url.clone();
-      let working_dir = env.cache_working_dir.clone();
-      let limit_bytes = env.cache_limit_GiB << 30;
+      let base_url = config.cache_base_url.clone();
+      let working_dir = config.cache_working_dir.clone();
+      let limit_bytes = config.settings().cache_limit_GiB << 30;
       let cache = LruFileCache::new(base_url, working_dir, limit_bytes);
       Arc::new(cache.await?)
     };
 
+    let http = {
+      // only the last ones are kept in memory, the rest are read back from the database
+      let store = Arc::new(db::responses::Store(db.clone()));
+      let cache = Cache::new(1024).store(store);
+      Http::new()
+        // tikwm fetches the whole video from tiktok before it answers
+        .timeout("tikwm.com", Duration::from_secs(60))
+        .cache(cache)
+    };
+
+    // discord would reject them one by one when they're registered
+    let commands = commands();
+    let invalid = validate_commands(&commands);
+    if !invalid.is_empty() {
+      let err = format!("invalid commands:\n{}", invalid.join("\n"));
+      tracing::error!("{err}");
+      return Err(err.into());
+    }
+
     let client = Self {
-      env,
-      commands: commands(),
+      config: config.clone(),
+      http,
+      commands,
+      components: components(),
+      queue: Queue::new(),
+      shutdown: Arc::new(Shutdown::new()),
       cache: cache.clone(),
       db,
+      metrics: Arc::new(Metrics::default()),
+      owners: Owners::default(),
     };
 
+    let shutdown = client.shutdown.clone();
+    let (metrics, http) = (client.metrics.clone(), client.http.clone());
+
     let intents = serenity::GatewayIntents::all();
-    let mut client = serenity::Client::builder(&client.env.discord_token, intents)
+    let mut client = serenity::Client::builder(&client.config.discord_token, intents)
       .raw_event_handler(client)
       .await?;
 
+    let reload = Self::reload(config.clone(), cache.clone());
+
     let shard_manager = client.shard_manager.clone();
+    let metrics = match config.metrics_addr {
+      Some(addr) => {
+        let sources = metrics::Sources {
+          cache: cache.clone(),
+          http,
+          shard_manager: shard_manager.clone(),
+        };
+        metrics::serve(addr, metrics, sources).boxed()
+      }
+      None => future::pending().boxed(),
+    };
     let exit = async move {
       let mut sigint = signal(SignalKind::interrupt())?;
       let mut sigterm = signal(SignalKind::terminate())?;
@@ -82,6 +170,8 @@ impl Client {
         r = signal::ctrl_c() => r?,
       }
 
+      shutdown.drain(config.settings().shutdown_deadline).await;
+
       let shutdown = shard_manager.shutdown_all();
       let shutdown = tokio::time::timeout(Duration::from_secs(5), shutdown);
       let sleep = tokio::time::sleep(Duration::from_secs(5));
@@ -101,65 +191,194 @@ impl Client {
       biased;
       r = client.start() => r?,
       r = cache.watch() => r?,
+      r = reload => r?,
+      r = metrics => r?,
       r = exit => r?,
     }
 
     Ok(())
   }
 
-  #[tracing::instrument(name="cmd", skip_all, fields(id=%ctx.id))]
-  async fn handle_command(&self, ctx: Context<'_>) -> serenity::Result<()> {
-    let start = Instant::now();
+  // settings can be changed without a restart, everything else is only read on startup
+  async fn reload(config: Arc<Config>, cache: Arc<LruFileCache>) -> Result<()> {
+    let mut sighup = signal(SignalKind::hangup())?;
+    loop {
+      sighup.recv().await;
+      match config.reload() {
+        Ok(settings) => {
+          cache.set_bytes_limit(settings.cache_limit_GiB << 30);
+          tracing::info!(?settings, "config reloaded");
+          let pinned = config::Settings::pinned();
+          if !pinned.is_empty() {
+            tracing::warn!(?pinned, "set by env vars, those can only change with a restart");
+          }
+        }
+        Err(err) => tracing::error!("{err}, keeping the old settings"),
+      }
+    }
+  }
 
+  #[tracing::instrument(
+    name = "cmd",
+    skip_all,
+    fields(
+      id = %ctx.id,
+      path = %util::CommandPathDisplay(&ctx.event.data),
+      user = %ctx.event.user.id,
+      guild = ctx.event.guild_id.map(|id| id.get()),
+    )
+  )]
+  async fn handle_command(&self, ctx: Context<'_>) -> serenity::Result<()> {
     self.log_command(&ctx).await;
 
     let run = async {
-      if ctx.command.owner_only {
-        let info = ctx.serenity.http.get_current_application_info().await?;
-        if ctx.event.user.id != info.owner.unwrap().id {
-          err::message!("this command is owner-only");
-        }
+      let path = util::CommandPathDisplay(&ctx.event.data).to_string();
+      if !self.is_command_enabled(ctx.event.guild_id, &path, ctx.command).await? {
+        err::message!("{}", tr!(ctx, "this command is disabled on this server"));
       }
 
+      access::check(&ctx, &path).await?;
+
+      ratelimit::check(&ctx).await?;
+
       (ctx.command.run)(&ctx).await
     };
 
-    match AssertUnwindSafe(run).catch_unwind().await {
+    self.handle_result(&ctx, run).await
+  }
+
+  // continues a flow that has been started by a command, so it's reported the same way
+  #[tracing::instrument(
+    name = "cmp",
+    skip_all,
+    fields(
+      id = %ctx.id,
+      path = %util::CommandPathDisplay(&ctx.event.data),
+      user = %ctx.event.user.id,
+      guild = ctx.event.guild_id.map(|id| id.get()),
+    )
+  )]
+  async fn handle_component(&self, ctx: Context<'_>, run: ComponentFn, component: Component) -> serenity::Result<()> {
+    let path = util::CommandPathDisplay(&ctx.event.data);
+    tracing::info!(key=%component.key, "{}", path);
+    tracing::debug!(user=%ctx.event.user.id, tag=%ctx.event.user.tag());
+
+    self.handle_result(&ctx, run(&ctx, &component)).await
+  }
+
+  async fn handle_result(&self, ctx: &Context<'_>, run: impl Future<Output = Result<()>>) -> serenity::Result<()> {
+    let start = Instant::now();
+
+    // tracked before checking, so that a drain starting in between waits for this one
+    let _running = self.shutdown.track();
+    if self.shutdown.is_closing() {
+      tracing::info!("rejected, shutting down");
+      return self.report_shutdown(ctx).await;
+    }
+
+    let result = tokio::select! {
+      result = AssertUnwindSafe(run).catch_unwind() => result,
+      _ = self.shutdown.expired() => {
+        tracing::info!("interrupted, shutting down");
+        return self.report_shutdown(ctx).await;
+      }
+    };
+
+    match result {
       Ok(Ok(_)) => {}
       Ok(Err(err)) => {
-        if let Some(err) = err.downcast_ref::<PyErr>() {
-          Python::with_gil(|py| err.print(py));
-        }
+        let traceback = err.downcast_ref::<PyErr>().map(|err| {
+          gil::with_gil(|py| err.print(py));
+          util::python_traceback(err)
+        });
         match err.downcast() {
           Ok(err) => match &*err {
-            CommandError::Timeout => {
-              tracing::info!("timeout");
-              ctx.event.delete_response(&ctx).await?;
-            }
             CommandError::Message(msg) => {
               tracing::info!(%msg, "error");
-              self.report_error(&ctx, Some(msg)).await?;
+              self.metrics.error("message");
+              let error = db::errors::Error {
+                display: Some(msg.clone()),
+                ..Default::default()
+              };
+              self.log_error(ctx, error).await;
+              self.report_error(ctx, Some(msg)).await?;
+            }
+            CommandError::Ratelimit { after, remaining } => {
+              tracing::info!(?after, "rate limited");
+              self.metrics.error("ratelimit");
+              self.report_ratelimit(ctx, *after, *remaining).await?;
             }
           },
           Err(err) => {
             tracing::error!(display=%err, "error");
             tracing::error!(debug=?err, "error");
-            self.report_error(&ctx, None).await?;
+            self
+              .metrics
+              .error(if traceback.is_some() { "python" } else { "internal" });
+            let error = db::errors::Error {
+              display: Some(err.to_string()),
+              debug: Some(format!("{:?}", err)),
+              traceback,
+              ..Default::default()
+            };
+            self.log_error(ctx, error).await;
+            self.report_error(ctx, None).await?;
           }
         }
       }
       Err(panic) => {
         let panic = util::panic_message(panic);
         tracing::error!(%panic, "panic");
-        self.report_error(&ctx, None).await?;
+        self.metrics.error("panic");
+        let error = db::errors::Error {
+          panic: Some(panic.into_owned()),
+          ..Default::default()
+        };
+        self.log_error(ctx, error).await;
+        self.report_error(ctx, None).await?;
       }
     }
 
+    let path = util::CommandPathDisplay(&ctx.event.data).to_string();
+    self.metrics.command(&path, start.elapsed());
     tracing::debug!("finished ({:.3?})", start.elapsed());
 
     Ok(())
   }
 
+  #[tracing::instrument(name="ac", skip_all, fields(id=%ctx.id))]
+  async fn handle_autocomplete(&self, ctx: Context<'_>) -> serenity::Result<()> {
+    let Some(focused) = ctx.event.data.autocomplete() else {
+      return Ok(());
+    };
+
+    let option = ctx.command.options.iter().find(|o| o.name == focused.name);
+    let Some(autocomplete) = option.and_then(|o| o.autocomplete) else {
+      return Ok(());
+    };
+
+    // an empty list is still a valid response, so errors don't get reported any further
+    let choices = match AssertUnwindSafe(autocomplete(&ctx, focused.value)).catch_unwind().await {
+      Ok(Ok(choices)) => choices,
+      Ok(Err(err)) => {
+        tracing::warn!(display=%err, "error");
+        Vec::new()
+      }
+      Err(panic) => {
+        let panic = util::panic_message(panic);
+        tracing::error!(%panic, "panic");
+        Vec::new()
+      }
+    };
+    tracing::trace!("{} choices for {:?}", choices.len(), focused.value);
+
+    // discord doesn't accept more than 25 choices
+    let choices = choices.into_iter().take(25).collect();
+    let response = serenity::CreateAutocompleteResponse::new().set_choices(choices);
+    let response = serenity::CreateInteractionResponse::Autocomplete(response);
+    ctx.event.create_response(&ctx, response).await
+  }
+
   async fn log_command(&self, ctx: &Context<'_>) {
     tracing::info!("{}", util::SlashCommandDisplay(&ctx.event.data));
 
@@ -179,6 +398,17 @@ impl Client {
     }
   }
 
+  // failing to store the error shouldn't stop it from being reported to the user
+  async fn log_error(&self, ctx: &Context<'_>, error: db::errors::Error) {
+    tracing::trace!("logging error");
+
+    let command = util::SlashCommandDisplay(&ctx.event.data).to_string();
+    let (user, guild) = (ctx.event.user.id, ctx.event.guild_id);
+    if let Err(err) = db::errors::insert(&self.db, ctx.id.0, &command, user, guild, &error).await {
+      tracing::warn!(display=%err, "failed to log error");
+    }
+  }
+
   async fn report_error(&self, ctx: &Context<'_>, msg: Option<&str>) -> serenity::Result<()> {
     tracing::trace!("reporting error");
 
@@ -186,51 +416,76 @@ impl Client {
     let default = || format!(concat!(
       "**OOPSIE WOOPSIE!!** Uwu We made a fucky wucky!! A wittle fucko boingo! ",
       "The code monkeys at our [headquarters]({}) are working VEWY HAWD to fix this!"
-    ), self.env.discord_dev_server_invite);
+    ), self.config.discord_dev_server_invite);
 
     let embed = serenity::CreateEmbed::new()
       .color(colors::ERROR.light)
-      .description(msg.map_or_else(default, |msg| format!("**Error:** {}", msg)))
+      .description(msg.map_or_else(default, |msg| format!("**{}:** {}", ctx.tr("Error"), msg)))
       .footer(serenity::CreateEmbedFooter::new(format!("ERROR ID {}", ctx.id)));
 
-    let create = || {
-      let embed = embed.clone();
-      let message = serenity::CreateInteractionResponseMessage::new()
-        .embed(embed)
-        .ephemeral(true);
-      let message = serenity::CreateInteractionResponse::Message(message);
-      ctx.event.create_response(ctx, message)
+    self.report(ctx, embed).await
+  }
+
+  async fn report_ratelimit(&self, ctx: &Context<'_>, after: Option<Duration>, remaining: f64) -> serenity::Result<()> {
+    tracing::trace!("reporting rate limit");
+
+    let desc = match after {
+      Some(after) => tr!(ctx, "Try again in {}", ratelimit::retry_after(after)),
+      None => tr!(ctx, "This is way too much for the rate limit"),
     };
+    let desc = format!("**{}** {}", ctx.tr("Slow down!"), desc);
 
-    let followup = || async {
-      // TODO: probably should delete followups as well, somehow
-      ctx.event.delete_response(ctx).await?;
+    let footer = tr!(ctx, "{} uses left", remaining as u64);
+    let embed = serenity::CreateEmbed::new()
+      .color(colors::WARN.light)
+      .description(desc)
+      .footer(serenity::CreateEmbedFooter::new(footer));
+
+    self.report(ctx, embed).await
+  }
+
+  async fn report_shutdown(&self, ctx: &Context<'_>) -> serenity::Result<()> {
+    tracing::trace!("reporting shutdown");
+
+    let desc = format!("**{}** {}", ctx.tr("Restarting!"), ctx.tr("try again in a minute"));
+    let embed = serenity::CreateEmbed::new().color(colors::WARN.light).description(desc);
+    let reply = Reply::new()
+      .content("")
+      .embed(embed)
+      .components(Vec::new())
+      .clear_attachments()
+      .ephemeral(true);
+
+    // unlike errors, this replaces whatever has been sent so far, e.g. a progress bar
+    let edit = || ctx.edit_reply(reply.clone()).map_ok(drop);
+    ctx.reply(reply.clone()).or_else(|_| edit()).await
+  }
 
-      let embed = embed.clone();
-      let message = serenity::CreateInteractionResponseFollowup::new()
-        .embed(embed)
-        .ephemeral(true);
-      ctx.event.create_followup(ctx, message).await?;
+  async fn report(&self, ctx: &Context<'_>, embed: serenity::CreateEmbed) -> serenity::Result<()> {
+    let reply = Reply::new().embed(embed).ephemeral(true);
 
+    let followup = || async {
+      // TODO: probably should delete followups as well, somehow
+      ctx.delete_reply().await?;
+      ctx.followup(reply.clone()).await?;
       Ok::<_, serenity::Error>(())
     };
 
-    create().or_else(|_| followup()).await?;
+    ctx.reply(reply.clone()).or_else(|_| followup()).await?;
 
     Ok(())
   }
 
-  async fn register_commands(&self, ctx: &serenity::Context) -> serenity::Result<()> {
-    let commands = commands::serialize(&self.commands);
-    let commands = serenity::Command::set_global_commands(ctx, commands).await?;
-    tracing::debug!("registered {} global commands", commands.len());
+  pub async fn is_command_enabled(&self, id: Option<serenity::GuildId>, path: &str, cmd: &Command) -> Result<bool> {
+    registration::enabled(self, id, path, cmd).await
+  }
 
-    let guild = self.env.discord_dev_server;
-    let commands = Default::default();
-    let commands = guild.set_commands(ctx, commands).await?;
-    tracing::debug!("registered {} guild-local commands", commands.len());
+  pub async fn enabled_commands(&self, id: Option<serenity::GuildId>) -> Result<Vec<(String, &Command)>> {
+    registration::all_enabled(self, id).await
+  }
 
-    Ok(())
+  pub async fn register_guild_commands(&self, http: &serenity::Http, id: serenity::GuildId) -> Result<()> {
+    registration::guild(self, http, id).await
   }
 
   async fn track_event(&self, event: &serenity::Event) -> Result<()> {
@@ -288,6 +543,9 @@ impl Client {
       Event::Ready(ReadyEvent { ready, .. }) => {
         self.on_ready(ctx, ready).await?;
       }
+      Event::GuildCreate(GuildCreateEvent { guild, .. }) => {
+        self.on_guild_create(ctx, guild).await?;
+      }
       Event::MessageCreate(MessageCreateEvent { message, .. }) => {
         self.on_message(ctx, message).await?;
       }
@@ -297,16 +555,44 @@ impl Client {
       }) => {
         self.on_command(ctx, command).await?;
       }
+      Event::InteractionCreate(InteractionCreateEvent {
+        interaction: Interaction::Autocomplete(autocomplete),
+        ..
+      }) => {
+        self.on_autocomplete(ctx, autocomplete).await?;
+      }
+      Event::InteractionCreate(InteractionCreateEvent {
+        interaction: Interaction::Component(component),
+        ..
+      }) => {
+        self.on_component(ctx, component).await?;
+      }
       _ => {}
     }
 
     Ok(())
   }
 
-  async fn on_ready(&self, ctx: &serenity::Context, ready: &serenity::Ready) -> serenity::Result<()> {
+  async fn on_ready(&self, ctx: &serenity::Context, ready: &serenity::Ready) -> Result<()> {
     let (r#as, id) = (ready.user.tag(), ready.user.id.get());
     tracing::info!(%r#as, id, "connected");
-    self.register_commands(ctx).await
+
+    // nobody is going to come back to a flow after this long
+    let pruned = db::components::prune(&self.db, "-7 days").await?;
+    tracing::debug!("pruned {} component states", pruned.rows_affected());
+    let pruned = db::responses::prune(&self.db).await?;
+    tracing::debug!("pruned {} cached responses", pruned.rows_affected());
+    // owner-only commands fall back to fetching them when they're used
+    if let Err(err) = self.owners.refresh(&ctx.http).await {
+      tracing::warn!(display=%err, debug=?err, "failed to fetch the owners");
+    }
+
+    registration::global(self, &ctx.http).await
+  }
+
+  // this also gets fired for every guild on startup, so per guild commands get registered here
+  async fn on_guild_create(&self, ctx: &serenity::Context, guild: &serenity::Guild) -> Result<()> {
+    self.register_guild_commands(&ctx.http, guild.id).await
   }
 
   async fn on_command(&self, ctx: &serenity::Context, cmd: &serenity::CommandInteraction) -> serenity::Result<()> {
@@ -314,9 +600,116 @@ impl Client {
     self.handle_command(ctx).await
   }
 
-  async fn on_message(&self, _ctx: &serenity::Context, _msg: &serenity::Message) -> Result<()> {
+  async fn on_autocomplete(&self, ctx: &serenity::Context, ac: &serenity::CommandInteraction) -> serenity::Result<()> {
+    let ctx = Context::new(self, ctx, ac);
+    self.handle_autocomplete(ctx).await
+  }
+
+  // components that aren't routed (like the cancel buttons) are handled by collectors instead
+  async fn on_component(&self, ctx: &serenity::Context, event: &serenity::ComponentInteraction) -> Result<()> {
+    let Some((route, id, key)) = router::parse(&event.data.custom_id) else {
+      return Ok(());
+    };
+    // anyone can click those, and they don't have any state
+    if route == unfurl::ROUTE {
+      return unfurl::on_click(self, ctx, event, id, key).await;
+    }
+    let Some(&run) = self.components.get(route) else {
+      return Ok(());
+    };
+
+    let row = match db::components::get(&self.db, id).await? {
+      Some(row) if row.user == event.user.id.get() as i64 => row,
+      Some(_) => return self.reject_component(ctx, event, "this is not for you").await,
+      None => {
+        return self
+          .reject_component(ctx, event, "this has expired, run the command again")
+          .await
+      }
+    };
+
+    let path = row.command.trim_start_matches('/').split(' ').collect::<Vec<_>>();
+    let command = text::interaction(&path, Vec::new(), serde_json::to_value(event)?)?;
+    let component = Component {
+      id,
+      key: key.to_owned(),
+      values: router::values(event),
+      state: serde_json::from_str(&row.state)?,
+    };
+
+    let ctx = Context::from_component(self, ctx, &command, event);
+    self.handle_component(ctx, run, component).await?;
+
     Ok(())
   }
+
+  async fn reject_component(
+    &self,
+    ctx: &serenity::Context,
+    event: &serenity::ComponentInteraction,
+    text: &'static str,
+  ) -> Result<()> {
+    let (error, text) = (
+      l10n::translate(&event.locale, "Error"),
+      l10n::translate(&event.locale, text),
+    );
+    let embed = serenity::CreateEmbed::new()
+      .color(colors::ERROR.light)
+      .description(format!("**{error}:** {text}"));
+    let msg = serenity::CreateInteractionResponseMessage::new()
+      .embed(embed)
+      .ephemeral(true);
+    event
+      .create_response(ctx, serenity::CreateInteractionResponse::Message(msg))
+      .await?;
+    Ok(())
+  }
+
+  async fn on_message(&self, ctx: &serenity::Context, msg: &serenity::Message) -> Result<()> {
+    // this also keeps me from reacting to my own messages
+    if msg.author.bot || self.shutdown.is_closing() {
+      return Ok(());
+    }
+
+    if let Some(prefix) = &self.config.settings().command_prefix {
+      if self.on_text_command(ctx, msg, prefix).await? {
+        return Ok(());
+      }
+    }
+
+    unfurl::offer(self, ctx, msg).await
+  }
+
+  // text commands (e.g. `r!weather London`) are optional and only work when a prefix is configured,
+  // returns whether the message was a command
+  async fn on_text_command(&self, ctx: &serenity::Context, msg: &serenity::Message, prefix: &str) -> Result<bool> {
+    let event = match text::parse(self, ctx, msg, prefix).await {
+      Ok(Some(event)) => event,
+      Ok(None) => return Ok(false),
+      Err(err) => match err.downcast::<CommandError>() {
+        Ok(err) => {
+          let CommandError::Message(text) = *err else {
+            return Err(err);
+          };
+          tracing::info!(%text, "failed to parse text command");
+          let locale = text::guild_locale(ctx, msg).unwrap_or_default();
+          let error = l10n::translate(&locale, "Error");
+          let embed = serenity::CreateEmbed::new()
+            .color(colors::ERROR.light)
+            .description(format!("**{error}:** {text}"));
+          let reply = serenity::CreateMessage::new().embed(embed).reference_message(msg);
+          msg.channel_id.send_message(ctx, reply).await?;
+          return Ok(true);
+        }
+        Err(err) => return Err(err),
+      },
+    };
+
+    let ctx = Context::from_message(self, ctx, &event, msg);
+    self.handle_command(ctx).await?;
+
+    Ok(true)
+  }
 }
 
 #[serenity::async_trait]
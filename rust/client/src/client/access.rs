@@ -0,0 +1,193 @@
+use std::collections::HashSet;
+
+use ::serenity::all as serenity;
+use parking_lot::RwLock;
+
+use super::{commands, err, tr, Command, Context, Result};
+use crate::db::guild_command_roles;
+
+// the owner of the application, or everyone on its team,
+// fetched once and then again on every ready, since teams can change
+#[derive(Debug, Default)]
+pub struct Owners(RwLock<Option<HashSet<serenity::UserId>>>);
+
+impl Owners {
+  pub async fn refresh(&self, http: &serenity::Http) -> Result<()> {
+    let info = http.get_current_application_info().await?;
+    let team = info.team.iter().flat_map(|team| &team.members);
+    let ids = info.owner.iter().map(|user| user.id);
+    let ids = ids.chain(team.map(|member| member.user.id)).collect();
+    *self.0.write() = Some(ids);
+    Ok(())
+  }
+
+  pub async fn contains(&self, http: &serenity::Http, id: serenity::UserId) -> Result<bool> {
+    if self.0.read().is_none() {
+      self.refresh(http).await?;
+    }
+    Ok(self.0.read().as_ref().is_some_and(|ids| ids.contains(&id)))
+  }
+}
+
+// why someone can't use a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Denial {
+  OwnerOnly,
+  GuildOnly,
+  Permissions(serenity::Permissions),
+  Nsfw,
+  Roles,
+}
+
+// who is asking and where, only what the commands they're asking about need is looked up
+#[derive(Debug, Default)]
+pub struct Invoker {
+  pub owner: bool,
+  pub guild: bool,
+  pub permissions: serenity::Permissions,
+  pub roles: Vec<serenity::RoleId>,
+  pub nsfw: bool,
+}
+
+impl Invoker {
+  pub async fn new(ctx: &Context<'_>, commands: &[&Command]) -> Result<Self> {
+    let owner = match commands.iter().any(|cmd| cmd.owner_only) {
+      true => {
+        ctx
+          .client
+          .owners
+          .contains(&ctx.serenity.http, ctx.event.user.id)
+          .await?
+      }
+      false => false,
+    };
+    let nsfw = match commands.iter().any(|cmd| cmd.nsfw) {
+      true => ctx.is_nsfw().await?,
+      false => false,
+    };
+    let member = ctx.event.member.as_deref();
+    Ok(Self {
+      owner,
+      guild: ctx.event.guild_id.is_some(),
+      permissions: member.and_then(|m| m.permissions).unwrap_or_default(),
+      roles: member.map(|m| m.roles.clone()).unwrap_or_default(),
+      nsfw,
+    })
+  }
+}
+
+impl Denial {
+  pub fn message(self, ctx: &Context<'_>) -> String {
+    match self {
+      Self::OwnerOnly => tr!(ctx, "this command is owner-only"),
+      Self::GuildOnly => tr!(ctx, "this command only works on servers"),
+      Self::Permissions(missing) => {
+        let missing = missing.get_permission_names().join(", ");
+        tr!(ctx, "you need these permissions: {}", missing)
+      }
+      Self::Nsfw => tr!(ctx, "this command only works in NSFW channels"),
+      Self::Roles => tr!(ctx, "you don't have a role that can use this command"),
+    }
+  }
+}
+
+// `allowed` are the roles on the allowlist of the command,
+// `checked` are the permissions discord has already checked, admins can override those, so they're left alone
+pub fn denial(
+  cmd: &Command,
+  who: &Invoker,
+  allowed: &[serenity::RoleId],
+  checked: Option<serenity::Permissions>,
+) -> Option<Denial> {
+  if cmd.owner_only && !who.owner {
+    return Some(Denial::OwnerOnly);
+  }
+  if !who.guild {
+    return cmd.guild_only.then_some(Denial::GuildOnly);
+  }
+
+  let required = cmd
+    .permissions
+    .filter(|&p| checked.is_none_or(|checked| !checked.contains(p)));
+  if let Some(required) = required.filter(|&p| !who.permissions.contains(p)) {
+    return Some(Denial::Permissions(required - who.permissions));
+  }
+  if cmd.nsfw && !who.nsfw {
+    return Some(Denial::Nsfw);
+  }
+
+  // those who can change the allowlist can't be locked out by it
+  let has_role = who.roles.iter().any(|role| allowed.contains(role));
+  if !allowed.is_empty() && !has_role && !who.permissions.manage_guild() {
+    return Some(Denial::Roles);
+  }
+
+  None
+}
+
+// discord only enforces permissions, DMs and NSFW on top-level commands, and not at all on text commands,
+// so what it doesn't is checked here, `path` is the full command path (e.g. `/deezer as-file`)
+pub async fn check(ctx: &Context<'_>, path: &str) -> Result<()> {
+  let cmd = ctx.command;
+  let who = Invoker::new(ctx, &[cmd]).await?;
+  let allowed = match ctx.event.guild_id {
+    Some(gid) => guild_command_roles::get(&ctx.client.db, gid, path).await?,
+    None => Vec::new(),
+  };
+  let checked = match ctx.message {
+    Some(_) => None,
+    None => commands::registered_permissions(&ctx.client.commands, path),
+  };
+
+  match denial(cmd, &who, &allowed, checked) {
+    Some(denial) => err::message!("{}", denial.message(ctx)),
+    None => Ok(()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[macros::command(desc = "Bans", permissions = "ban_members")]
+  async fn ban(_ctx: &Context<'_>) -> Result<()> {
+    Ok(())
+  }
+
+  #[macros::command(desc = "Posts", guild_only, nsfw)]
+  async fn post(_ctx: &Context<'_>) -> Result<()> {
+    Ok(())
+  }
+
+  #[test]
+  fn denials() {
+    let (ban, post) = (ban("ban"), post("post"));
+    let member = Invoker {
+      guild: true,
+      ..Default::default()
+    };
+    let kick = serenity::Permissions::KICK_MEMBERS;
+    let ban_members = serenity::Permissions::BAN_MEMBERS;
+
+    // discord has checked it already, and might have been told otherwise by the admins
+    assert_eq!(denial(&ban, &member, &[], Some(ban_members)), None);
+    // a text command, or a group that only requires a part of it
+    assert_eq!(denial(&ban, &member, &[], None), Some(Denial::Permissions(ban_members)));
+    assert_eq!(
+      denial(&ban, &member, &[], Some(kick)),
+      Some(Denial::Permissions(ban_members))
+    );
+
+    assert_eq!(denial(&post, &Invoker::default(), &[], None), Some(Denial::GuildOnly));
+    assert_eq!(denial(&post, &member, &[], None), Some(Denial::Nsfw));
+
+    let role = serenity::RoleId::new(1);
+    let nsfw = Invoker { nsfw: true, ..member };
+    assert_eq!(denial(&post, &nsfw, &[role], None), Some(Denial::Roles));
+    let with_role = Invoker {
+      roles: vec![role],
+      ..nsfw
+    };
+    assert_eq!(denial(&post, &with_role, &[role], None), None);
+  }
+}
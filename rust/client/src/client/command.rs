@@ -1,35 +1,91 @@
+use std::fmt::{self, Display, Formatter};
 use std::future::Future;
 use std::pin::Pin;
 
 use ::serenity::all as serenity;
 
-use super::{Context, Result};
+use super::{Context, Integration, Ratelimit, Result};
 
 pub type RunFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+pub type AutocompleteFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<serenity::AutocompleteChoice>>> + Send + 'a>>;
+pub type AutocompleteFn = for<'a> fn(&'a Context<'_>, &'a str) -> AutocompleteFuture<'a>;
 
 #[derive(Debug)]
 pub struct Command {
   pub name: &'static str,
+  pub kind: serenity::CommandType,
   pub description: Option<&'static str>,
+  pub localizations: &'static [Localization],
   pub owner_only: bool,
+  // what discord calls `default_member_permissions`, server admins can override it
+  pub permissions: Option<serenity::Permissions>,
+  // what discord calls `dm_permission`, but inverted
+  pub guild_only: bool,
+  pub nsfw: bool,
+  pub registration: Registration,
+  pub requires: Option<Integration>,
+  pub ratelimits: &'static [Ratelimit],
 
   pub run: for<'a> fn(&'a Context<'_>) -> RunFuture<'a>,
   pub options: Vec<&'static CommandOption>,
 }
 
+// where a command gets registered:
+// - `Global` everywhere, can still be disabled per guild
+// - `Guild` per guild, has to be enabled by server admins first
+// - `Dev` only on the dev server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Registration {
+  Global,
+  Guild,
+  Dev,
+}
+
 #[derive(Debug)]
 pub struct CommandOption {
   pub name: &'static str,
   pub description: Option<&'static str>,
+  pub localizations: &'static [Localization],
   pub choices: Option<&'static [CommandOptionChoice]>,
   pub min: Option<f64>,
   pub max: Option<f64>,
+  // in characters, only for strings
+  pub min_length: Option<u16>,
+  pub max_length: Option<u16>,
+  // only for channels, any channel if empty
+  pub channel_types: &'static [serenity::ChannelType],
   pub required: bool,
   pub ty: serenity::CommandOptionType,
+  pub autocomplete: Option<AutocompleteFn>,
 }
 
 #[derive(Debug)]
 pub struct CommandOptionChoice {
   pub name: &'static str,
-  pub value: &'static str,
+  pub value: ChoiceValue,
+}
+
+// has to match the type of the option it's a choice for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChoiceValue {
+  String(&'static str),
+  Integer(i64),
+  Number(f64),
+}
+
+impl Display for ChoiceValue {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::String(s) => f.write_str(s),
+      Self::Integer(n) => write!(f, "{n}"),
+      Self::Number(n) => write!(f, "{n}"),
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct Localization {
+  pub locale: &'static str,
+  pub name: Option<&'static str>,
+  pub description: Option<&'static str>,
 }
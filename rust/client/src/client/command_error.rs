@@ -1,10 +1,11 @@
 use std::error::Error;
 use std::fmt::{self, Debug, Display, Formatter};
+use std::time::Duration;
 
 #[derive(Debug)]
 pub enum CommandError {
   Message(String),
-  Timeout,
+  Ratelimit { after: Option<Duration>, remaining: f64 },
 }
 
 impl Error for CommandError {}
@@ -22,12 +23,5 @@ pub mod err {
     })
   });
 
-  macro_rules! timeout(() => {
-    return ::std::result::Result::Err({
-      $crate::client::CommandError::Timeout.into()
-    })
-  });
-
   pub(crate) use message;
-  pub(crate) use timeout;
 }
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use ::serenity::all as serenity;
 
-use super::{Command, CommandOption};
+use super::{ChoiceValue, Command, CommandOption, Localization};
 
 pub(crate) use macros::*;
 
@@ -14,33 +14,80 @@ pub enum CommandTree {
   Commands(Commands),
 }
 
-pub fn serialize(commands: &Commands) -> Vec<serenity::CreateCommand> {
-  fn serialize_command_tree(tree: &CommandTree, name: &str) -> serenity::CreateCommandOption {
+// what discord is told about who can see a top-level command
+struct Access {
+  permissions: Option<serenity::Permissions>,
+  guild_only: bool,
+  nsfw: bool,
+}
+
+// `filter` gets called with a full command path (e.g. `/deezer as-file`),
+// groups that end up with no subcommands are left out entirely
+pub fn serialize(commands: &Commands, filter: impl Fn(&str, &Command) -> bool) -> Vec<serenity::CreateCommand> {
+  fn serialize_command_tree(
+    tree: &CommandTree,
+    name: &str,
+    path: &str,
+    filter: &dyn Fn(&str, &Command) -> bool,
+  ) -> Option<serenity::CreateCommandOption> {
     match tree {
-      CommandTree::Command(cmd) => serialize_subcommand(cmd),
+      CommandTree::Command(cmd) => filter(path, cmd).then(|| serialize_subcommand(cmd)),
       CommandTree::Commands(cmds) => {
         let ty = serenity::CommandOptionType::SubCommandGroup;
         let builder = serenity::CreateCommandOption::new(ty, name, "…");
-        cmds.iter().fold(builder, |b, (&name, tree)| {
-          let opt = serialize_command_tree(tree, name);
-          b.add_sub_option(opt)
-        })
+        let opts = sorted(cmds).filter_map(|(name, tree)| {
+          let path = format!("{path} {name}");
+          serialize_command_tree(tree, name, &path, filter)
+        });
+        let opts = opts.collect::<Vec<_>>();
+        (!opts.is_empty()).then(|| opts.into_iter().fold(builder, |b, opt| b.add_sub_option(opt)))
       }
     }
   }
 
-  fn serialize_commands(cmds: &Commands, name: &str) -> serenity::CreateCommand {
+  fn serialize_commands(
+    cmds: &Commands,
+    name: &str,
+    filter: &dyn Fn(&str, &Command) -> bool,
+  ) -> Option<serenity::CreateCommand> {
     let builder = serenity::CreateCommand::new(name).description("…");
-    cmds.iter().fold(builder, |b, (&name, tree)| {
-      let opt = serialize_command_tree(tree, name);
-      b.add_option(opt)
-    })
+    let builder = restrict(builder, &group_access(cmds));
+    let opts = sorted(cmds).filter_map(|(sub, tree)| {
+      let path = format!("/{name} {sub}");
+      serialize_command_tree(tree, sub, &path, filter)
+    });
+    let opts = opts.collect::<Vec<_>>();
+    (!opts.is_empty()).then(|| opts.into_iter().fold(builder, |b, opt| b.add_option(opt)))
+  }
+
+  fn restrict(builder: serenity::CreateCommand, access: &Access) -> serenity::CreateCommand {
+    let builder = match access.permissions {
+      Some(permissions) => builder.default_member_permissions(permissions),
+      None => builder,
+    };
+    // discord leaves it out when it's true, so it's only set when it's false, to compare equal
+    let builder = match access.guild_only {
+      true => builder.dm_permission(false),
+      false => builder,
+    };
+    builder.nsfw(access.nsfw)
   }
 
   fn serialize_subcommand(cmd: &Command) -> serenity::CreateCommandOption {
+    assert_eq!(
+      cmd.kind,
+      serenity::CommandType::ChatInput,
+      "context menu commands can't be nested"
+    );
     let ty = serenity::CommandOptionType::SubCommand;
     let desc = cmd.description.unwrap_or("…");
     let builder = serenity::CreateCommandOption::new(ty, cmd.name, desc);
+    let builder = localize(
+      builder,
+      cmd.localizations,
+      |b, l, s| b.name_localized(l, s),
+      |b, l, s| b.description_localized(l, s),
+    );
     cmd.options.iter().fold(builder, |b, &opt| {
       let opt = serialize_command_option(opt);
       b.add_sub_option(opt)
@@ -48,8 +95,24 @@ pub fn serialize(commands: &Commands) -> Vec<serenity::CreateCommand> {
   }
 
   fn serialize_command(cmd: &Command) -> serenity::CreateCommand {
-    let desc = cmd.description.unwrap_or("…");
-    let builder = serenity::CreateCommand::new(cmd.name).description(desc);
+    let builder = serenity::CreateCommand::new(cmd.name).kind(cmd.kind);
+    // context menu commands have neither descriptions nor options
+    let builder = match cmd.kind {
+      serenity::CommandType::ChatInput => builder.description(cmd.description.unwrap_or("…")),
+      _ => builder,
+    };
+    let builder = localize(
+      builder,
+      cmd.localizations,
+      |b, l, s| b.name_localized(l, s),
+      |b, l, s| b.description_localized(l, s),
+    );
+    let access = Access {
+      permissions: cmd.permissions,
+      guild_only: cmd.guild_only,
+      nsfw: cmd.nsfw,
+    };
+    let builder = restrict(builder, &access);
     cmd.options.iter().fold(builder, |b, &opt| {
       let opt = serialize_command_option(opt);
       b.add_option(opt)
@@ -59,7 +122,15 @@ pub fn serialize(commands: &Commands) -> Vec<serenity::CreateCommand> {
   fn serialize_command_option(opt: &CommandOption) -> serenity::CreateCommandOption {
     let choices = opt.choices.unwrap_or(&[]).iter();
     let desc = opt.description.unwrap_or("…");
-    let builder = serenity::CreateCommandOption::new(opt.ty, opt.name, desc).required(opt.required);
+    let builder = serenity::CreateCommandOption::new(opt.ty, opt.name, desc)
+      .required(opt.required)
+      .set_autocomplete(opt.autocomplete.is_some());
+    let builder = localize(
+      builder,
+      opt.localizations,
+      |b, l, s| b.name_localized(l, s),
+      |b, l, s| b.description_localized(l, s),
+    );
     let builder = match opt.min {
       Some(n) => builder.min_number_value(n),
       None => builder,
@@ -68,17 +139,99 @@ pub fn serialize(commands: &Commands) -> Vec<serenity::CreateCommand> {
       Some(n) => builder.max_number_value(n),
       None => builder,
     };
-    choices.fold(builder, |b, ch| b.add_string_choice(ch.name, ch.value))
+    let builder = match opt.min_length {
+      Some(n) => builder.min_length(n),
+      None => builder,
+    };
+    let builder = match opt.max_length {
+      Some(n) => builder.max_length(n),
+      None => builder,
+    };
+    let builder = match opt.channel_types {
+      [] => builder,
+      types => builder.channel_types(types.to_vec()),
+    };
+    choices.fold(builder, |b, ch| match ch.value {
+      ChoiceValue::String(value) => b.add_string_choice(ch.name, value),
+      // `macros::Choice` doesn't let integer values outside of `i32` through
+      ChoiceValue::Integer(value) => b.add_int_choice(ch.name, value as i32),
+      ChoiceValue::Number(value) => b.add_number_choice(ch.name, value),
+    })
   }
 
-  let commands = commands.iter().map(|(&name, tree)| match tree {
-    CommandTree::Command(cmd) => serialize_command(cmd),
-    CommandTree::Commands(cmds) => serialize_commands(cmds, name),
+  let commands = sorted(commands).filter_map(|(name, tree)| match tree {
+    CommandTree::Command(cmd) => filter(&format!("/{name}"), cmd).then(|| serialize_command(cmd)),
+    CommandTree::Commands(cmds) => serialize_commands(cmds, name, &filter),
   });
 
   commands.collect()
 }
 
+// the permissions discord checks before a command at `path` (e.g. `/deezer as-file`) gets to us,
+// unless server admins have overridden them
+pub fn registered_permissions(commands: &Commands, path: &str) -> Option<serenity::Permissions> {
+  let top = path.trim_start_matches('/').split(' ').next().unwrap_or_default();
+  match commands.get(top)? {
+    CommandTree::Command(cmd) => cmd.permissions,
+    CommandTree::Commands(cmds) => group_access(cmds).permissions,
+  }
+}
+
+// a group can only be as restricted as the least restricted of its subcommands,
+// the ones that are more restricted than that are left to `access::check`
+fn group_access(cmds: &Commands) -> Access {
+  let all = walk(cmds);
+  let permissions = all.iter().map(|(_, cmd)| cmd.permissions);
+  let permissions = permissions.reduce(|a, b| a.zip(b).map(|(a, b)| a & b)).flatten();
+  Access {
+    // none at all would hide it from everyone but admins
+    permissions: permissions.filter(|p| !p.is_empty()),
+    guild_only: all.iter().all(|(_, cmd)| cmd.guild_only),
+    nsfw: all.iter().all(|(_, cmd)| cmd.nsfw),
+  }
+}
+
+// builders for commands and their options don't have a common trait
+fn localize<B>(builder: B, l10n: &[Localization], name: fn(B, &str, &str) -> B, desc: fn(B, &str, &str) -> B) -> B {
+  l10n.iter().fold(builder, |b, l10n| {
+    let b = match l10n.name {
+      Some(s) => name(b, l10n.locale, s),
+      None => b,
+    };
+    match l10n.description {
+      Some(s) => desc(b, l10n.locale, s),
+      None => b,
+    }
+  })
+}
+
+// all the leaf commands along with their full paths (e.g. `/deezer as-file`)
+pub fn walk(commands: &Commands) -> Vec<(String, &Command)> {
+  fn walk_inner<'a>(acc: &mut Vec<(String, &'a Command)>, tree: &'a CommandTree, path: String) {
+    match tree {
+      CommandTree::Command(cmd) => acc.push((path, cmd)),
+      CommandTree::Commands(cmds) => {
+        for (name, tree) in sorted(cmds) {
+          walk_inner(acc, tree, format!("{path} {name}"));
+        }
+      }
+    }
+  }
+
+  let mut acc = Vec::new();
+  for (name, tree) in sorted(commands) {
+    walk_inner(&mut acc, tree, format!("/{name}"));
+  }
+  acc
+}
+
+// discord keeps the options in the order they were registered in, so it has to be stable
+fn sorted(commands: &Commands) -> impl Iterator<Item = (&'static str, &CommandTree)> {
+  let mut commands = commands.iter().map(|(&name, tree)| (name, tree)).collect::<Vec<_>>();
+  commands.sort_unstable_by_key(|&(name, _)| name);
+  commands.into_iter()
+}
+
 pub fn resolve<'a>(
   commands: &'a Commands,
   options: Vec<serenity::ResolvedOption<'a>>,
@@ -99,24 +252,76 @@ pub fn resolve<'a>(
 }
 
 mod macros {
+  // names are checked at compile time, as much as they can be, the rest is up to `validate_commands`,
+  // the ones in brackets count how deep it is, discord only allows a command, a group, and a subcommand
   macro_rules! commands {
-    ($acc:expr,) => {};
-    ($acc:expr, $name:expr => $command:path, $($tokens:tt)*) => {
+    (@insert $acc:expr, [$($depth:tt)*],) => {};
+    (@insert $acc:expr, [$($depth:tt)*], $name:expr => $command:path, $($tokens:tt)*) => {
+      const _: () = $crate::client::validate_name($name, 0 $(+ $depth)* == 0);
       $acc.insert($name, $crate::client::CommandTree::Command($command($name)));
-      $crate::client::commands!($acc, $($tokens)*);
+      $crate::client::commands!(@insert $acc, [$($depth)*], $($tokens)*);
     };
-    ($acc:expr, $name:expr => { $($subcommands:tt)* }, $($tokens:tt)*) => {
+    (@insert $acc:expr, [$($depth:tt)*], $name:expr => { $($subcommands:tt)* }, $($tokens:tt)*) => {
+      const _: () = $crate::client::validate_name($name, 0 $(+ $depth)* == 0);
       $acc.insert($name, $crate::client::CommandTree::Commands({
-        $crate::client::commands!($($subcommands)*)
+        $crate::client::commands!(@tree [$($depth)* 1], $($subcommands)*)
       }));
-      $crate::client::commands!($acc, $($tokens)*);
+      $crate::client::commands!(@insert $acc, [$($depth)*], $($tokens)*);
     };
-    ($($tokens:tt)*) => {{
+    (@tree [1 1 1], $($tokens:tt)*) => {
+      compile_error!("subcommand groups can't be nested any deeper")
+    };
+    (@tree [$($depth:tt)*], $($tokens:tt)*) => {{
       let mut acc = $crate::client::Commands::new();
-      $crate::client::commands!(&mut acc, $($tokens)*);
+      $crate::client::commands!(@insert &mut acc, [$($depth)*], $($tokens)*);
       acc
     }};
+    ($($tokens:tt)*) => {
+      $crate::client::commands!(@tree [], $($tokens)*)
+    };
   }
 
   pub(crate) use commands;
 }
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+  use crate::client::{Context, Result};
+
+  #[::macros::command(desc = "Kicks", guild_only, permissions = "kick_members")]
+  async fn kick(_ctx: &Context<'_>) -> Result<()> {
+    Ok(())
+  }
+
+  #[::macros::command(desc = "Bans", guild_only, nsfw, permissions = "kick_members, ban_members")]
+  async fn ban(_ctx: &Context<'_>) -> Result<()> {
+    Ok(())
+  }
+
+  #[test]
+  fn access() {
+    let group = Commands::from([
+      ("kick", CommandTree::Command(kick("kick"))),
+      ("ban", CommandTree::Command(ban("ban"))),
+    ]);
+    let commands = Commands::from([
+      ("ban", CommandTree::Command(ban("ban"))),
+      ("mod", CommandTree::Commands(group)),
+    ]);
+    let serialized = serialize(&commands, |_, _| true);
+    let json = serde_json::to_value(serialized).unwrap();
+
+    let kick_members = serenity::Permissions::KICK_MEMBERS.bits();
+    let both = (serenity::Permissions::KICK_MEMBERS | serenity::Permissions::BAN_MEMBERS).bits();
+    assert_eq!(json[0]["default_member_permissions"], json!(both.to_string()));
+    assert_eq!(json[0]["dm_permission"], json!(false));
+    assert_eq!(json[0]["nsfw"], json!(true));
+    // only what all of the subcommands have in common
+    assert_eq!(json[1]["default_member_permissions"], json!(kick_members.to_string()));
+    assert_eq!(json[1]["dm_permission"], json!(false));
+    assert_eq!(json[1]["nsfw"], json!(false));
+  }
+}
@@ -0,0 +1,432 @@
+#![allow(non_snake_case)]
+
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::RwLock;
+use std::time::Duration;
+use std::{env, error, fs, io};
+
+use serenity::all::*;
+use url::Url;
+
+// values come from env vars first, then from the (optional) toml file, which uses the same keys, e.g.:
+// `CACHE_LIMIT_GiB = 4`
+const FILE: &str = "CONFIG_FILE";
+const DEFAULT_FILE: &str = "config.toml";
+
+#[derive(Debug)]
+pub struct Config {
+  pub database_url: String,
+  pub cache_working_dir: PathBuf,
+  pub cache_base_url: Url,
+  pub discord_token: String,
+  pub discord_dev_server: GuildId,
+  pub discord_dev_server_invite: String,
+  pub deezer_arl: Option<String>,
+  pub spotify_app_id: Option<String>,
+  pub spotify_app_secret: Option<String>,
+  pub imgur_app_id: Option<String>,
+  pub imgur_app_secret: Option<String>,
+  pub omdb_api_key: Option<String>,
+  pub openweathermap_api_key: Option<String>,
+  pub metrics_addr: Option<SocketAddr>,
+  settings: RwLock<Settings>,
+}
+
+// the part that gets reloaded on SIGHUP, secrets and such require a restart,
+// a process can't be handed new env vars, so only the ones set in the file can change
+#[derive(Debug, Clone)]
+pub struct Settings {
+  pub cache_limit_GiB: u64,
+  pub command_prefix: Option<String>,
+  pub shutdown_deadline: Duration,
+}
+
+// commands that need a third party service are disabled when it's not configured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Integration {
+  Deezer,
+  Spotify,
+  Imgur,
+  Omdb,
+  OpenWeatherMap,
+}
+
+impl Integration {
+  pub const ALL: [Self; 5] = [
+    Self::Deezer,
+    Self::Spotify,
+    Self::Imgur,
+    Self::Omdb,
+    Self::OpenWeatherMap,
+  ];
+
+  pub fn keys(self) -> &'static [&'static str] {
+    match self {
+      Self::Deezer => &["DEEZER_ARL"],
+      Self::Spotify => &["SPOTIFY_APP_ID", "SPOTIFY_APP_SECRET"],
+      Self::Imgur => &["IMGUR_APP_ID", "IMGUR_APP_SECRET"],
+      Self::Omdb => &["OMDB_API_KEY"],
+      Self::OpenWeatherMap => &["OPENWEATHERMAP_API_KEY"],
+    }
+  }
+}
+
+impl Config {
+  pub fn load() -> Result<Self, Error> {
+    let mut s = Source::load()?;
+
+    let database_url = s.required("DATABASE_URL");
+    let cache_working_dir = s.required("CACHE_WORKING_DIR");
+    let cache_base_url = s.required("CACHE_BASE_URL");
+    let discord_token = s.required("DISCORD_TOKEN");
+    let discord_dev_server = s.required::<u64>("DISCORD_DEV_SERVER_ID");
+    let discord_dev_server_invite = s.required("DISCORD_DEV_SERVER_INVITE");
+    let settings = Settings::load(&mut s);
+
+    for integration in Integration::ALL {
+      let keys = integration.keys();
+      let set = keys.iter().filter(|key| s.get(key).is_some()).count();
+      if set != 0 && set != keys.len() {
+        s.errors.push(format!("{}: have to be set together", keys.join(", ")));
+      }
+    }
+
+    let deezer_arl = s.optional("DEEZER_ARL");
+    let spotify_app_id = s.optional("SPOTIFY_APP_ID");
+    let spotify_app_secret = s.optional("SPOTIFY_APP_SECRET");
+    let imgur_app_id = s.optional("IMGUR_APP_ID");
+    let imgur_app_secret = s.optional("IMGUR_APP_SECRET");
+    let omdb_api_key = s.optional("OMDB_API_KEY");
+    let openweathermap_api_key = s.optional("OPENWEATHERMAP_API_KEY");
+    let metrics_addr = s.optional("METRICS_ADDR");
+
+    // everything required is there once this succeeds
+    s.finish()?;
+
+    Ok(Self {
+      database_url: database_url.unwrap(),
+      cache_working_dir: cache_working_dir.unwrap(),
+      cache_base_url: cache_base_url.unwrap(),
+      discord_token: discord_token.unwrap(),
+      discord_dev_server: discord_dev_server.unwrap().into(),
+      discord_dev_server_invite: discord_dev_server_invite.unwrap(),
+      deezer_arl,
+      spotify_app_id,
+      spotify_app_secret,
+      imgur_app_id,
+      imgur_app_secret,
+      omdb_api_key,
+      openweathermap_api_key,
+      metrics_addr,
+      settings: RwLock::new(settings.unwrap()),
+    })
+  }
+
+  // placeholders that don't point anywhere, nothing in tests should be connecting to them
+  #[cfg(test)]
+  pub fn test(cache_working_dir: PathBuf) -> Self {
+    Self {
+      database_url: "sqlite::memory:".to_owned(),
+      cache_working_dir,
+      cache_base_url: Url::parse("http://localhost/").unwrap(),
+      discord_token: "token".to_owned(),
+      discord_dev_server: GuildId::new(1),
+      discord_dev_server_invite: "https://discord.gg/".to_owned(),
+      deezer_arl: None,
+      spotify_app_id: None,
+      spotify_app_secret: None,
+      imgur_app_id: None,
+      imgur_app_secret: None,
+      omdb_api_key: None,
+      openweathermap_api_key: None,
+      metrics_addr: None,
+      settings: RwLock::new(Settings {
+        cache_limit_GiB: 1,
+        command_prefix: None,
+        shutdown_deadline: Duration::from_secs(30),
+      }),
+    }
+  }
+
+  pub fn settings(&self) -> Settings {
+    self.settings.read().unwrap().clone()
+  }
+
+  // loads everything again, but only applies the settings,
+  // the ones set by env vars keep their values since those take precedence
+  pub fn reload(&self) -> Result<Settings, Error> {
+    let settings = Self::load()?.settings();
+    *self.settings.write().unwrap() = settings.clone();
+    Ok(settings)
+  }
+
+  pub fn has(&self, integration: Integration) -> bool {
+    match integration {
+      Integration::Deezer => self.deezer_arl.is_some(),
+      Integration::Spotify => self.spotify_app_id.is_some() && self.spotify_app_secret.is_some(),
+      Integration::Imgur => self.imgur_app_id.is_some() && self.imgur_app_secret.is_some(),
+      Integration::Omdb => self.omdb_api_key.is_some(),
+      Integration::OpenWeatherMap => self.openweathermap_api_key.is_some(),
+    }
+  }
+}
+
+impl Settings {
+  pub const KEYS: [&'static str; 3] = ["CACHE_LIMIT_GiB", "COMMAND_PREFIX", "SHUTDOWN_DEADLINE_SECS"];
+
+  // the ones that reloading can't change
+  pub fn pinned() -> Vec<&'static str> {
+    Self::KEYS
+      .into_iter()
+      .filter(|key| env::var_os(key).is_some())
+      .collect()
+  }
+
+  fn load(s: &mut Source) -> Option<Self> {
+    let cache_limit_GiB = s.required("CACHE_LIMIT_GiB");
+    let command_prefix = s.optional("COMMAND_PREFIX");
+    let shutdown_deadline = s.optional("SHUTDOWN_DEADLINE_SECS").unwrap_or(30);
+    Some(Self {
+      cache_limit_GiB: cache_limit_GiB?,
+      command_prefix,
+      shutdown_deadline: Duration::from_secs(shutdown_deadline),
+    })
+  }
+}
+
+// ---
+
+// collects all the problems instead of stopping at the first one
+struct Source {
+  file: HashMap<String, String>,
+  errors: Vec<String>,
+}
+
+impl Source {
+  fn load() -> Result<Self, Error> {
+    let (path, explicit) = match env::var(FILE) {
+      Ok(path) => (path, true),
+      Err(_) => (DEFAULT_FILE.to_owned(), false),
+    };
+
+    let text = match fs::read_to_string(&path) {
+      Ok(text) => text,
+      // the default one doesn't have to exist
+      Err(err) if err.kind() == io::ErrorKind::NotFound && !explicit => String::new(),
+      Err(err) => return Err(Error(vec![format!("{}: {}", path, err)])),
+    };
+
+    let table = toml::from_str::<toml::Table>(&text).map_err(|err| Error(vec![format!("{}: {}", path, err)]))?;
+    let file = table.into_iter().map(|(key, value)| {
+      let value = match value {
+        toml::Value::String(s) => s,
+        value => value.to_string(),
+      };
+      (key, value)
+    });
+
+    Ok(Self {
+      file: file.collect(),
+      errors: Vec::new(),
+    })
+  }
+
+  fn get(&self, key: &str) -> Option<String> {
+    env::var(key).ok().or_else(|| self.file.get(key).cloned())
+  }
+
+  fn required<T: FromStr>(&mut self, key: &str) -> Option<T>
+  where
+    T::Err: Display,
+  {
+    if self.get(key).is_none() {
+      self.errors.push(format!("{}: missing", key));
+    }
+    self.optional(key)
+  }
+
+  fn optional<T: FromStr>(&mut self, key: &str) -> Option<T>
+  where
+    T::Err: Display,
+  {
+    match self.get(key)?.parse() {
+      Ok(value) => Some(value),
+      Err(err) => {
+        self.errors.push(format!("{}: {}", key, err));
+        None
+      }
+    }
+  }
+
+  fn finish(self) -> Result<(), Error> {
+    match self.errors.is_empty() {
+      true => Ok(()),
+      false => Err(Error(self.errors)),
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct Error(Vec<String>);
+
+impl error::Error for Error {}
+impl Display for Error {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "invalid config")?;
+    for error in &self.0 {
+      write!(f, "\n  {}", error)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{Mutex, PoisonError};
+
+  use super::*;
+
+  // env vars are shared by the whole process, so only one test can be setting them at a time
+  static ENV: Mutex<()> = Mutex::new(());
+
+  const KEYS: &[&str] = &[
+    "DATABASE_URL",
+    "CACHE_WORKING_DIR",
+    "CACHE_BASE_URL",
+    "DISCORD_TOKEN",
+    "DISCORD_DEV_SERVER_ID",
+    "DISCORD_DEV_SERVER_INVITE",
+    "METRICS_ADDR",
+  ];
+
+  const VALID: &str = r#"
+    DATABASE_URL = "sqlite://riamu.db"
+    CACHE_WORKING_DIR = "/tmp/cache"
+    CACHE_BASE_URL = "https://cache.example.com/"
+    DISCORD_TOKEN = "token"
+    DISCORD_DEV_SERVER_ID = 123
+    DISCORD_DEV_SERVER_INVITE = "https://discord.gg/invite"
+    CACHE_LIMIT_GiB = 4
+  "#;
+
+  // runs `f` with `toml` as the config file, and only `vars` set out of the config env vars
+  fn with_config<T>(toml: &str, vars: &[(&str, &str)], f: impl FnOnce() -> T) -> T {
+    let _env = ENV.lock().unwrap_or_else(PoisonError::into_inner);
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, toml).unwrap();
+
+    let integrations = Integration::ALL.iter().flat_map(|i| i.keys());
+    let keys = KEYS.iter().chain(&Settings::KEYS).chain(integrations);
+    for key in keys {
+      env::remove_var(key);
+    }
+    env::set_var(FILE, &path);
+    for (key, value) in vars {
+      env::set_var(key, value);
+    }
+
+    let result = f();
+    for (key, _) in vars {
+      env::remove_var(key);
+    }
+    env::remove_var(FILE);
+    result
+  }
+
+  fn load(toml: &str, vars: &[(&str, &str)]) -> Result<Config, Error> {
+    with_config(toml, vars, Config::load)
+  }
+
+  // the keys the errors are about, in order
+  fn invalid(result: Result<Config, Error>) -> Vec<String> {
+    let errors = result.unwrap_err().0;
+    let keys = errors.iter().map(|err| err.split(": ").next().unwrap().to_owned());
+    keys.collect()
+  }
+
+  #[test]
+  fn valid() {
+    let config = load(VALID, &[]).unwrap();
+    assert_eq!(config.database_url, "sqlite://riamu.db");
+    assert_eq!(config.cache_base_url.as_str(), "https://cache.example.com/");
+    assert_eq!(config.discord_dev_server, GuildId::new(123));
+    assert_eq!(config.settings().cache_limit_GiB, 4);
+    assert_eq!(config.settings().command_prefix, None);
+    assert_eq!(config.settings().shutdown_deadline, Duration::from_secs(30));
+    assert!(!config.has(Integration::Spotify));
+
+    let toml = format!("{VALID}\nSPOTIFY_APP_ID = \"id\"\nSPOTIFY_APP_SECRET = \"secret\"");
+    assert!(load(&toml, &[]).unwrap().has(Integration::Spotify));
+  }
+
+  #[test]
+  fn reports_everything() {
+    let toml = r#"
+      CACHE_WORKING_DIR = "/tmp/cache"
+      CACHE_BASE_URL = "not a url"
+      DISCORD_DEV_SERVER_ID = "me"
+      DISCORD_DEV_SERVER_INVITE = "https://discord.gg/invite"
+      CACHE_LIMIT_GiB = -1
+      METRICS_ADDR = "localhost"
+    "#;
+    assert_eq!(
+      invalid(load(toml, &[])),
+      [
+        "DATABASE_URL",
+        "CACHE_BASE_URL",
+        "DISCORD_TOKEN",
+        "DISCORD_DEV_SERVER_ID",
+        "CACHE_LIMIT_GiB",
+        "METRICS_ADDR"
+      ]
+    );
+  }
+
+  #[test]
+  fn env_first() {
+    let toml = format!("{VALID}\nCOMMAND_PREFIX = \"!\"");
+    let vars = [("COMMAND_PREFIX", "?"), ("DATABASE_URL", "sqlite://other.db")];
+    let config = load(&toml, &vars).unwrap();
+    assert_eq!(config.settings().command_prefix.as_deref(), Some("?"));
+    assert_eq!(config.database_url, "sqlite://other.db");
+
+    // env vars make up for what's missing in the file, and can be invalid too
+    let config = load("", &[("DATABASE_URL", "sqlite://riamu.db")]);
+    assert!(!invalid(config).contains(&"DATABASE_URL".to_owned()));
+    let config = load(VALID, &[("CACHE_LIMIT_GiB", "lots")]);
+    assert_eq!(invalid(config), ["CACHE_LIMIT_GiB"]);
+  }
+
+  #[test]
+  fn together() {
+    let toml = format!("{VALID}\nIMGUR_APP_SECRET = \"secret\"\nOMDB_API_KEY = \"key\"");
+    let err = load(&toml, &[]).unwrap_err();
+    assert_eq!(err.0, ["IMGUR_APP_ID, IMGUR_APP_SECRET: have to be set together"]);
+
+    let err = load(VALID, &[("SPOTIFY_APP_ID", "id")]).unwrap_err();
+    assert_eq!(err.0, ["SPOTIFY_APP_ID, SPOTIFY_APP_SECRET: have to be set together"]);
+  }
+
+  #[test]
+  fn files() {
+    let err = load("DATABASE_URL = ", &[]).unwrap_err();
+    assert_eq!(err.0.len(), 1);
+    assert!(err.0[0].contains("config.toml: "));
+
+    // a file that was asked for has to exist
+    let err = with_config(VALID, &[(FILE, "/nonexistent/riamu.toml")], Config::load).unwrap_err();
+    assert_eq!(invalid(Err(err)), ["/nonexistent/riamu.toml"]);
+  }
+
+  #[test]
+  fn pinned() {
+    let vars = [("COMMAND_PREFIX", "?"), ("DATABASE_URL", "sqlite://riamu.db")];
+    assert_eq!(with_config(VALID, &vars, Settings::pinned), ["COMMAND_PREFIX"]);
+    assert!(with_config(VALID, &[], Settings::pinned).is_empty());
+  }
+}
@@ -1,9 +1,15 @@
-use std::fmt::{self, Display, Formatter};
+use std::fmt::{self, Display, Formatter, Write};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use ::fmt::num::Format as _;
 use ::serenity::all as serenity;
+use futures::StreamExt;
+use python::progress::{Progress, Tracker};
 use util::hash::splitmix64;
 
-use super::{commands, Client, Command};
+use super::{commands, l10n, Client, Command, Reply};
 
 #[derive(Debug)]
 pub struct Context<'a> {
@@ -13,6 +19,13 @@ pub struct Context<'a> {
   pub serenity: &'a serenity::Context,
   pub command: &'a Command,
   pub options: Vec<serenity::ResolvedOption<'a>>,
+  pub target: Option<serenity::ResolvedTarget<'a>>,
+  // set for text commands, `event` is then made up from this message
+  pub message: Option<&'a serenity::Message>,
+  // set when continuing a flow from one of its components, `event` is then made up from this interaction
+  pub component: Option<&'a serenity::ComponentInteraction>,
+  // the last message sent in response to a text command, it gets edited instead of sending a new one
+  response: Mutex<Option<serenity::MessageId>>,
 }
 
 impl<'a> Context<'a> {
@@ -20,7 +33,37 @@ impl<'a> Context<'a> {
   pub fn new(client: &'a Client, ctx: &'a serenity::Context, event: &'a serenity::CommandInteraction) -> Self {
     let id = Id(event.id.get());
     let (command, options) = commands::resolve(&client.commands, event.data.options(), &event.data.name);
-    Context { id, client, serenity: ctx, event, command, options }
+    let target = event.data.target();
+    let (message, component, response) = (None, None, Mutex::new(None));
+    Context { id, client, serenity: ctx, event, command, options, target, message, component, response }
+  }
+
+  #[rustfmt::skip]
+  pub fn from_message(
+    client: &'a Client,
+    ctx: &'a serenity::Context,
+    event: &'a serenity::CommandInteraction,
+    message: &'a serenity::Message,
+  ) -> Self {
+    Context { message: Some(message), ..Self::new(client, ctx, event) }
+  }
+
+  #[rustfmt::skip]
+  pub fn from_component(
+    client: &'a Client,
+    ctx: &'a serenity::Context,
+    event: &'a serenity::CommandInteraction,
+    component: &'a serenity::ComponentInteraction,
+  ) -> Self {
+    Context { component: Some(component), ..Self::new(client, ctx, event) }
+  }
+
+  pub fn tr(&self, text: &'static str) -> &'static str {
+    l10n::translate(&self.event.locale, text)
+  }
+
+  pub fn tr_format(&self, text: &'static str, args: &[&dyn Display]) -> String {
+    l10n::format(self.tr(text), args)
   }
 
   pub async fn filesize_limit(&self) -> serenity::Result<u64> {
@@ -31,15 +74,146 @@ impl<'a> Context<'a> {
     Ok([25, 25, 50, 100][tier as usize] << 20)
   }
 
+  // DMs aren't, there's no telling whether whoever is there is an adult,
+  // threads are as NSFW as the channel they're in
+  pub async fn is_nsfw(&self) -> serenity::Result<bool> {
+    if self.event.guild_id.is_none() {
+      return Ok(false);
+    }
+    let Some(channel) = self.event.channel_id.to_channel(self.serenity).await?.guild() else {
+      return Ok(false);
+    };
+    match channel.parent_id {
+      Some(parent) if channel.thread_metadata.is_some() => {
+        let parent = parent.to_channel(self.serenity).await?.guild();
+        Ok(parent.is_some_and(|parent| parent.nsfw))
+      }
+      _ => Ok(channel.nsfw),
+    }
+  }
+
   pub async fn progress(&self, message: impl Into<String>) -> serenity::Result<serenity::Message> {
-    let button = serenity::CreateButton::new("…")
-      .style(serenity::ButtonStyle::Secondary)
-      .disabled(true)
-      .label(message);
-    let buttons = serenity::CreateActionRow::Buttons(vec![button]);
-    let edit = serenity::EditInteractionResponse::new().components(vec![buttons]);
-    let edit = self.event.edit_response(self, edit);
-    edit.await
+    self.edit_reply(progress_reply(&message.into(), None, None)).await
+  }
+
+  // keeps the progress of a download updated until `run` is done, with a button to cancel it,
+  // returns `None` if it's been cancelled, the download is awaited anyway so that its files can be cleaned up
+  pub async fn track<T>(
+    &self,
+    message: &str,
+    tracker: &Arc<Tracker>,
+    run: impl Future<Output = T>,
+  ) -> serenity::Result<Option<T>> {
+    // editing more often than that would hit the rate limits quickly
+    const INTERVAL: Duration = Duration::from_secs(3);
+
+    let cancel = Some(self.tr("Cancel"));
+    let msg = self.edit_reply(progress_reply(message, None, cancel)).await?;
+    let mut clicks = msg
+      .await_component_interaction(self)
+      .author_id(self.event.user.id)
+      .custom_ids(vec!["cancel".into()])
+      .stream();
+
+    let mut updates = tracker.subscribe();
+    let mut interval = tokio::time::interval(INTERVAL);
+    let mut run = std::pin::pin!(run);
+
+    loop {
+      tokio::select! {
+        out = &mut run => {
+          if !tracker.is_cancelled() {
+            return Ok(Some(out));
+          }
+          tracing::debug!("cancelled");
+          let content = format!("\u{274c} {}", self.tr("cancelled"));
+          self.edit_reply(Reply::new().content(content).components(Vec::new())).await?;
+          return Ok(None);
+        }
+        Some(mci) = clicks.next(), if !tracker.is_cancelled() => {
+          tracing::debug!("cancelling…");
+          tracker.cancel();
+          mci.defer(self).await?;
+          self.edit_reply(progress_reply(self.tr("cancelling…"), None, None)).await?;
+        }
+        _ = interval.tick(), if !tracker.is_cancelled() => {
+          if updates.has_changed().unwrap_or(false) {
+            let progress = *updates.borrow_and_update();
+            self.edit_reply(progress_reply(message, Some(&progress), cancel)).await?;
+          }
+        }
+      }
+    }
+  }
+
+  // the methods below work the same way for both slash and text commands,
+  // for the latter "deferring" just shows the typing indicator,
+  // and for components it means that the message they're attached to is going to be edited
+
+  pub async fn defer(&self) -> serenity::Result<()> {
+    match (self.message, self.component) {
+      (Some(msg), _) => msg.channel_id.broadcast_typing(self).await,
+      (_, Some(component)) => component.defer(self).await,
+      _ => self.event.defer(self).await,
+    }
+  }
+
+  pub async fn defer_ephemeral(&self) -> serenity::Result<()> {
+    match (self.message, self.component) {
+      (Some(msg), _) => msg.channel_id.broadcast_typing(self).await,
+      (_, Some(component)) => component.defer(self).await,
+      _ => self.event.defer_ephemeral(self).await,
+    }
+  }
+
+  pub async fn reply(&self, reply: Reply) -> serenity::Result<()> {
+    match self.message {
+      Some(_) => self.edit_reply(reply).await.map(drop),
+      None => {
+        let msg = serenity::CreateInteractionResponse::Message(reply.into());
+        self.event.create_response(self, msg).await
+      }
+    }
+  }
+
+  pub async fn edit_reply(&self, reply: Reply) -> serenity::Result<serenity::Message> {
+    let Some(msg) = self.message else {
+      return self.event.edit_response(self, reply.into()).await;
+    };
+
+    let response = *self.response.lock().unwrap();
+    let response = match response {
+      Some(id) => msg.channel_id.edit_message(self, id, reply.into()).await?,
+      None => {
+        let create = serenity::CreateMessage::from(reply).reference_message(msg);
+        msg.channel_id.send_message(self, create).await?
+      }
+    };
+
+    *self.response.lock().unwrap() = Some(response.id);
+    Ok(response)
+  }
+
+  pub async fn delete_reply(&self) -> serenity::Result<()> {
+    let Some(msg) = self.message else {
+      return self.event.delete_response(self).await;
+    };
+
+    let response = self.response.lock().unwrap().take();
+    match response {
+      Some(id) => msg.channel_id.delete_message(self, id).await,
+      None => Ok(()),
+    }
+  }
+
+  pub async fn followup(&self, reply: Reply) -> serenity::Result<serenity::Message> {
+    match self.message {
+      Some(msg) => {
+        let create = serenity::CreateMessage::from(reply).reference_message(msg);
+        msg.channel_id.send_message(self, create).await
+      }
+      None => self.event.create_followup(self, reply.into()).await,
+    }
   }
 }
 
@@ -69,6 +243,48 @@ impl AsRef<serenity::ShardMessenger> for Context<'_> {
 
 // ---
 
+// `cancel` is the label of the cancel button, if there should be one
+fn progress_reply(message: &str, progress: Option<&Progress>, cancel: Option<&str>) -> Reply {
+  let mut label = message.to_owned();
+  if let Some(progress) = progress {
+    write_progress(&mut label, progress).unwrap();
+  }
+
+  let mut buttons = vec![serenity::CreateButton::new("…")
+    .style(serenity::ButtonStyle::Secondary)
+    .disabled(true)
+    .label(::fmt::ellipsis(&label, 80))];
+  if let Some(label) = cancel {
+    let cancel = serenity::CreateButton::new("cancel")
+      .style(serenity::ButtonStyle::Danger)
+      .label(label);
+    buttons.push(cancel);
+  }
+
+  Reply::new().components(vec![serenity::CreateActionRow::Buttons(buttons)])
+}
+
+// e.g. `▰▰▰▱▱▱▱▱▱▱ 32% · 12.3MiB of 38.4MiB · 1.17MiB/s · 0:22 left`
+fn write_progress(f: &mut String, progress: &Progress) -> fmt::Result {
+  if let Some(fraction) = progress.fraction() {
+    let filled = (fraction * 10.0).round() as usize;
+    let bar = "\u{25B0}".repeat(filled) + &"\u{25B1}".repeat(10 - filled);
+    write!(f, " {} {:.0}%", bar, fraction * 100.0)?;
+  }
+  match (progress.downloaded, progress.total) {
+    (Some(downloaded), Some(total)) => write!(f, " · {}B of {}B", downloaded.iec(), total.iec())?,
+    (Some(downloaded), None) => write!(f, " · {}B", downloaded.iec())?,
+    _ => {}
+  }
+  if let Some(speed) = progress.speed {
+    write!(f, " · {}B/s", speed.iec())?;
+  }
+  if let Some(eta) = progress.eta {
+    write!(f, " · {} left", ::fmt::duration(eta as u64))?;
+  }
+  Ok(())
+}
+
 #[derive(Debug)]
 pub struct Id(pub u64);
 
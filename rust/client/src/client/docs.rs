@@ -0,0 +1,260 @@
+use std::fmt::Write;
+
+use ::serenity::all as serenity;
+use serde_json::{json, Value};
+use serenity::CommandOptionType as T;
+
+use super::{walk, ChoiceValue, Command, CommandOption, Commands, Registration, Result};
+
+// the wiki gets the same tree that gets registered, `riamu export markdown|json` prints it,
+// `/help` renders it too, from the same pieces
+pub fn to_markdown(commands: &Commands) -> Result<String> {
+  let mut acc = String::from("# Commands\n");
+  for (path, cmd) in walk(commands) {
+    writeln!(acc, "\n## `{path}`\n")?;
+    if let Some(desc) = cmd.description {
+      writeln!(acc, "{desc}\n")?;
+    }
+    for note in notes(cmd) {
+      writeln!(acc, "- {note}")?;
+    }
+    if cmd.options.is_empty() {
+      continue;
+    }
+
+    writeln!(acc, "\n| Option | Type | Required | Description |")?;
+    writeln!(acc, "| --- | --- | --- | --- |")?;
+    for opt in &cmd.options {
+      let desc = opt.description.map(str::to_owned).into_iter().chain(details(opt));
+      let desc = desc.collect::<Vec<_>>().join("<br>").replace('|', r"\|");
+      let required = if opt.required { "yes" } else { "no" };
+      writeln!(
+        acc,
+        "| `{}` | {} | {} | {} |",
+        opt.name,
+        type_name(opt.ty),
+        required,
+        desc
+      )?;
+    }
+  }
+  Ok(acc)
+}
+
+pub fn to_json(commands: &Commands) -> Value {
+  let commands = walk(commands).into_iter().map(|(path, cmd)| {
+    json!({
+      "path": path,
+      "kind": kind_name(cmd.kind),
+      "description": cmd.description,
+      "localizations": localizations(cmd.localizations),
+      "owner_only": cmd.owner_only,
+      "guild_only": cmd.guild_only,
+      "nsfw": cmd.nsfw,
+      "permissions": cmd.permissions.map(|p| p.get_permission_names()),
+      "registration": format!("{:?}", cmd.registration).to_lowercase(),
+      "requires": cmd.requires.map(|i| format!("{i:?}").to_lowercase()),
+      "options": cmd.options.iter().map(|&opt| option_json(opt)).collect::<Vec<_>>(),
+    })
+  });
+  Value::Array(commands.collect())
+}
+
+// who can use a command and where, one short line each
+pub fn notes(cmd: &Command) -> Vec<String> {
+  let mut acc = Vec::new();
+  match cmd.kind {
+    serenity::CommandType::User => acc.push("context menu on users".to_owned()),
+    serenity::CommandType::Message => acc.push("context menu on messages".to_owned()),
+    _ => {}
+  }
+  if cmd.owner_only {
+    acc.push("owner only".to_owned());
+  }
+  if cmd.guild_only {
+    acc.push("only on servers".to_owned());
+  }
+  if cmd.nsfw {
+    acc.push("only in NSFW channels".to_owned());
+  }
+  if let Some(permissions) = cmd.permissions {
+    acc.push(format!("needs {}", permissions.get_permission_names().join(", ")));
+  }
+  match cmd.registration {
+    Registration::Global => {}
+    Registration::Guild => acc.push("has to be enabled by server admins".to_owned()),
+    Registration::Dev => acc.push("only on the dev server".to_owned()),
+  }
+  if let Some(integration) = cmd.requires {
+    acc.push(format!("needs {integration:?} to be configured"));
+  }
+  acc
+}
+
+// what's allowed beyond the type, one short line each
+pub fn details(opt: &CommandOption) -> Vec<String> {
+  let mut acc = Vec::new();
+  match (opt.min, opt.max) {
+    (Some(min), Some(max)) => acc.push(format!("from {min} to {max}")),
+    (Some(min), None) => acc.push(format!("at least {min}")),
+    (None, Some(max)) => acc.push(format!("at most {max}")),
+    (None, None) => {}
+  }
+  match (opt.min_length, opt.max_length) {
+    (Some(min), Some(max)) => acc.push(format!("from {min} to {max} characters")),
+    (Some(min), None) => acc.push(format!("at least {min} characters")),
+    (None, Some(max)) => acc.push(format!("at most {max} characters")),
+    (None, None) => {}
+  }
+  if !opt.channel_types.is_empty() {
+    let types = opt.channel_types.iter().map(serenity::ChannelType::name);
+    acc.push(format!("{} channels", types.collect::<Vec<_>>().join(", ")));
+  }
+  if let Some(choices) = opt.choices {
+    let names = choices.iter().map(|choice| choice.name);
+    acc.push(format!("one of: {}", names.collect::<Vec<_>>().join(", ")));
+  }
+  acc
+}
+
+pub fn type_name(ty: serenity::CommandOptionType) -> &'static str {
+  match ty {
+    T::String => "text",
+    T::Integer => "integer",
+    T::Number => "number",
+    T::Boolean => "true or false",
+    T::User => "user",
+    T::Channel => "channel",
+    T::Role => "role",
+    T::Mentionable => "user or role",
+    T::Attachment => "file",
+    _ => "other",
+  }
+}
+
+// ---
+
+fn kind_name(kind: serenity::CommandType) -> &'static str {
+  match kind {
+    serenity::CommandType::User => "user",
+    serenity::CommandType::Message => "message",
+    _ => "chat_input",
+  }
+}
+
+fn option_json(opt: &CommandOption) -> Value {
+  let choices = opt.choices.map(|choices| {
+    let choices = choices.iter().map(|choice| {
+      let value = match choice.value {
+        ChoiceValue::String(value) => json!(value),
+        ChoiceValue::Integer(value) => json!(value),
+        ChoiceValue::Number(value) => json!(value),
+      };
+      json!({ "name": choice.name, "value": value })
+    });
+    choices.collect::<Vec<_>>()
+  });
+  let channel_types = opt.channel_types.iter().map(serenity::ChannelType::name);
+
+  json!({
+    "name": opt.name,
+    "description": opt.description,
+    "localizations": localizations(opt.localizations),
+    "type": type_name(opt.ty),
+    "required": opt.required,
+    "min": opt.min,
+    "max": opt.max,
+    "min_length": opt.min_length,
+    "max_length": opt.max_length,
+    "channel_types": channel_types.collect::<Vec<_>>(),
+    "choices": choices,
+    "autocomplete": opt.autocomplete.is_some(),
+  })
+}
+
+fn localizations(l10n: &[super::Localization]) -> Value {
+  let l10n = l10n.iter().map(|l10n| {
+    let value = json!({ "name": l10n.name, "description": l10n.description });
+    (l10n.locale.to_owned(), value)
+  });
+  Value::Object(l10n.collect())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::client::{CommandTree, Context};
+
+  #[derive(macros::Choice)]
+  enum Unit {
+    #[name = "celsius"]
+    C,
+    #[name = "fahrenheit"]
+    F,
+  }
+
+  #[macros::command(desc = "Convert a temperature", guild_only)]
+  async fn convert(
+    _ctx: &Context<'_>,
+    #[desc = "The temperature | in degrees"]
+    #[min = 0]
+    degrees: f64,
+    #[desc = "What to convert to"] unit: Unit,
+    #[max_length = 10] note: Option<&str>,
+  ) -> Result<()> {
+    let _ = (degrees, unit, note);
+    Ok(())
+  }
+
+  #[macros::command(context_menu, owner_only)]
+  async fn menu(_ctx: &Context<'_>, _user: &serenity::User) -> Result<()> {
+    Ok(())
+  }
+
+  fn commands() -> Commands {
+    let group = Commands::from([("convert", CommandTree::Command(convert("convert")))]);
+    Commands::from([
+      ("temperature", CommandTree::Commands(group)),
+      ("Inspect", CommandTree::Command(menu("Inspect"))),
+    ])
+  }
+
+  #[test]
+  fn markdown() {
+    let markdown = to_markdown(&commands()).unwrap();
+    let expected = "\
+# Commands
+
+## `/Inspect`
+
+- context menu on users
+- owner only
+
+## `/temperature convert`
+
+Convert a temperature
+
+- only on servers
+
+| Option | Type | Required | Description |
+| --- | --- | --- | --- |
+| `degrees` | number | yes | The temperature \\| in degrees<br>at least 0 |
+| `unit` | text | yes | What to convert to<br>one of: celsius, fahrenheit |
+| `note` | text | no | at most 10 characters |
+";
+    assert_eq!(markdown, expected);
+  }
+
+  #[test]
+  fn json() {
+    let json = to_json(&commands());
+    assert_eq!(json[0]["path"], "/Inspect");
+    assert_eq!(json[0]["kind"], "user");
+    assert_eq!(json[0]["owner_only"], true);
+    let options = &json[1]["options"];
+    assert_eq!(options[0]["min"], 0.0);
+    assert_eq!(options[1]["choices"][1], json!({ "name": "fahrenheit", "value": "F" }));
+    assert_eq!(options[2]["max_length"], 10);
+    assert_eq!(json[1]["guild_only"], true);
+  }
+}
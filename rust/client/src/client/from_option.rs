@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use chrono::{FixedOffset, Offset, Utc};
+use chrono_tz::Tz;
+use color::srgb::sRGB;
+use url::Url;
+
+// types that are typed in as text and parsed, so they can be used as command parameters,
+// failing to parse is reported as "`name` should be <EXPECTED>"
+pub trait FromOption: Sized {
+  const EXPECTED: &'static str;
+
+  fn from_option(value: &str) -> Option<Self>;
+}
+
+impl FromOption for Url {
+  const EXPECTED: &'static str = "a link (e.g.: https://example.com)";
+
+  fn from_option(value: &str) -> Option<Self> {
+    let url = Url::parse(value.trim()).ok()?;
+    matches!(url.scheme(), "http" | "https").then_some(url)
+  }
+}
+
+// a zone is resolved to the offset it has right now
+impl FromOption for FixedOffset {
+  const EXPECTED: &'static str = "a UTC offset or a time zone (e.g.: -7, +3, +0530, +12:45, Europe/London)";
+
+  fn from_option(value: &str) -> Option<Self> {
+    match utc_offset(value) {
+      Some(offset) => Some(offset),
+      None => Some(Utc::now().with_timezone(&Tz::from_option(value)?).offset().fix()),
+    }
+  }
+}
+
+impl FromOption for Tz {
+  const EXPECTED: &'static str = "a time zone (e.g.: Europe/London, America/New_York)";
+
+  fn from_option(value: &str) -> Option<Self> {
+    value.trim().parse().ok()
+  }
+}
+
+impl FromOption for Duration {
+  const EXPECTED: &'static str = "a duration (e.g.: 90s, 1h30m, 2d)";
+
+  fn from_option(value: &str) -> Option<Self> {
+    humantime::parse_duration(value.trim()).ok()
+  }
+}
+
+// hex, with or without the `#`, and in the short form too
+impl FromOption for sRGB<u8, 3> {
+  const EXPECTED: &'static str = "a color (e.g.: #f80, #ff8800)";
+
+  fn from_option(value: &str) -> Option<Self> {
+    let hex = value.trim();
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+      return None;
+    }
+
+    let n = u32::from_str_radix(hex, 16).ok()?;
+    match hex.len() {
+      3 => Some(Self([n >> 8, n >> 4, n].map(|x| (x & 0xf) as u8 * 0x11))),
+      6 => {
+        let [_, r, g, b] = n.to_be_bytes();
+        Some(Self([r, g, b]))
+      }
+      _ => None,
+    }
+  }
+}
+
+// in hours, or in ±HHMM or ±HH:MM format
+fn utc_offset(value: &str) -> Option<FixedOffset> {
+  let value = value.trim();
+  let (sign, value) = match value.strip_prefix('-') {
+    Some(value) => (-1, value),
+    None => (1, value.strip_prefix('+').unwrap_or(value)),
+  };
+  if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit() || b == b':') {
+    return None;
+  }
+
+  let (h, m) = match value.split_once(':') {
+    Some((h, m)) => (h.parse::<i32>().ok()?, m.parse::<i32>().ok()?),
+    None if value.len() > 2 => {
+      let n = value.parse::<i32>().ok()?;
+      (n / 100, n % 100)
+    }
+    None => (value.parse().ok()?, 0),
+  };
+
+  match (h, m) {
+    (0..=23, 0..=59) => FixedOffset::east_opt(sign * (60 * 60 * h + 60 * m)),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn offsets() {
+    let offset = |value| FixedOffset::from_option(value).map(|o| o.local_minus_utc());
+    assert_eq!(offset("-7"), Some(-7 * 3600));
+    assert_eq!(offset("+3"), Some(3 * 3600));
+    assert_eq!(offset("0530"), Some(5 * 3600 + 30 * 60));
+    assert_eq!(offset("-05:30"), Some(-(5 * 3600 + 30 * 60)));
+    assert_eq!(offset("UTC"), Some(0));
+    assert_eq!(offset("24"), None);
+    assert_eq!(offset("+-3"), None);
+    assert_eq!(offset("Nowhere/Nothing"), None);
+  }
+
+  #[test]
+  fn colors() {
+    let color = |value| sRGB::<u8, 3>::from_option(value).map(|c| c.0);
+    assert_eq!(color("#ff8800"), Some([0xff, 0x88, 0x00]));
+    assert_eq!(color("f80"), Some([0xff, 0x88, 0x00]));
+    assert_eq!(color("#12345"), None);
+    assert_eq!(color("#-12"), None);
+  }
+
+  #[test]
+  fn others() {
+    assert_eq!(Duration::from_option("1h30m"), Some(Duration::from_secs(5400)));
+    assert_eq!(Duration::from_option("soon"), None);
+    assert_eq!(Tz::from_option("Asia/Tokyo"), Some(Tz::Asia__Tokyo));
+    assert!(Url::from_option("https://example.com").is_some());
+    assert!(Url::from_option("file:///etc/passwd").is_none());
+  }
+}
@@ -0,0 +1,144 @@
+use std::fmt::{Display, Write};
+
+// reply text, keyed by its english version, which is also what you get when there is no translation
+// discord locales are either just a language (`ru`) or a language with a region (`pt-BR`)
+#[rustfmt::skip]
+static STRINGS: &[(&str, &[(&str, &str)])] = &[
+  ("ru", &[
+    ("Error", "Ошибка"),
+    ("Slow down!", "Помедленнее!"),
+    ("Try again in {}", "Попробуйте снова через {}"),
+    ("This is way too much for the rate limit", "Это слишком много для лимита"),
+    ("{} uses left", "Осталось использований: {}"),
+    ("this command is owner-only", "эта команда только для владельца"),
+    ("this command only works on servers", "эта команда работает только на серверах"),
+    ("you need these permissions: {}", "вам нужны эти права: {}"),
+    ("this command only works in NSFW channels", "эта команда работает только в NSFW-каналах"),
+    ("you don't have a role that can use this command", "у вас нет роли, которой можно использовать эту команду"),
+    ("this command is disabled on this server", "эта команда отключена на этом сервере"),
+    ("could not find anything", "ничего не найдено"),
+    ("too ambiguous, be more specific", "слишком неоднозначно, уточните запрос"),
+    ("unknown location: {}", "неизвестное место: {}"),
+    ("unknown board: {}", "неизвестная доска: {}"),
+    ("/{}/ is NSFW, it can only be used in NSFW channels", "/{}/ — NSFW-доска, её можно использовать только в NSFW-каналах"),
+    ("this has expired, run the command again", "это устарело, запустите команду снова"),
+    ("this is not for you", "это не для вас"),
+    ("the message with the link is gone", "сообщения со ссылкой больше нет"),
+    ("cancelled", "отменено"),
+    ("Cancel", "Отмена"),
+    ("cancelling…", "отмена…"),
+    ("Restarting!", "Перезапускаюсь!"),
+    ("try again in a minute", "попробуйте снова через минуту"),
+    ("waiting in queue (#{})…", "ожидание в очереди (№{})…"),
+  ]),
+  ("ja", &[
+    ("Error", "エラー"),
+    ("Slow down!", "落ち着いて！"),
+    ("Try again in {}", "{}後にもう一度お試しください"),
+    ("This is way too much for the rate limit", "レート制限を大きく超えています"),
+    ("{} uses left", "残り{}回"),
+    ("this command is owner-only", "このコマンドはオーナー専用です"),
+    ("this command only works on servers", "このコマンドはサーバーでのみ使えます"),
+    ("you need these permissions: {}", "次の権限が必要です: {}"),
+    ("this command only works in NSFW channels", "このコマンドはNSFWチャンネルでのみ使えます"),
+    ("you don't have a role that can use this command", "このコマンドを使えるロールを持っていません"),
+    ("this command is disabled on this server", "このコマンドはこのサーバーで無効になっています"),
+    ("could not find anything", "何も見つかりませんでした"),
+    ("too ambiguous, be more specific", "曖昧すぎます、もっと具体的にしてください"),
+    ("unknown location: {}", "不明な場所: {}"),
+    ("unknown board: {}", "不明な板: {}"),
+    ("/{}/ is NSFW, it can only be used in NSFW channels", "/{}/はNSFWなので、NSFWチャンネルでのみ使えます"),
+    ("this has expired, run the command again", "期限切れです、もう一度コマンドを実行してください"),
+    ("this is not for you", "これはあなた向けではありません"),
+    ("the message with the link is gone", "リンクのあるメッセージはもうありません"),
+    ("cancelled", "キャンセルしました"),
+    ("Cancel", "キャンセル"),
+    ("cancelling…", "キャンセル中…"),
+    ("Restarting!", "再起動中！"),
+    ("try again in a minute", "1分後にもう一度お試しください"),
+    ("waiting in queue (#{})…", "順番待ち中（{}番目）…"),
+  ]),
+];
+
+pub fn translate(locale: &str, text: &'static str) -> &'static str {
+  let language = locale.split('-').next().unwrap_or(locale);
+  let table = STRINGS
+    .iter()
+    .find(|&&(l, _)| l == locale)
+    .or_else(|| STRINGS.iter().find(|&&(l, _)| l == language));
+  let translation = table.and_then(|(_, table)| table.iter().find(|&&(key, _)| key == text));
+  translation.map_or(text, |&(_, text)| text)
+}
+
+// a runtime `format!` replacement, only knows about positional `{}`
+pub fn format(template: &str, args: &[&dyn Display]) -> String {
+  let mut acc = String::with_capacity(template.len());
+  let mut args = args.iter();
+  let mut parts = template.split("{}");
+
+  acc.push_str(parts.next().unwrap_or_default());
+  for part in parts {
+    if let Some(arg) = args.next() {
+      write!(acc, "{arg}").unwrap();
+    }
+    acc.push_str(part);
+  }
+
+  acc
+}
+
+// translates and formats reply text according to the locale of whoever invoked the command
+macro_rules! tr {
+  ($ctx:expr, $text:literal) => {
+    $ctx.tr($text).to_owned()
+  };
+  ($ctx:expr, $text:literal, $($arg:expr),+ $(,)?) => {
+    $ctx.tr_format($text, &[$(&$arg),+])
+  };
+}
+
+pub(crate) use tr;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn translations() {
+    assert_eq!(translate("ru", "Error"), "Ошибка");
+    assert_eq!(translate("ja", "Cancel"), "キャンセル");
+    // regions fall back to the language, and anything else to english
+    assert_eq!(translate("ru-RU", "Error"), "Ошибка");
+    assert_eq!(translate("pt-BR", "Error"), "Error");
+    assert_eq!(translate("en-US", "Error"), "Error");
+    assert_eq!(translate("ru", "not translated"), "not translated");
+  }
+
+  #[test]
+  fn formatting() {
+    assert_eq!(format("{} uses left", &[&3]), "3 uses left");
+    assert_eq!(format("{}/{}", &[&"a", &1.5]), "a/1.5");
+    assert_eq!(format("no placeholders", &[&1]), "no placeholders");
+    // missing arguments are left out, instead of panicking at runtime
+    assert_eq!(format("{} and {}", &[&1]), "1 and ");
+    assert_eq!(format("", &[]), "");
+  }
+
+  // every language has the same strings, with as many placeholders as the english ones
+  #[test]
+  fn tables() {
+    fn keys(table: &[(&'static str, &str)]) -> Vec<&'static str> {
+      table.iter().map(|&(key, _)| key).collect()
+    }
+
+    let (_, first) = STRINGS[0];
+    for &(locale, table) in STRINGS {
+      assert_eq!(keys(table), keys(first), "{locale}");
+
+      for (key, value) in table {
+        let n = key.matches("{}").count();
+        assert_eq!(value.matches("{}").count(), n, "{locale}: {key}");
+      }
+    }
+  }
+}
@@ -0,0 +1,262 @@
+use ::serenity::all as serenity;
+use discord::limits::*;
+use serenity::CommandOptionType as T;
+
+use super::{ChoiceValue, Command, CommandOption, CommandTree, Commands, Localization};
+
+// called by `commands!` on every name, so that a bad one doesn't compile,
+// only non-ascii characters are let through as is, the rest is up to `validate_commands`,
+// context menu commands can only be at the top level, and their names can have spaces and capitals
+pub const fn validate_name(name: &str, top_level: bool) {
+  let bytes = name.as_bytes();
+  let (mut i, mut chars) = (0, 0);
+  while i < bytes.len() {
+    let b = bytes[i];
+    // continuation bytes don't start a new character
+    if b & 0xc0 != 0x80 {
+      chars += 1;
+    }
+    if !top_level && b.is_ascii() && !(b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_') {
+      panic!("subcommand names can only have lowercase letters, digits, `-` and `_`");
+    }
+    i += 1;
+  }
+  if chars == 0 || chars > NAME_LEN {
+    panic!("command names have to be from 1 to 32 characters long");
+  }
+}
+
+// everything that's wrong with the tree, each prefixed with the path of the command it's about
+pub fn validate_commands(commands: &Commands) -> Vec<String> {
+  let mut acc = Validator::default();
+  for (name, tree) in commands {
+    acc.total = 0;
+    let path = format!("/{name}");
+    acc.tree(tree, name, &path, 0);
+    if acc.total > TOTAL_LEN {
+      let total = acc.total;
+      acc.error(
+        &path,
+        format!("has {total} characters in total, at most {TOTAL_LEN} are allowed"),
+      );
+    }
+  }
+  acc.errors.sort();
+  acc.errors
+}
+
+// ---
+
+#[derive(Default)]
+struct Validator {
+  errors: Vec<String>,
+  total: usize,
+}
+
+impl Validator {
+  fn error(&mut self, path: &str, msg: impl AsRef<str>) {
+    self.errors.push(format!("{path}: {}", msg.as_ref()));
+  }
+
+  fn tree(&mut self, tree: &CommandTree, name: &str, path: &str, depth: usize) {
+    let context_menu = matches!(tree, CommandTree::Command(cmd) if cmd.kind != serenity::CommandType::ChatInput);
+    self.name(path, name, context_menu);
+
+    match tree {
+      CommandTree::Command(_) if context_menu && depth > 0 => self.error(path, "context menu commands can't be nested"),
+      CommandTree::Command(cmd) => self.command(path, cmd),
+      CommandTree::Commands(cmds) => {
+        // a command, a group, and a subcommand
+        if depth >= 2 {
+          self.error(path, "subcommand groups can't be nested any deeper");
+        }
+        if cmds.len() > OPTIONS {
+          self.error(
+            path,
+            format!("has {} subcommands, at most {OPTIONS} are allowed", cmds.len()),
+          );
+        }
+        for (name, tree) in cmds {
+          self.tree(tree, name, &format!("{path} {name}"), depth + 1);
+        }
+      }
+    }
+  }
+
+  fn command(&mut self, path: &str, cmd: &Command) {
+    self.description(path, cmd.description);
+    let context_menu = cmd.kind != serenity::CommandType::ChatInput;
+    self.localizations(path, cmd.localizations, context_menu);
+
+    if cmd.options.len() > OPTIONS {
+      let n = cmd.options.len();
+      self.error(path, format!("has {n} options, at most {OPTIONS} are allowed"));
+    }
+    if let Some(i) = cmd.options.iter().position(|opt| !opt.required) {
+      if let Some(opt) = cmd.options[i..].iter().find(|opt| opt.required) {
+        self.error(
+          path,
+          format!("`{}` is required, but comes after an optional one", opt.name),
+        );
+      }
+    }
+
+    for opt in &cmd.options {
+      self.option(&format!("{path} `{}`", opt.name), opt);
+    }
+  }
+
+  fn option(&mut self, path: &str, opt: &CommandOption) {
+    self.name(path, opt.name, false);
+    self.description(path, opt.description);
+    self.localizations(path, opt.localizations, false);
+
+    let choices = opt.choices.unwrap_or_default();
+    if choices.len() > CHOICES {
+      let n = choices.len();
+      self.error(path, format!("has {n} choices, at most {CHOICES} are allowed"));
+    }
+    for choice in choices {
+      self.total += choice.name.chars().count();
+      if choice.name.is_empty() || choice.name.chars().count() > CHOICE_LEN {
+        self.error(
+          path,
+          format!("choice names have to be from 1 to {CHOICE_LEN} characters long"),
+        );
+      }
+      if let ChoiceValue::String(value) = choice.value {
+        self.total += value.chars().count();
+        if value.chars().count() > CHOICE_LEN {
+          self.error(
+            path,
+            format!("choice values can't be longer than {CHOICE_LEN} characters"),
+          );
+        }
+      }
+    }
+
+    let lengths = [opt.min_length, opt.max_length];
+    if lengths.iter().flatten().any(|&n| n > STRING_LEN) {
+      self.error(path, format!("lengths can't be greater than {STRING_LEN}"));
+    }
+    if lengths.iter().any(Option::is_some) && opt.ty != T::String {
+      self.error(path, "only strings can have a length");
+    }
+    if !opt.channel_types.is_empty() && opt.ty != T::Channel {
+      self.error(path, "only channels can have channel types");
+    }
+  }
+
+  fn name(&mut self, path: &str, name: &str, context_menu: bool) {
+    self.total += name.chars().count();
+    self.valid_name(path, name, context_menu);
+  }
+
+  // lowercase where there's a lowercase, except for context menus,
+  // localized names get checked too, but don't count towards the total, that's per locale
+  fn valid_name(&mut self, path: &str, name: &str, context_menu: bool) {
+    if name.is_empty() || name.chars().count() > NAME_LEN {
+      self.error(path, format!("names have to be from 1 to {NAME_LEN} characters long"));
+    }
+    let valid = |c: char| (c.is_alphanumeric() || c == '-' || c == '_') && !c.is_uppercase();
+    if !context_menu && !name.chars().all(valid) {
+      self.error(path, "names can only have lowercase letters, digits, `-` and `_`");
+    }
+  }
+
+  fn description(&mut self, path: &str, description: Option<&str>) {
+    let n = description.unwrap_or_default().chars().count();
+    self.total += n;
+    if n > DESCRIPTION_LEN {
+      self.error(
+        path,
+        format!("descriptions can't be longer than {DESCRIPTION_LEN} characters"),
+      );
+    }
+  }
+
+  fn localizations(&mut self, path: &str, localizations: &[Localization], context_menu: bool) {
+    for l10n in localizations {
+      let path = format!("{path} ({})", l10n.locale);
+      if let Some(name) = l10n.name {
+        self.valid_name(&path, name, context_menu);
+      }
+      if l10n
+        .description
+        .is_some_and(|desc| desc.chars().count() > DESCRIPTION_LEN)
+      {
+        self.error(
+          &path,
+          format!("descriptions can't be longer than {DESCRIPTION_LEN} characters"),
+        );
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::client::{Context, Result};
+
+  #[macros::command(desc = "Does nothing")]
+  async fn noop(_ctx: &Context<'_>) -> Result<()> {
+    Ok(())
+  }
+
+  #[macros::command(context_menu)]
+  async fn menu(_ctx: &Context<'_>, _user: &serenity::User) -> Result<()> {
+    Ok(())
+  }
+
+  #[test]
+  fn names() {
+    const _: () = validate_name("as-file", false);
+    const _: () = validate_name("Status history", true);
+    const _: () = validate_name("история", false);
+  }
+
+  #[test]
+  fn tree() {
+    let group = |cmds: Vec<(&'static str, CommandTree)>| CommandTree::Commands(cmds.into_iter().collect());
+    let many = (0..26)
+      .map(|i| (&*format!("n{i}").leak(), CommandTree::Command(noop("n"))))
+      .collect();
+    let l10n = |name| Localization {
+      locale: "ru",
+      name: Some(name),
+      description: None,
+    };
+    let mut localized = noop("localized");
+    localized.localizations = vec![l10n("Плохо")].leak();
+    let mut localized_menu = menu("Status history");
+    localized_menu.localizations = vec![l10n("История статусов")].leak();
+    let commands = Commands::from([
+      ("ok", CommandTree::Command(noop("ok"))),
+      ("localized", CommandTree::Command(localized)),
+      ("Status", CommandTree::Command(localized_menu)),
+      ("Status history", CommandTree::Command(menu("Status history"))),
+      ("Bad", CommandTree::Command(noop("Bad"))),
+      (
+        "deep",
+        group(vec![(
+          "a",
+          group(vec![("b", group(vec![("c", CommandTree::Command(noop("c")))]))]),
+        )]),
+      ),
+      ("nested", group(vec![("menu", CommandTree::Command(menu("menu")))])),
+      ("many", group(many)),
+    ]);
+
+    assert_eq!(
+      validate_commands(&commands),
+      [
+        "/Bad: names can only have lowercase letters, digits, `-` and `_`",
+        "/deep a b: subcommand groups can't be nested any deeper",
+        "/localized (ru): names can only have lowercase letters, digits, `-` and `_`",
+        "/many: has 26 subcommands, at most 25 are allowed",
+        "/nested menu: context menu commands can't be nested",
+      ]
+    );
+  }
+}
@@ -0,0 +1,294 @@
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::fmt::{self, Display, Formatter, Write};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ::serenity::all as serenity;
+use ::util::network::Http;
+use cache::LruFileCache;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{header, Body, Method, Request, Response, StatusCode};
+use parking_lot::Mutex;
+use python::gil;
+
+use super::Result;
+
+// upper bounds of the latency buckets in seconds, downloads can take minutes
+const BUCKETS: [f64; 12] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0];
+
+// what gets measured along the way, the rest is read from where it lives when it's scraped
+#[derive(Debug, Default)]
+pub struct Metrics {
+  commands: Mutex<BTreeMap<String, Histogram>>,
+  errors: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Sources {
+  pub cache: Arc<LruFileCache>,
+  pub http: Http,
+  pub shard_manager: Arc<serenity::ShardManager>,
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+  buckets: [u64; BUCKETS.len()],
+  sum: f64,
+  count: u64,
+}
+
+impl Metrics {
+  pub fn command(&self, path: &str, elapsed: Duration) {
+    let mut commands = self.commands.lock();
+    let histogram = match commands.get_mut(path) {
+      Some(histogram) => histogram,
+      None => commands.entry(path.to_owned()).or_default(),
+    };
+    histogram.observe(elapsed.as_secs_f64());
+  }
+
+  // `kind` is one of "message", "ratelimit", "internal", "python" or "panic"
+  pub fn error(&self, kind: &'static str) {
+    *self.errors.lock().entry(kind).or_default() += 1;
+  }
+
+  // in the prometheus text format
+  pub async fn render(&self, sources: &Sources) -> Result<String> {
+    let mut acc = String::new();
+    self.render_measured(&mut acc)?;
+
+    let cache = sources.cache.stats();
+    header(&mut acc, "cache_files", "gauge", "Files in the file cache.")?;
+    writeln!(acc, "riamu_cache_files {}", cache.files)?;
+    header(&mut acc, "cache_bytes", "gauge", "Bytes taken by the file cache.")?;
+    writeln!(acc, "riamu_cache_bytes {}", cache.bytes_stored)?;
+    header(
+      &mut acc,
+      "cache_bytes_limit",
+      "gauge",
+      "How big the file cache is allowed to get.",
+    )?;
+    writeln!(acc, "riamu_cache_bytes_limit {}", cache.bytes_limit)?;
+
+    let responses = sources.http.cache_stats();
+    header(
+      &mut acc,
+      "response_cache_hits_total",
+      "counter",
+      "Third party responses served from the cache.",
+    )?;
+    for (source, stats) in &responses {
+      writeln!(
+        acc,
+        "riamu_response_cache_hits_total{{source=\"{}\"}} {}",
+        Label(source),
+        stats.hits
+      )?;
+    }
+    header(
+      &mut acc,
+      "response_cache_misses_total",
+      "counter",
+      "Third party responses that had to be fetched.",
+    )?;
+    for (source, stats) in &responses {
+      writeln!(
+        acc,
+        "riamu_response_cache_misses_total{{source=\"{}\"}} {}",
+        Label(source),
+        stats.misses
+      )?;
+    }
+
+    header(
+      &mut acc,
+      "gateway_latency_seconds",
+      "gauge",
+      "Time between the last heartbeat and its ack.",
+    )?;
+    for (id, runner) in sources.shard_manager.runners.lock().await.iter() {
+      if let Some(latency) = runner.latency {
+        let latency = latency.as_secs_f64();
+        writeln!(acc, "riamu_gateway_latency_seconds{{shard=\"{}\"}} {}", id, latency)?;
+      }
+    }
+
+    let gil = gil::stats();
+    header(
+      &mut acc,
+      "python_gil_acquired_total",
+      "counter",
+      "Times the GIL has been acquired.",
+    )?;
+    writeln!(acc, "riamu_python_gil_acquired_total {}", gil.acquired)?;
+    header(
+      &mut acc,
+      "python_gil_wait_seconds_total",
+      "counter",
+      "Time spent waiting for the GIL.",
+    )?;
+    writeln!(acc, "riamu_python_gil_wait_seconds_total {}", gil.waited.as_secs_f64())?;
+
+    Ok(acc)
+  }
+
+  fn render_measured(&self, acc: &mut String) -> fmt::Result {
+    let commands = self.commands.lock();
+
+    header(
+      acc,
+      "commands_total",
+      "counter",
+      "Commands handled, including their components.",
+    )?;
+    for (path, histogram) in commands.iter() {
+      writeln!(
+        acc,
+        "riamu_commands_total{{command=\"{}\"}} {}",
+        Label(path),
+        histogram.count
+      )?;
+    }
+
+    header(
+      acc,
+      "command_duration_seconds",
+      "histogram",
+      "Time it took to handle a command.",
+    )?;
+    for (path, histogram) in commands.iter() {
+      let path = Label(path);
+      for (le, count) in BUCKETS.iter().zip(histogram.buckets) {
+        writeln!(
+          acc,
+          "riamu_command_duration_seconds_bucket{{command=\"{path}\",le=\"{le}\"}} {count}"
+        )?;
+      }
+      let count = histogram.count;
+      writeln!(
+        acc,
+        "riamu_command_duration_seconds_bucket{{command=\"{path}\",le=\"+Inf\"}} {count}"
+      )?;
+      writeln!(
+        acc,
+        "riamu_command_duration_seconds_sum{{command=\"{path}\"}} {}",
+        histogram.sum
+      )?;
+      writeln!(
+        acc,
+        "riamu_command_duration_seconds_count{{command=\"{path}\"}} {count}"
+      )?;
+    }
+
+    header(
+      acc,
+      "command_errors_total",
+      "counter",
+      "Commands that failed, by the kind of error.",
+    )?;
+    for (kind, count) in self.errors.lock().iter() {
+      writeln!(acc, "riamu_command_errors_total{{kind=\"{kind}\"}} {count}")?;
+    }
+
+    Ok(())
+  }
+}
+
+impl Histogram {
+  // buckets are cumulative
+  fn observe(&mut self, value: f64) {
+    for (le, bucket) in BUCKETS.iter().zip(&mut self.buckets) {
+      if value <= *le {
+        *bucket += 1;
+      }
+    }
+    self.sum += value;
+    self.count += 1;
+  }
+}
+
+// only `GET /metrics`, it's meant to be reachable from the scraper and nothing else
+pub async fn serve(addr: SocketAddr, metrics: Arc<Metrics>, sources: Sources) -> Result<()> {
+  let make_service = make_service_fn(move |_| {
+    let (metrics, sources) = (metrics.clone(), sources.clone());
+    let service = service_fn(move |req| {
+      let (metrics, sources) = (metrics.clone(), sources.clone());
+      async move { Ok::<_, Infallible>(respond(&metrics, &sources, req).await) }
+    });
+    async move { Ok::<_, Infallible>(service) }
+  });
+
+  let server = hyper::Server::try_bind(&addr)?.serve(make_service);
+  tracing::info!(%addr, "serving metrics");
+  Ok(server.await?)
+}
+
+async fn respond(metrics: &Metrics, sources: &Sources, req: Request<Body>) -> Response<Body> {
+  let (status, body) = match (req.method(), req.uri().path()) {
+    (&Method::GET, "/metrics") => match metrics.render(sources).await {
+      Ok(text) => (StatusCode::OK, text),
+      Err(err) => {
+        tracing::error!("failed to render metrics: {err}");
+        (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+      }
+    },
+    _ => (StatusCode::NOT_FOUND, "not found".to_owned()),
+  };
+
+  let response = Response::builder().status(status);
+  let response = response.header(header::CONTENT_TYPE, "text/plain; version=0.0.4");
+  response.body(body.into()).unwrap()
+}
+
+fn header(acc: &mut String, name: &str, ty: &str, help: &str) -> fmt::Result {
+  writeln!(acc, "# HELP riamu_{name} {help}")?;
+  writeln!(acc, "# TYPE riamu_{name} {ty}")
+}
+
+// label values are quoted, so quotes, backslashes and newlines have to be escaped
+struct Label<'a>(&'a str);
+
+impl Display for Label<'_> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    for c in self.0.chars() {
+      match c {
+        '\\' => f.write_str(r"\\")?,
+        '"' => f.write_str(r#"\""#)?,
+        '\n' => f.write_str(r"\n")?,
+        c => f.write_char(c)?,
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn render() {
+    let metrics = Metrics::default();
+    metrics.command("/weather", Duration::from_millis(300));
+    metrics.command("/weather", Duration::from_secs(1000));
+    metrics.command("/text \"quoted\"", Duration::ZERO);
+    metrics.error("message");
+    metrics.error("message");
+
+    let mut acc = String::new();
+    metrics.render_measured(&mut acc).unwrap();
+    let lines = acc.lines().collect::<Vec<_>>();
+
+    assert!(lines.contains(&r#"riamu_commands_total{command="/weather"} 2"#));
+    assert!(lines.contains(&r#"riamu_commands_total{command="/text \"quoted\""} 1"#));
+    assert!(lines.contains(&r#"riamu_command_duration_seconds_bucket{command="/weather",le="0.25"} 0"#));
+    assert!(lines.contains(&r#"riamu_command_duration_seconds_bucket{command="/weather",le="0.5"} 1"#));
+    assert!(lines.contains(&r#"riamu_command_duration_seconds_bucket{command="/weather",le="300"} 1"#));
+    assert!(lines.contains(&r#"riamu_command_duration_seconds_bucket{command="/weather",le="+Inf"} 2"#));
+    assert!(lines.contains(&r#"riamu_command_duration_seconds_sum{command="/weather"} 1000.3"#));
+    assert!(lines.contains(&r#"riamu_command_errors_total{kind="message"} 2"#));
+    assert!(lines.contains(&"# TYPE riamu_command_duration_seconds histogram"));
+  }
+}
@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::pin::pin;
+use std::sync::Mutex;
+
+use ::serenity::all as serenity;
+use tokio::sync::Notify;
+
+use super::{tr, Context};
+
+// heavy jobs of the same kind wait for each other instead of all running at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Job {
+  Download,
+  Deezer,
+  TikTok,
+}
+
+impl Job {
+  pub const ALL: [Self; 3] = [Self::Download, Self::Deezer, Self::TikTok];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Self::Download => "download",
+      Self::Deezer => "deezer",
+      Self::TikTok => "tiktok",
+    }
+  }
+
+  // how many jobs of this kind can run at the same time
+  pub fn limit(self) -> usize {
+    match self {
+      Self::Download => 2,
+      Self::Deezer => 2,
+      Self::TikTok => 4,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Stats {
+  pub job: Job,
+  pub running: usize,
+  pub waiting: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct Queue {
+  state: Mutex<HashMap<Job, State>>,
+  notify: Notify,
+}
+
+#[derive(Debug, Default)]
+struct State {
+  next_ticket: u64,
+  running: Vec<serenity::UserId>,
+  waiting: Vec<(u64, serenity::UserId)>,
+}
+
+impl State {
+  // users with fewer running jobs go first, so that one user can't take over the whole queue
+  fn order(&self) -> Vec<u64> {
+    let running = |user| self.running.iter().filter(|&&u| u == user).count();
+    let mut waiting = self.waiting.clone();
+    waiting.sort_by_key(|&(ticket, user)| (running(user), ticket));
+    waiting.into_iter().map(|(ticket, _)| ticket).collect()
+  }
+}
+
+impl Queue {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  // waits for a free slot, showing the position in the queue meanwhile,
+  // the slot is taken until the returned permit is dropped
+  pub async fn acquire<'a>(&'a self, ctx: &Context<'_>, job: Job) -> serenity::Result<Permit<'a>> {
+    let user = ctx.event.user.id;
+    let ticket = self.with(job, |state| {
+      let ticket = state.next_ticket;
+      state.next_ticket += 1;
+      state.waiting.push((ticket, user));
+      ticket
+    });
+    let _waiting = Waiting {
+      queue: self,
+      job,
+      ticket,
+    };
+
+    let mut shown = None;
+    loop {
+      // has to be registered before checking, so that a release in between doesn't get missed
+      let mut notified = pin!(self.notify.notified());
+      notified.as_mut().enable();
+
+      let position = self.with(job, |state| {
+        let position = state.order().iter().position(|&t| t == ticket).unwrap();
+        let free = job.limit().saturating_sub(state.running.len());
+        if position < free {
+          state.waiting.retain(|&(t, _)| t != ticket);
+          state.running.push(user);
+          None
+        } else {
+          Some(position + 1)
+        }
+      });
+
+      let Some(position) = position else {
+        return Ok(Permit { queue: self, job, user });
+      };
+
+      if shown != Some(position) {
+        tracing::debug!(job = job.name(), position, "queued");
+        // the arguments aren't `Send`, so they can't be kept across the await
+        let text = tr!(ctx, "waiting in queue (#{})…", position);
+        ctx.progress(text).await?;
+        shown = Some(position);
+      }
+
+      notified.await;
+    }
+  }
+
+  pub fn stats(&self) -> Vec<Stats> {
+    let state = self.state.lock().unwrap();
+    let stats = Job::ALL.into_iter().map(|job| {
+      let (running, waiting) = state.get(&job).map_or((0, 0), |s| (s.running.len(), s.waiting.len()));
+      Stats { job, running, waiting }
+    });
+    stats.collect()
+  }
+
+  fn with<T>(&self, job: Job, f: impl FnOnce(&mut State) -> T) -> T {
+    f(self.state.lock().unwrap().entry(job).or_default())
+  }
+}
+
+pub struct Permit<'a> {
+  queue: &'a Queue,
+  job: Job,
+  user: serenity::UserId,
+}
+
+impl Drop for Permit<'_> {
+  fn drop(&mut self) {
+    self.queue.with(self.job, |state| {
+      if let Some(i) = state.running.iter().position(|&u| u == self.user) {
+        state.running.swap_remove(i);
+      }
+    });
+    self.queue.notify.notify_waiters();
+  }
+}
+
+// leaves the queue if waiting gets interrupted (e.g. by an error while showing the position),
+// once the slot is taken this doesn't do anything
+struct Waiting<'a> {
+  queue: &'a Queue,
+  job: Job,
+  ticket: u64,
+}
+
+impl Drop for Waiting<'_> {
+  fn drop(&mut self) {
+    let ticket = self.ticket;
+    self
+      .queue
+      .with(self.job, |state| state.waiting.retain(|&(t, _)| t != ticket));
+    self.queue.notify.notify_waiters();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use serde_json::json;
+  use tokio::time;
+
+  use super::*;
+  use crate::client::testing::Harness;
+  use crate::client::Result;
+
+  #[macros::command(desc = "Does nothing")]
+  async fn noop(_ctx: &Context<'_>) -> Result<()> {
+    Ok(())
+  }
+
+  fn running(queue: &Queue, job: Job) -> (usize, usize) {
+    let stats = queue.stats().into_iter().find(|s| s.job == job).unwrap();
+    (stats.running, stats.waiting)
+  }
+
+  #[test]
+  fn order() {
+    let [a, b, c] = [1, 2, 3].map(serenity::UserId::new);
+    let state = State {
+      next_ticket: 4,
+      running: vec![a, a, b],
+      waiting: vec![(0, a), (1, b), (2, c), (3, c)],
+    };
+    // whoever has nothing running goes first, then in the order they came in
+    assert_eq!(state.order(), [2, 3, 1, 0]);
+  }
+
+  #[tokio::test]
+  async fn slots() {
+    let mut harness = Harness::new().await;
+    let event = harness.event(noop, json!({})).unwrap();
+    let ctx = Context::new(&harness.client, &harness.discord.context, &event);
+    let queue = &harness.client.queue;
+    ctx.defer().await.unwrap();
+
+    // two deezer jobs can run at once
+    let first = queue.acquire(&ctx, Job::Deezer).await.unwrap();
+    let _second = queue.acquire(&ctx, Job::Deezer).await.unwrap();
+    assert_eq!(running(queue, Job::Deezer), (2, 0));
+    assert_eq!(running(queue, Job::TikTok), (0, 0));
+
+    let mut third = pin!(queue.acquire(&ctx, Job::Deezer));
+    assert!(time::timeout(Duration::from_millis(100), &mut third).await.is_err());
+    assert_eq!(running(queue, Job::Deezer), (2, 1));
+
+    let replies = harness.replies();
+    let label = &replies.last().unwrap()["components"][0]["components"][0]["label"];
+    assert_eq!(label, "waiting in queue (#1)…");
+
+    drop(first);
+    let _third = third.await.unwrap();
+    assert_eq!(running(queue, Job::Deezer), (2, 0));
+  }
+
+  #[tokio::test]
+  async fn interrupted() {
+    let mut harness = Harness::new().await;
+    let event = harness.event(noop, json!({})).unwrap();
+    let ctx = Context::new(&harness.client, &harness.discord.context, &event);
+    let queue = &harness.client.queue;
+    ctx.defer().await.unwrap();
+
+    let _permits = [
+      queue.acquire(&ctx, Job::Download).await.unwrap(),
+      queue.acquire(&ctx, Job::Download).await.unwrap(),
+    ];
+    let waiting = time::timeout(Duration::from_millis(100), queue.acquire(&ctx, Job::Download));
+    assert!(waiting.await.is_err());
+
+    // giving up leaves the queue, and doesn't take a slot
+    assert_eq!(running(queue, Job::Download), (2, 0));
+    let state = queue.state.lock().unwrap();
+    assert_eq!(state[&Job::Download].next_ticket, 3);
+  }
+}
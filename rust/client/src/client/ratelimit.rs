@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use ::serenity::all as serenity;
+use gcra::{Rate, Retry};
+
+use super::util::CommandPathDisplay;
+use super::{CommandError, Context, Result};
+use crate::db::{self, ratelimits};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Ratelimit {
+  pub scope: Scope,
+  pub rate: Rate,
+  pub cost: f64,
+  // limits are per command unless they're `shared`
+  pub bucket: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+  Global,
+  Guild,
+  User,
+}
+
+impl Ratelimit {
+  pub const fn new(scope: Scope, rate: Rate) -> Self {
+    Self {
+      scope,
+      rate,
+      cost: 1.0,
+      bucket: None,
+    }
+  }
+
+  pub const fn cost(self, cost: f64) -> Self {
+    Self { cost, ..self }
+  }
+
+  // commands with the same bucket count against the same limit, e.g. two ways to download the same thing
+  pub const fn shared(self, bucket: &'static str) -> Self {
+    Self {
+      bucket: Some(bucket),
+      ..self
+    }
+  }
+
+  // what the limit is counted under, command paths start with a slash so they can't clash with buckets
+  fn name<'a>(&self, path: &'a str) -> &'a str {
+    self.bucket.unwrap_or(path)
+  }
+}
+
+impl Scope {
+  fn id(self, event: &serenity::CommandInteraction) -> u64 {
+    match self {
+      Self::Global => 0,
+      Self::Guild => event.guild_id.map_or(event.channel_id.get(), |id| id.get()),
+      Self::User => event.user.id.get(),
+    }
+  }
+}
+
+type Key = (String, Scope, u64);
+
+pub async fn check(ctx: &Context<'_>) -> Result<()> {
+  let path = CommandPathDisplay(&ctx.event.data).to_string();
+  let limits = ctx.command.ratelimits.iter();
+  let limits = limits.map(|rl| (*rl, (rl.name(&path).to_owned(), rl.scope, rl.scope.id(ctx.event))));
+  conform(&ctx.client.db, &limits.collect::<Vec<_>>()).await
+}
+
+// all the limits of a command have to conform, if any of them doesn't
+// then the ones that were already updated get rolled back (negative `n`)
+async fn conform(db: &db::Pool, limits: &[(Ratelimit, Key)]) -> Result<()> {
+  for (i, (rl, key)) in limits.iter().enumerate() {
+    let (result, info) = ratelimits::update_n(db, key, rl.rate, rl.cost).await?;
+    tracing::debug!(scope = ?rl.scope, "ratelimit: {:.1} remaining", info.remaining());
+
+    if let Err(retry) = result {
+      for (rl, key) in &limits[..i] {
+        let _ = ratelimits::update_n(db, key, rl.rate, -rl.cost).await?;
+      }
+
+      let after = match retry {
+        Retry::After(after) => Some(after),
+        Retry::Never => None,
+      };
+      let remaining = info.remaining().max(0.0);
+      return Err(CommandError::Ratelimit { after, remaining }.into());
+    }
+  }
+
+  Ok(())
+}
+
+pub fn retry_after(after: Duration) -> String {
+  // `fmt::dhms` truncates, so round up to not tell people to retry too early
+  let secs = after.as_secs() + (after.subsec_nanos() > 0) as u64;
+  fmt::dhms(secs)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn key(bucket: &str, scope: Scope) -> Key {
+    (bucket.to_owned(), scope, 1)
+  }
+
+  #[tokio::test]
+  async fn limits() {
+    let db = db::memory().await.unwrap();
+    let minute = Duration::from_secs(60);
+    let user = Ratelimit::new(Scope::User, Rate::new(2.0, minute));
+    let global = Ratelimit::new(Scope::Global, Rate::new(1.0, minute));
+    let limits = [(user, key("/a", Scope::User)), (global, key("/a", Scope::Global))];
+
+    conform(&db, &limits).await.unwrap();
+    let err = conform(&db, &limits).await.unwrap_err();
+    assert!(matches!(
+      err.downcast_ref::<CommandError>(),
+      Some(CommandError::Ratelimit { after: Some(_), .. })
+    ));
+
+    // the user limit got its second use back when the global one refused
+    conform(&db, &limits[..1]).await.unwrap();
+    assert!(conform(&db, &limits[..1]).await.is_err());
+
+    // other commands have limits of their own
+    conform(&db, &[(global, key("/b", Scope::Global))]).await.unwrap();
+  }
+
+  #[tokio::test]
+  async fn costs() {
+    let db = db::memory().await.unwrap();
+    let rl = Ratelimit::new(Scope::User, Rate::new(3.0, Duration::from_secs(60))).cost(2.0);
+    let limits = [(rl, key("/a", Scope::User))];
+
+    conform(&db, &limits).await.unwrap();
+    assert!(conform(&db, &limits).await.is_err());
+  }
+
+  #[tokio::test]
+  async fn buckets() {
+    let db = db::memory().await.unwrap();
+    let rl = Ratelimit::new(Scope::Global, Rate::new(1.0, Duration::from_secs(60)));
+    assert_eq!(rl.name("/a"), "/a");
+
+    let rl = rl.shared("a");
+    assert_eq!(rl.name("/a"), "a");
+    conform(&db, &[(rl, key(rl.name("/a"), Scope::Global))]).await.unwrap();
+    let err = conform(&db, &[(rl, key(rl.name("/b"), Scope::Global))]).await;
+    assert!(err.is_err());
+  }
+}
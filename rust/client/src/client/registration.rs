@@ -0,0 +1,267 @@
+use std::collections::{HashMap, HashSet};
+
+use ::serenity::all as serenity;
+use serde_json::Value;
+
+use super::{commands, Client, Command, Commands, Registration, Result};
+use crate::db::guild_commands;
+
+#[derive(Debug, Clone, Copy)]
+enum Target {
+  Global,
+  Guild(serenity::GuildId),
+}
+
+impl Target {
+  async fn get(self, http: &serenity::Http) -> serenity::Result<Vec<serenity::Command>> {
+    match self {
+      Self::Global => serenity::Command::get_global_commands(http).await,
+      Self::Guild(id) => id.get_commands(http).await,
+    }
+  }
+
+  async fn create(self, http: &serenity::Http, cmd: serenity::CreateCommand) -> serenity::Result<serenity::Command> {
+    match self {
+      Self::Global => serenity::Command::create_global_command(http, cmd).await,
+      Self::Guild(id) => id.create_command(http, cmd).await,
+    }
+  }
+
+  async fn delete(self, http: &serenity::Http, cmd: serenity::CommandId) -> serenity::Result<()> {
+    match self {
+      Self::Global => serenity::Command::delete_global_command(http, cmd).await,
+      Self::Guild(id) => id.delete_command(http, cmd).await,
+    }
+  }
+}
+
+// a top-level command is registered globally only if all of its subcommands are global,
+// otherwise it gets registered per guild with only the subcommands enabled there
+pub async fn global(client: &Client, http: &serenity::Http) -> Result<()> {
+  let global = global_names(&client.commands);
+  let commands = commands::serialize(&client.commands, |path, cmd| {
+    global.contains(top_level(path)) && is_available(client, cmd)
+  });
+  sync(Target::Global, http, commands).await
+}
+
+pub async fn guild(client: &Client, http: &serenity::Http, gid: serenity::GuildId) -> Result<()> {
+  let global = global_names(&client.commands);
+  let toggles = toggles(client, gid).await?;
+  let is_dev = gid == client.config.discord_dev_server;
+
+  let commands = commands::serialize(&client.commands, |path, cmd| {
+    !global.contains(top_level(path)) &&
+      is_available(client, cmd) &&
+      is_enabled(cmd, toggles.get(path).copied(), is_dev)
+  });
+  sync(Target::Guild(gid), http, commands).await
+}
+
+// commands in DMs are always global, so there is nothing to toggle there
+pub async fn enabled(client: &Client, gid: Option<serenity::GuildId>, path: &str, cmd: &Command) -> Result<bool> {
+  if !is_available(client, cmd) {
+    return Ok(false);
+  }
+  let Some(gid) = gid else {
+    return Ok(cmd.registration == Registration::Global);
+  };
+  let toggle = guild_commands::get(&client.db, gid, path).await?;
+  Ok(is_enabled(cmd, toggle, gid == client.config.discord_dev_server))
+}
+
+// the same as `enabled` for every command at once, with the toggles loaded only once
+pub async fn all_enabled(client: &Client, gid: Option<serenity::GuildId>) -> Result<Vec<(String, &Command)>> {
+  let toggles = match gid {
+    Some(gid) => toggles(client, gid).await?,
+    None => HashMap::new(),
+  };
+  let is_dev = gid == Some(client.config.discord_dev_server);
+
+  let commands = commands::walk(&client.commands).into_iter();
+  let commands = commands.filter(|(path, cmd)| {
+    is_available(client, cmd) &&
+      match gid {
+        Some(_) => is_enabled(cmd, toggles.get(path).copied(), is_dev),
+        None => cmd.registration == Registration::Global,
+      }
+  });
+  Ok(commands.collect())
+}
+
+// commands that need a third party service that isn't configured don't show up anywhere
+fn is_available(client: &Client, cmd: &Command) -> bool {
+  cmd.requires.is_none_or(|integration| client.config.has(integration))
+}
+
+fn is_enabled(cmd: &Command, toggle: Option<bool>, is_dev: bool) -> bool {
+  match cmd.registration {
+    Registration::Global => toggle.unwrap_or(true),
+    Registration::Guild => toggle.unwrap_or(false),
+    Registration::Dev => is_dev,
+  }
+}
+
+async fn toggles(client: &Client, gid: serenity::GuildId) -> Result<HashMap<String, bool>> {
+  let rows = guild_commands::all(&client.db, gid).await?;
+  Ok(rows.into_iter().map(|row| (row.command, row.enabled)).collect())
+}
+
+fn global_names(commands: &Commands) -> HashSet<&str> {
+  let all = commands::walk(commands);
+  let mut names = all.iter().map(|(path, _)| top_level(path)).collect::<HashSet<_>>();
+  for (path, cmd) in &all {
+    if cmd.registration != Registration::Global {
+      names.remove(top_level(path));
+    }
+  }
+  names
+    .into_iter()
+    .map(|name| commands.get_key_value(name).unwrap().0)
+    .copied()
+    .collect()
+}
+
+fn top_level(path: &str) -> &str {
+  let path = path.trim_start_matches('/');
+  path.split(' ').next().unwrap_or(path)
+}
+
+// ---
+
+async fn sync(target: Target, http: &serenity::Http, commands: Vec<serenity::CreateCommand>) -> Result<()> {
+  // chat input and context menu commands can have the same name
+  let key = |cmd: &Value| {
+    let kind = cmd["type"].as_u64().unwrap_or(1);
+    (kind, cmd["name"].as_str().unwrap_or_default().to_owned())
+  };
+
+  let mut existing = HashMap::new();
+  for cmd in target.get(http).await? {
+    let payload = payload(serde_json::to_value(&cmd)?);
+    existing.insert(key(&payload), (cmd.id, payload));
+  }
+
+  let (mut created, mut deleted, mut unchanged) = (0, 0, 0);
+  let mut keys = HashSet::new();
+
+  for cmd in commands {
+    let desired = payload(serde_json::to_value(&cmd)?);
+    let key = key(&desired);
+
+    match existing.get(&key) {
+      Some((_, old)) if same(&desired, old) => unchanged += 1,
+      _ => {
+        target.create(http, cmd).await?;
+        created += 1;
+      }
+    }
+
+    keys.insert(key);
+  }
+
+  for (key, (id, _)) in &existing {
+    if !keys.contains(key) {
+      target.delete(http, *id).await?;
+      deleted += 1;
+    }
+  }
+
+  tracing::debug!(?target, created, deleted, unchanged, "registered commands");
+
+  Ok(())
+}
+
+// what gets sent when registering, discord adds ids, versions and such of its own
+const FIELDS: &[&str] = &[
+  "type",
+  "name",
+  "name_localizations",
+  "description",
+  "description_localizations",
+  "options",
+  "default_member_permissions",
+  "dm_permission",
+  "nsfw",
+];
+
+// only what's sent, without what's the same as leaving it out
+fn payload(mut cmd: Value) -> Value {
+  if let Value::Object(fields) = &mut cmd {
+    fields.retain(|key, _| FIELDS.contains(&key.as_str()));
+    if fields.get("type") == Some(&Value::from(1)) {
+      fields.remove("type");
+    }
+    if fields.get("dm_permission") == Some(&Value::Bool(true)) {
+      fields.remove("dm_permission");
+    }
+  }
+  cmd
+}
+
+// discord omits some fields when they're falsy, and sends some others as null or empty,
+// so a field that's missing on one side is the same as a falsy one on the other
+fn same(a: &Value, b: &Value) -> bool {
+  match (a, b) {
+    (Value::Object(a), Value::Object(b)) => {
+      let mut keys = a.keys().chain(b.keys());
+      keys.all(|key| match (a.get(key), b.get(key)) {
+        (Some(v), Some(w)) => same(v, w),
+        (Some(v), None) | (None, Some(v)) => is_falsy(v),
+        (None, None) => true,
+      })
+    }
+    (Value::Array(a), Value::Array(b)) => a.len() == b.len() && a.iter().zip(b).all(|(v, w)| same(v, w)),
+    (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+    (a, b) => a == b || (is_falsy(a) && is_falsy(b)),
+  }
+}
+
+fn is_falsy(value: &Value) -> bool {
+  match value {
+    Value::Null | Value::Bool(false) => true,
+    Value::Array(a) => a.is_empty(),
+    Value::Object(o) => o.is_empty(),
+    _ => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn payloads() {
+    let existing = payload(json!({
+      "id": "1",
+      "application_id": "2",
+      "version": "3",
+      "type": 1,
+      "name": "ban",
+      "description": "Bans",
+      "name_localizations": null,
+      "default_member_permissions": "4",
+      "dm_permission": true,
+      "nsfw": false,
+      "options": [{ "type": 3, "name": "reason", "description": "Why", "required": false, "choices": [] }],
+    }));
+
+    let desired = json!({
+      "name": "ban",
+      "description": "Bans",
+      "default_member_permissions": "4",
+      "options": [{ "type": 3, "name": "reason", "description": "Why" }],
+    });
+    assert!(same(&payload(desired.clone()), &existing));
+
+    // taking something away has to be noticed just the same
+    let mut removed = desired.clone();
+    removed.as_object_mut().unwrap().remove("default_member_permissions");
+    assert!(!same(&payload(removed), &existing));
+    let mut changed = desired;
+    changed["options"][0]["required"] = json!(true);
+    assert!(!same(&payload(changed), &existing));
+  }
+}
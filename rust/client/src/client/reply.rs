@@ -0,0 +1,113 @@
+use ::serenity::all as serenity;
+
+// a response that can be sent both as an interaction response and as a regular message,
+// serenity has a separate builder for each and there is no way to move attachments between them
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct Reply {
+  content: Option<String>,
+  embeds: Option<Vec<serenity::CreateEmbed>>,
+  components: Option<Vec<serenity::CreateActionRow>>,
+  // `None` keeps existing attachments, `Some` replaces them
+  attachments: Option<Vec<serenity::CreateAttachment>>,
+  ephemeral: bool,
+}
+
+impl Reply {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn content(mut self, content: impl Into<String>) -> Self {
+    self.content = Some(content.into());
+    self
+  }
+
+  pub fn embed(mut self, embed: serenity::CreateEmbed) -> Self {
+    self.embeds = Some(vec![embed]);
+    self
+  }
+
+  pub fn components(mut self, components: Vec<serenity::CreateActionRow>) -> Self {
+    self.components = Some(components);
+    self
+  }
+
+  pub fn new_attachment(mut self, attachment: serenity::CreateAttachment) -> Self {
+    self.attachments.get_or_insert_with(Vec::new).push(attachment);
+    self
+  }
+
+  pub fn clear_attachments(mut self) -> Self {
+    self.attachments = Some(Vec::new());
+    self
+  }
+
+  // only makes sense for slash commands, text command replies are always public
+  pub fn ephemeral(mut self, ephemeral: bool) -> Self {
+    self.ephemeral = ephemeral;
+    self
+  }
+}
+
+// content, embeds and components are set the same way on every builder
+macro_rules! common {
+  ($builder:expr, $reply:expr) => {{
+    let mut builder = $builder;
+    if let Some(content) = $reply.content {
+      builder = builder.content(content);
+    }
+    if let Some(embeds) = $reply.embeds {
+      builder = builder.embeds(embeds);
+    }
+    if let Some(components) = $reply.components {
+      builder = builder.components(components);
+    }
+    builder
+  }};
+}
+
+impl From<Reply> for serenity::CreateInteractionResponseMessage {
+  fn from(reply: Reply) -> Self {
+    let msg = common!(Self::new().ephemeral(reply.ephemeral), reply);
+    msg.add_files(reply.attachments.unwrap_or_default())
+  }
+}
+
+impl From<Reply> for serenity::CreateInteractionResponseFollowup {
+  fn from(reply: Reply) -> Self {
+    let msg = common!(Self::new().ephemeral(reply.ephemeral), reply);
+    msg.add_files(reply.attachments.unwrap_or_default())
+  }
+}
+
+impl From<Reply> for serenity::CreateMessage {
+  fn from(reply: Reply) -> Self {
+    let msg = common!(Self::new(), reply);
+    msg.add_files(reply.attachments.unwrap_or_default())
+  }
+}
+
+impl From<Reply> for serenity::EditInteractionResponse {
+  fn from(reply: Reply) -> Self {
+    let edit = common!(Self::new(), reply);
+    match reply.attachments {
+      Some(attachments) => attachments
+        .into_iter()
+        .fold(edit.clear_attachments(), Self::new_attachment),
+      None => edit,
+    }
+  }
+}
+
+impl From<Reply> for serenity::EditMessage {
+  fn from(reply: Reply) -> Self {
+    let edit = common!(Self::new(), reply);
+    match reply.attachments {
+      Some(attachments) => attachments
+        .into_iter()
+        .fold(edit.remove_all_attachments(), Self::new_attachment),
+      None => edit,
+    }
+  }
+}
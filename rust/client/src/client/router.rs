@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use ::serenity::all as serenity;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{err, util, Context, Result, RunFuture};
+use crate::db::components;
+
+pub(crate) use macros::*;
+
+pub type ComponentFn = for<'a> fn(&'a Context<'_>, &'a Component) -> RunFuture<'a>;
+pub type Components = HashMap<&'static str, ComponentFn>;
+
+// custom ids look like `route:id:key`, where `route` picks a handler,
+// `id` points to the state stored in the database and `key` tells apart components of the same flow
+#[derive(Debug, Clone, Copy)]
+pub struct ComponentId {
+  route: &'static str,
+  id: i64,
+}
+
+impl ComponentId {
+  // stores the state of a new flow, which can then be continued by any component built with this id
+  pub async fn new(ctx: &Context<'_>, route: &'static str, state: &impl Serialize) -> Result<Self> {
+    let command = util::CommandPathDisplay(&ctx.event.data).to_string();
+    let state = serde_json::to_string(state)?;
+    let id = components::insert(&ctx.client.db, ctx.event.user.id, &command, &state).await?;
+    Ok(Self { route, id })
+  }
+
+  // the id of the flow a component belongs to, to build more components for it
+  pub fn of(route: &'static str, component: &Component) -> Self {
+    Self {
+      route,
+      id: component.id,
+    }
+  }
+
+  pub fn custom_id(self, key: &str) -> String {
+    format!("{}:{}:{}", self.route, self.id, key)
+  }
+}
+
+#[derive(Debug)]
+pub struct Component {
+  pub id: i64,
+  pub key: String,
+  pub values: Vec<String>,
+  pub state: Value,
+}
+
+impl Component {
+  pub fn state<T: DeserializeOwned>(&self) -> Result<T> {
+    Ok(T::deserialize(&self.state)?)
+  }
+
+  // the first selected value, select menus here only ever allow to select one
+  pub fn value(&self) -> &str {
+    self.values.first().map_or("", String::as_str)
+  }
+
+  // sets a single field of the state and returns the whole updated state
+  pub async fn set<T: DeserializeOwned>(&self, ctx: &Context<'_>, field: &str, value: &impl Serialize) -> Result<T> {
+    let value = serde_json::to_string(value)?;
+    let Some(state) = components::set(&ctx.client.db, self.id, field, &value).await? else {
+      err::message!("{}", ctx.tr("this has expired, run the command again"));
+    };
+    Ok(serde_json::from_str(&state)?)
+  }
+
+  // ends the flow, its components expire, only the first of concurrent interactions gets `true`,
+  // so that whatever the flow ends with only happens once
+  pub async fn finish(&self, ctx: &Context<'_>) -> Result<bool> {
+    Ok(components::delete(&ctx.client.db, self.id).await?)
+  }
+}
+
+pub fn parse(custom_id: &str) -> Option<(&str, i64, &str)> {
+  let mut parts = custom_id.splitn(3, ':');
+  let route = parts.next()?;
+  let id = parts.next()?.parse().ok()?;
+  let key = parts.next()?;
+  Some((route, id, key))
+}
+
+pub fn values(event: &serenity::ComponentInteraction) -> Vec<String> {
+  match &event.data.kind {
+    serenity::ComponentInteractionDataKind::StringSelect { values } => values.clone(),
+    _ => Vec::new(),
+  }
+}
+
+mod macros {
+  macro_rules! components {
+    ($($route:literal => $handler:path),* $(,)?) => {{
+      let mut acc = $crate::client::Components::new();
+      $({
+        fn handler<'a>(
+          ctx: &'a $crate::client::Context<'_>,
+          component: &'a $crate::client::Component,
+        ) -> $crate::client::RunFuture<'a> {
+          ::std::boxed::Box::pin($handler(ctx, component))
+        }
+        acc.insert($route, handler as $crate::client::ComponentFn);
+      })*
+      acc
+    }};
+  }
+
+  pub(crate) use components;
+}
@@ -0,0 +1,142 @@
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+// how long the interrupted commands get to tell their users about it
+const GRACE: Duration = Duration::from_secs(5);
+
+// keeps track of the running commands, so that they can finish before exiting
+#[derive(Debug, Default)]
+pub struct Shutdown {
+  closing: AtomicBool,
+  expired: CancellationToken,
+  running: AtomicUsize,
+  drained: Notify,
+}
+
+impl Shutdown {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  // commands are tracked before checking this, and draining closes before counting them,
+  // with both sequentially consistent, either the command sees it's closing or the drain sees the command
+  pub fn is_closing(&self) -> bool {
+    self.closing.load(Ordering::SeqCst)
+  }
+
+  pub fn track(&self) -> Running<'_> {
+    self.running.fetch_add(1, Ordering::SeqCst);
+    Running(self)
+  }
+
+  // resolves once the running commands should give up
+  pub async fn expired(&self) {
+    self.expired.cancelled().await
+  }
+
+  // stops accepting new commands and waits for the running ones, they get interrupted after the deadline
+  pub async fn drain(&self, deadline: Duration) {
+    self.closing.store(true, Ordering::SeqCst);
+
+    let running = self.running.load(Ordering::SeqCst);
+    tracing::info!(running, "waiting for running commands…");
+    if tokio::time::timeout(deadline, self.wait()).await.is_ok() {
+      return;
+    }
+
+    let running = self.running.load(Ordering::Relaxed);
+    tracing::warn!(running, "interrupting running commands…");
+    self.expired.cancel();
+    tokio::time::timeout(GRACE, self.wait()).await.ok();
+  }
+
+  async fn wait(&self) {
+    loop {
+      // has to be registered before checking, so that the last command finishing in between doesn't get missed
+      let mut notified = pin!(self.drained.notified());
+      notified.as_mut().enable();
+      if self.running.load(Ordering::Relaxed) == 0 {
+        return;
+      }
+      notified.await;
+    }
+  }
+}
+
+pub struct Running<'a>(&'a Shutdown);
+
+impl Drop for Running<'_> {
+  fn drop(&mut self) {
+    self.0.running.fetch_sub(1, Ordering::Relaxed);
+    self.0.drained.notify_waiters();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use tokio::time::{self, Instant};
+
+  use super::*;
+
+  // a command that takes `duration`, unless it gets interrupted
+  fn command(shutdown: &Arc<Shutdown>, duration: Duration) -> tokio::task::JoinHandle<bool> {
+    let shutdown = shutdown.clone();
+    tokio::spawn(async move {
+      let _running = shutdown.track();
+      tokio::select! {
+        _ = time::sleep(duration) => true,
+        _ = shutdown.expired() => false,
+      }
+    })
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn drains_early() {
+    let shutdown = Arc::new(Shutdown::new());
+    let command = command(&shutdown, Duration::from_secs(3));
+    tokio::task::yield_now().await;
+
+    let start = Instant::now();
+    shutdown.drain(Duration::from_secs(60)).await;
+    assert_eq!(start.elapsed(), Duration::from_secs(3));
+    assert!(shutdown.is_closing());
+    assert!(command.await.unwrap());
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn interrupts_after_deadline() {
+    let shutdown = Arc::new(Shutdown::new());
+    let command = command(&shutdown, Duration::from_secs(600));
+    tokio::task::yield_now().await;
+
+    let start = Instant::now();
+    shutdown.drain(Duration::from_secs(60)).await;
+    assert!(!command.await.unwrap());
+    assert!(start.elapsed() >= Duration::from_secs(60) && start.elapsed() < Duration::from_secs(60) + GRACE);
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn gives_up_after_grace() {
+    let shutdown = Shutdown::new();
+    let _stuck = shutdown.track();
+
+    let start = Instant::now();
+    shutdown.drain(Duration::from_secs(60)).await;
+    assert_eq!(start.elapsed(), Duration::from_secs(60) + GRACE);
+    time::timeout(Duration::ZERO, shutdown.expired()).await.unwrap();
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn nothing_running() {
+    let shutdown = Shutdown::new();
+    let start = Instant::now();
+    shutdown.drain(Duration::from_secs(60)).await;
+    assert_eq!(start.elapsed(), Duration::ZERO);
+  }
+}
@@ -0,0 +1,187 @@
+use std::sync::Arc;
+
+use ::serenity::all as serenity;
+use cache::LruFileCache;
+use serde_json::{json, Value};
+use stub::Stub;
+use tempfile::TempDir;
+
+use super::{Client, Command, CommandTree, Commands, Config, Context, Metrics, Owners, Queue, Result, Shutdown};
+use crate::commands::components;
+use crate::db;
+
+pub use self::discord::{Discord, File, Request};
+
+mod discord;
+
+// the name commands are registered under, it doesn't show up anywhere
+const NAME: &str = "command";
+const USER_ID: u64 = 3;
+
+// runs commands without a live gateway: discord is faked, third party services are stubbed,
+// the database lives in memory, and the cache in a temporary directory,
+// everything gets thrown away once this is dropped
+pub struct Harness {
+  pub client: Client,
+  pub discord: Discord,
+  pub stub: Stub,
+  // the server commands are run on and who runs them there, see `Harness::in_guild`
+  member: Option<(serenity::GuildId, Value)>,
+  _cache_dir: TempDir,
+}
+
+impl Harness {
+  pub async fn new() -> Self {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let stub = Stub::start();
+    let config = Config::test(cache_dir.path().to_owned());
+
+    let db = db::memory().await.unwrap();
+    let cache = {
+      let base_url = config.cache_base_url.clone();
+      let working_dir = config.cache_working_dir.clone();
+      let cache = LruFileCache::new(base_url, working_dir, 1 << 30);
+      Arc::new(cache.await.unwrap())
+    };
+
+    let client = Client {
+      config: Arc::new(config),
+      http: stub.http(),
+      commands: Commands::new(),
+      components: components(),
+      queue: Queue::new(),
+      shutdown: Arc::new(Shutdown::new()),
+      cache,
+      db,
+      metrics: Arc::new(Metrics::default()),
+      owners: Owners::default(),
+    };
+
+    Self {
+      client,
+      discord: Discord::start().await,
+      stub,
+      member: None,
+      _cache_dir: cache_dir,
+    }
+  }
+
+  // commands run after this are run on a server, by a member with these permissions and roles
+  pub fn in_guild(&mut self, permissions: serenity::Permissions, roles: &[serenity::RoleId]) -> serenity::GuildId {
+    let gid = serenity::GuildId::new(self.discord.next_id());
+    let member = json!({
+      "user": user(),
+      "roles": roles,
+      "joined_at": null,
+      "deaf": false,
+      "mute": false,
+      "flags": 0,
+      "permissions": permissions,
+    });
+    self.member = Some((gid, member));
+    gid
+  }
+
+  // invokes a command the way discord would, in DMs unless `in_guild` was called, with options given by name,
+  // e.g. `json!({ "n": 3 })`, only options with plain values work, since there is nothing to resolve users or attachments from,
+  // the command is added to `client.commands`, next to whatever else is there
+  pub async fn run(&mut self, command: fn(&'static str) -> Command, options: Value) -> Result<()> {
+    let event = self.event(command, options)?;
+    let ctx = Context::new(&self.client, &self.discord.context, &event);
+    (ctx.command.run)(&ctx).await
+  }
+
+  // the interaction `run` would handle, for what needs a `Context` without running a command
+  pub fn event(
+    &mut self,
+    command: fn(&'static str) -> Command,
+    options: Value,
+  ) -> Result<serenity::CommandInteraction> {
+    let command = command(NAME);
+    let options = options.as_object().into_iter().flatten().map(|(name, value)| {
+      let option = command.options.iter().find(|opt| opt.name == name);
+      let option = option.unwrap_or_else(|| panic!("unknown option: {name}"));
+      json!({ "name": name, "type": option.ty, "value": value })
+    });
+    let mut event = json!({
+      "id": self.discord.next_id().to_string(),
+      "application_id": discord::APPLICATION_ID.to_string(),
+      "type": 2,
+      "token": "token",
+      "data": {
+        "id": self.discord.next_id().to_string(),
+        "name": NAME,
+        "type": command.kind,
+        "options": options.collect::<Vec<_>>(),
+      },
+      "channel_id": self.discord.next_id().to_string(),
+      "user": user(),
+      "locale": "en-US",
+      "version": 1,
+      "app_permissions": "0",
+      "entitlements": [],
+    });
+    if let Some((gid, member)) = &self.member {
+      event["guild_id"] = json!(gid);
+      event["member"] = member.clone();
+    }
+    let event = serde_json::from_value::<serenity::CommandInteraction>(event)?;
+
+    self.client.commands.insert(NAME, CommandTree::Command(command));
+    Ok(event)
+  }
+
+  pub fn requests(&self) -> Vec<Request> {
+    self.discord.requests()
+  }
+
+  // everything that's been sent as a message or an edit of one, in order
+  pub fn replies(&self) -> Vec<Value> {
+    let requests = self.discord.requests();
+    requests.iter().filter_map(Request::message).cloned().collect()
+  }
+}
+
+// ---
+
+fn user() -> Value {
+  json!({ "id": USER_ID.to_string(), "username": "user", "discriminator": "0000", "avatar": null })
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+  use crate::client::Reply;
+
+  #[macros::command(desc = "Upload a file")]
+  async fn upload(ctx: &Context<'_>) -> Result<()> {
+    ctx.defer().await?;
+    let file = serenity::CreateAttachment::bytes(b"hello".to_vec(), "hello.txt");
+    ctx
+      .edit_reply(Reply::new().content("done").new_attachment(file))
+      .await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn captures_edits_with_attachments() {
+    let mut harness = Harness::new().await;
+    harness.run(upload, json!({})).await.unwrap();
+
+    let requests = harness.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[0].path.ends_with("/callback"));
+    assert!(requests[0].message().is_none(), "deferring doesn't send anything");
+
+    let [File { name, data }] = &requests[1].files[..] else {
+      panic!("expected one file, got {:?}", requests[1].files);
+    };
+    assert_eq!((name.as_str(), data.as_slice()), ("hello.txt", &b"hello"[..]));
+    let replies = harness.replies();
+    assert_eq!(replies.len(), 1);
+    assert_eq!(replies[0]["content"], "done");
+    assert_eq!(replies[0]["attachments"][0]["filename"], "hello.txt");
+  }
+}
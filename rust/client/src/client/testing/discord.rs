@@ -0,0 +1,280 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use ::serenity::all as serenity;
+use ::serenity::prelude::{RwLock, TypeMap};
+use hyper::body::to_bytes;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Response, StatusCode};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+
+pub const APPLICATION_ID: u64 = 1;
+pub const BOT_ID: u64 = 2;
+
+// a fake discord that records everything sent to its http api and answers with made up messages,
+// its gateway accepts connections but never says anything, it's only there because shards need one
+pub struct Discord {
+  pub context: serenity::Context,
+  state: Arc<State>,
+  // keeps the shard's channels open, collectors are registered through them
+  _runner: serenity::ShardRunner,
+}
+
+#[derive(Debug, Clone)]
+pub struct Request {
+  pub method: Method,
+  // without the `/api/v10` prefix, e.g. `/interactions/3/token/callback`
+  pub path: String,
+  pub json: Value,
+  pub files: Vec<File>,
+}
+
+#[derive(Debug, Clone)]
+pub struct File {
+  pub name: String,
+  pub data: Vec<u8>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+  requests: Mutex<Vec<Request>>,
+  next_id: AtomicU64,
+}
+
+impl Discord {
+  pub async fn start() -> Self {
+    let state = Arc::new(State {
+      next_id: AtomicU64::new(1000),
+      ..Default::default()
+    });
+
+    let http_addr = serve_http(state.clone());
+    let gateway_addr = serve_gateway().await;
+
+    let http = serenity::HttpBuilder::new("token")
+      .proxy(format!("http://{http_addr}"))
+      .ratelimiter_disabled(true)
+      .application_id(APPLICATION_ID.into())
+      .build();
+    let http = Arc::new(http);
+    let cache = Arc::new(serenity::Cache::new());
+    let data = Arc::new(RwLock::new(TypeMap::new()));
+    let ws_url = Arc::new(tokio::sync::Mutex::new(format!("ws://{gateway_addr}")));
+    let intents = serenity::GatewayIntents::empty();
+
+    let shard_info = serenity::ShardInfo {
+      id: serenity::ShardId(0),
+      total: 1,
+    };
+    let shard = serenity::Shard::new(ws_url.clone(), "token", shard_info, intents, None);
+    let shard = shard.await.expect("failed to connect to the fake gateway");
+
+    let (manager, _) = serenity::ShardManager::new(serenity::ShardManagerOptions {
+      data: data.clone(),
+      event_handlers: Vec::new(),
+      raw_event_handlers: Vec::new(),
+      shard_index: 0,
+      shard_init: 0,
+      shard_total: 1,
+      ws_url,
+      cache: cache.clone(),
+      http: http.clone(),
+      intents,
+      presence: None,
+    });
+    let runner = serenity::ShardRunner::new(serenity::ShardRunnerOptions {
+      data: data.clone(),
+      event_handlers: Vec::new(),
+      raw_event_handlers: Vec::new(),
+      manager,
+      shard,
+      cache: cache.clone(),
+      http: http.clone(),
+    });
+
+    let context = serenity::Context {
+      data,
+      shard: serenity::ShardMessenger::new(&runner),
+      shard_id: serenity::ShardId(0),
+      http,
+      cache,
+    };
+
+    Self {
+      context,
+      state,
+      _runner: runner,
+    }
+  }
+
+  pub fn next_id(&self) -> u64 {
+    self.state.next_id.fetch_add(1, Ordering::Relaxed)
+  }
+
+  pub fn requests(&self) -> Vec<Request> {
+    self.state.requests.lock().unwrap().clone()
+  }
+}
+
+impl Request {
+  // the message that this request sends or edits, if any
+  pub fn message(&self) -> Option<&Value> {
+    match self.path.ends_with("/callback") {
+      // 4 is a new message, 7 is an update of the message a component is attached to
+      true => matches!(self.json["type"].as_u64(), Some(4 | 7)).then_some(&self.json["data"]),
+      false => self.is_message().then_some(&self.json),
+    }
+  }
+
+  // creating or editing a regular message, a followup, or an interaction response
+  fn is_message(&self) -> bool {
+    let path = &self.path;
+    self.method != Method::DELETE && (path.contains("/messages") || path.starts_with("/webhooks/"))
+  }
+}
+
+// ---
+
+fn serve_http(state: Arc<State>) -> SocketAddr {
+  let make_service = make_service_fn(move |_| {
+    let state = state.clone();
+    let service = service_fn(move |req| {
+      let state = state.clone();
+      async move { Ok::<_, Infallible>(respond(&state, req).await) }
+    });
+    async move { Ok::<_, Infallible>(service) }
+  });
+
+  let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+  let addr = server.local_addr();
+  tokio::spawn(server);
+  addr
+}
+
+async fn serve_gateway() -> SocketAddr {
+  let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+  let addr = listener.local_addr().unwrap();
+  tokio::spawn(async move {
+    let mut connections = Vec::new();
+    while let Ok((stream, _)) = listener.accept().await {
+      if let Ok(ws) = tokio_tungstenite::accept_async(stream).await {
+        connections.push(ws);
+      }
+    }
+  });
+  addr
+}
+
+async fn respond(state: &State, req: hyper::Request<Body>) -> Response<Body> {
+  let (parts, body) = req.into_parts();
+  let body = to_bytes(body).await.unwrap_or_default();
+  let content_type = parts.headers.get("content-type").and_then(|v| v.to_str().ok());
+
+  let (json, files) = match content_type.and_then(|ct| ct.split_once("boundary=")) {
+    Some((_, boundary)) => multipart(&body, boundary),
+    None => (serde_json::from_slice(&body).unwrap_or_default(), Vec::new()),
+  };
+
+  let request = Request {
+    method: parts.method,
+    path: parts.uri.path().trim_start_matches("/api/v10").to_owned(),
+    json,
+    files,
+  };
+  let response = match &request {
+    r if r.path.ends_with("/callback") || r.path.ends_with("/typing") || r.method == Method::DELETE => None,
+    r if r.is_message() => Some(message(state, r)),
+    r => {
+      tracing::warn!(method = %r.method, path = r.path, "not faked");
+      let error = json!({ "code": 0, "message": "not faked" });
+      let response = Response::builder().status(StatusCode::NOT_FOUND);
+      return response.body(error.to_string().into()).unwrap();
+    }
+  };
+  state.requests.lock().unwrap().push(request);
+
+  match response {
+    Some(json) => Response::builder()
+      .header("content-type", "application/json")
+      .body(json.to_string().into())
+      .unwrap(),
+    None => Response::builder()
+      .status(StatusCode::NO_CONTENT)
+      .body(Body::empty())
+      .unwrap(),
+  }
+}
+
+// just enough of a message for serenity to deserialize it
+fn message(state: &State, request: &Request) -> Value {
+  let id = state.next_id.fetch_add(1, Ordering::Relaxed);
+  let attachments = request.files.iter().map(|file| {
+    let id = state.next_id.fetch_add(1, Ordering::Relaxed);
+    let url = format!("https://cdn.discordapp.com/attachments/{id}/{}", file.name);
+    json!({ "id": id.to_string(), "filename": file.name, "size": file.data.len(), "url": url, "proxy_url": url })
+  });
+
+  json!({
+    "id": id.to_string(),
+    "channel_id": id.to_string(),
+    "author": { "id": BOT_ID.to_string(), "username": "riamu", "discriminator": "0000", "avatar": null, "bot": true },
+    "content": request.json["content"].as_str().unwrap_or_default(),
+    "embeds": request.json.get("embeds").cloned().unwrap_or(json!([])),
+    "components": request.json.get("components").cloned().unwrap_or(json!([])),
+    "attachments": attachments.collect::<Vec<_>>(),
+    "timestamp": "2024-01-01T00:00:00Z",
+    "edited_timestamp": null,
+    "tts": false,
+    "mention_everyone": false,
+    "mentions": [],
+    "mention_roles": [],
+    "pinned": false,
+    "type": 0,
+  })
+}
+
+// attachments are sent as `files[n]` parts, and everything else as json in `payload_json`
+fn multipart(body: &[u8], boundary: &str) -> (Value, Vec<File>) {
+  let delimiter = format!("--{}", boundary.trim_matches('"'));
+  let (mut json, mut files) = (Value::Null, Vec::new());
+
+  for part in split(body, delimiter.as_bytes()) {
+    let Some(at) = find(part, b"\r\n\r\n") else {
+      continue;
+    };
+    let headers = String::from_utf8_lossy(&part[..at]);
+    let data = part[at + 4..].strip_suffix(b"\r\n").unwrap_or(&part[at + 4..]);
+
+    let param = |key: &str| {
+      let (_, rest) = headers.split_once(&format!("{key}=\""))?;
+      rest.split_once('"').map(|(value, _)| value.to_owned())
+    };
+    match (param("name").as_deref(), param("filename")) {
+      (Some("payload_json"), _) => json = serde_json::from_slice(data).unwrap_or_default(),
+      (_, Some(name)) => files.push(File {
+        name,
+        data: data.to_vec(),
+      }),
+      _ => {}
+    }
+  }
+
+  (json, files)
+}
+
+fn split<'a>(mut haystack: &'a [u8], delimiter: &[u8]) -> Vec<&'a [u8]> {
+  let mut parts = Vec::new();
+  while let Some(at) = find(haystack, delimiter) {
+    parts.push(&haystack[..at]);
+    haystack = &haystack[at + delimiter.len()..];
+  }
+  parts.push(haystack);
+  parts
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+  haystack.windows(needle.len()).position(|w| w == needle)
+}
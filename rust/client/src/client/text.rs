@@ -0,0 +1,598 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+use ::serenity::all as serenity;
+use serde_json::{json, Value};
+use serenity::CommandOptionType as T;
+
+use super::{err, Client, Command, CommandOption, CommandTree, Result};
+
+// text commands get turned into made up slash command interactions,
+// so they go through the very same option extraction and command handling
+pub async fn parse(
+  client: &Client,
+  ctx: &serenity::Context,
+  msg: &serenity::Message,
+  prefix: &str,
+) -> Result<Option<serenity::CommandInteraction>> {
+  let Some(text) = msg.content.strip_prefix(prefix) else {
+    return Ok(None);
+  };
+
+  let mut args = tokenize(text).into_iter();
+
+  // unknown commands are ignored, the prefix could as well be a part of a regular message
+  let Some(name) = args.next() else {
+    return Ok(None);
+  };
+  let Some((&name, mut tree)) = client.commands.get_key_value(&*name.text.to_lowercase()) else {
+    return Ok(None);
+  };
+
+  let mut path = vec![name];
+  let cmd = loop {
+    match tree {
+      CommandTree::Command(cmd) => break cmd,
+      CommandTree::Commands(cmds) => {
+        let sub = args.next().map(|arg| arg.text.to_lowercase());
+        let Some((&name, next)) = sub.and_then(|sub| cmds.get_key_value(&*sub)) else {
+          let mut names = cmds.keys().copied().collect::<Vec<_>>();
+          names.sort_unstable();
+          err::message!("expected one of: {}", names.join(", "));
+        };
+        path.push(name);
+        tree = next;
+      }
+    }
+  };
+
+  // context menu commands don't make any sense without the context menu
+  if cmd.kind != serenity::CommandType::ChatInput {
+    return Ok(None);
+  }
+
+  let usage = usage(prefix, &path, cmd);
+  let values = assign(cmd, args.collect(), &usage)?;
+
+  let mut resolved = serenity::CommandDataResolved::default();
+  let mut options = Vec::new();
+  let mut attachments = msg.attachments.iter();
+
+  for (opt, value) in cmd.options.iter().zip(values) {
+    let value = match (opt.ty, value) {
+      (T::Attachment, _) => match attachments.next() {
+        Some(attachment) => {
+          resolved.attachments.insert(attachment.id, attachment.clone());
+          json!(attachment.id)
+        }
+        None if opt.required => err::message!("missing attachment for `{}`, usage: {}", opt.name, usage),
+        None => continue,
+      },
+      (_, Some(value)) => convert(ctx, msg, opt, &value, &mut resolved).await?,
+      (_, None) if opt.required => err::message!("missing `{}`, usage: {}", opt.name, usage),
+      (_, None) => continue,
+    };
+    options.push(json!({ "name": opt.name, "type": u8::from(opt.ty), "value": value }));
+  }
+
+  let locale = guild_locale(ctx, msg);
+  let base = json!({
+    "id": msg.id,
+    "application_id": ctx.cache.current_user().id,
+    "guild_id": msg.guild_id,
+    "channel_id": msg.channel_id,
+    "user": msg.author,
+    "token": "",
+    "version": 1,
+    "locale": locale.as_deref().unwrap_or("en-US"),
+    "guild_locale": locale,
+  });
+
+  let mut event = interaction(&path, options, base)?;
+  event.data.resolved = resolved;
+  event.member = member(ctx, msg).await?.map(Box::new);
+
+  Ok(Some(event))
+}
+
+// makes up a slash command interaction from a command path and its options,
+// messages don't say what language their author uses, so it's whatever the server prefers
+pub fn guild_locale(ctx: &serenity::Context, msg: &serenity::Message) -> Option<String> {
+  let guild = msg.guild_id.and_then(|id| ctx.cache.guild(id));
+  guild.map(|guild| guild.preferred_locale.clone())
+}
+
+// everything else (id, user, channel, locale, etc.) is taken from `base`
+pub fn interaction(path: &[&str], options: Vec<Value>, mut base: Value) -> Result<serenity::CommandInteraction> {
+  // wrap the options into subcommands and subcommand groups, innermost first
+  let mut options = Value::Array(options);
+  for (i, name) in path.iter().enumerate().skip(1).rev() {
+    let ty = if i == path.len() - 1 {
+      T::SubCommand
+    } else {
+      T::SubCommandGroup
+    };
+    options = json!([{ "name": name, "type": u8::from(ty), "options": options }]);
+  }
+
+  base["data"] = json!({
+    "id": base["id"],
+    "name": path[0],
+    "type": u8::from(serenity::CommandType::ChatInput),
+    "options": options,
+  });
+
+  Ok(serde_json::from_value(base)?)
+}
+
+// ---
+
+#[derive(Debug)]
+struct Arg {
+  text: String,
+  quoted: bool,
+}
+
+// splits on whitespace, except inside of double quotes, `\"` and `\\` stand for themselves
+fn tokenize(text: &str) -> Vec<Arg> {
+  let mut args = Vec::new();
+  let mut arg: Option<Arg> = None;
+  let mut in_quotes = false;
+
+  let mut chars = text.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '"' => {
+        // an empty pair of quotes is still an argument
+        arg.get_or_insert_with(|| Arg {
+          text: String::new(),
+          quoted: true,
+        });
+        in_quotes = !in_quotes;
+      }
+      c if c.is_whitespace() && !in_quotes => args.extend(arg.take()),
+      c => {
+        let c = match c {
+          '\\' => chars.next_if(|&next| next == '"' || next == '\\').unwrap_or(c),
+          c => c,
+        };
+        let arg = arg.get_or_insert_with(|| Arg {
+          text: String::new(),
+          quoted: false,
+        });
+        arg.text.push(c);
+      }
+    }
+  }
+
+  args.extend(arg);
+  args
+}
+
+// options can be given either by name (`units:imperial`) or by position,
+// in which case the last string option takes the rest of the line
+fn assign(cmd: &Command, args: Vec<Arg>, usage: &str) -> Result<Vec<Option<String>>> {
+  let mut values = vec![None; cmd.options.len()];
+  let mut positional = VecDeque::new();
+
+  for arg in args {
+    let named = arg
+      .text
+      .split_once(':')
+      .filter(|_| !arg.quoted)
+      .and_then(|(key, value)| {
+        let i = cmd.options.iter().position(|o| o.name == key)?;
+        Some((i, value.to_owned()))
+      });
+    match named {
+      Some((i, value)) => values[i] = Some(value),
+      None => positional.push_back(arg.text),
+    }
+  }
+
+  let free = (0..cmd.options.len())
+    .filter(|&i| values[i].is_none() && cmd.options[i].ty != T::Attachment)
+    .collect::<Vec<_>>();
+
+  for (n, &i) in free.iter().enumerate() {
+    let is_rest = n == free.len() - 1 && cmd.options[i].ty == T::String;
+    values[i] = if is_rest && !positional.is_empty() {
+      Some(positional.drain(..).collect::<Vec<_>>().join(" "))
+    } else {
+      positional.pop_front()
+    };
+  }
+
+  if !positional.is_empty() {
+    err::message!("too many arguments, usage: {}", usage);
+  }
+
+  Ok(values)
+}
+
+async fn convert(
+  ctx: &serenity::Context,
+  msg: &serenity::Message,
+  opt: &CommandOption,
+  value: &str,
+  resolved: &mut serenity::CommandDataResolved,
+) -> Result<Value> {
+  let value = match opt.choices {
+    Some(choices) => {
+      let choice = choices
+        .iter()
+        .find(|c| c.name.eq_ignore_ascii_case(value) || c.value.to_string().eq_ignore_ascii_case(value));
+      let Some(choice) = choice else {
+        let names = choices.iter().map(|c| c.value.to_string()).collect::<Vec<_>>();
+        err::message!("`{}` should be one of: {}", opt.name, names.join(", "));
+      };
+      Cow::Owned(choice.value.to_string())
+    }
+    None => Cow::Borrowed(value),
+  };
+  let value = &*value;
+
+  let in_range = |n: f64| opt.min.is_none_or(|min| n >= min) && opt.max.is_none_or(|max| n <= max);
+  let in_length =
+    |n: usize| opt.min_length.is_none_or(|min| n >= min.into()) && opt.max_length.is_none_or(|max| n <= max.into());
+
+  let value = match opt.ty {
+    T::String if in_length(value.chars().count()) => json!(value),
+    T::String => err::message!("`{}` should be{}", opt.name, length(opt)),
+    T::Integer => match value.parse::<i64>() {
+      Ok(n) if in_range(n as f64) => json!(n),
+      _ => err::message!("`{}` should be a whole number{}", opt.name, range(opt)),
+    },
+    T::Number => match value.parse::<f64>() {
+      Ok(n) if n.is_finite() && in_range(n) => json!(n),
+      _ => err::message!("`{}` should be a number{}", opt.name, range(opt)),
+    },
+    T::Boolean => match &*value.to_lowercase() {
+      "true" | "yes" | "on" | "1" => json!(true),
+      "false" | "no" | "off" | "0" => json!(false),
+      _ => err::message!("`{}` should be either yes or no", opt.name),
+    },
+    T::User => match user(ctx, msg, value, resolved).await? {
+      Some(id) => json!(id),
+      None => err::message!("`{}` should be a user mention or id", opt.name),
+    },
+    T::Role => match role(ctx, msg, value, resolved) {
+      Some(id) => json!(id),
+      None => err::message!("`{}` should be a role mention or id", opt.name),
+    },
+    T::Mentionable => match role(ctx, msg, value, resolved) {
+      Some(id) => json!(id),
+      None => match user(ctx, msg, value, resolved).await? {
+        Some(id) => json!(id),
+        None => err::message!("`{}` should be a user or role mention", opt.name),
+      },
+    },
+    T::Channel => match channel(ctx, msg, opt, value, resolved)? {
+      Some(id) => json!(id),
+      None => err::message!("`{}` should be a channel mention or id", opt.name),
+    },
+    ty => err::message!("`{}` can't be used from text ({:?})", opt.name, ty),
+  };
+
+  Ok(value)
+}
+
+async fn user(
+  ctx: &serenity::Context,
+  msg: &serenity::Message,
+  value: &str,
+  resolved: &mut serenity::CommandDataResolved,
+) -> Result<Option<serenity::UserId>> {
+  let Some(id) = mention(value, &["<@!", "<@"], ">") else {
+    return Ok(None);
+  };
+  let id = serenity::UserId::new(id);
+
+  let user = match msg.mentions.iter().find(|u| u.id == id) {
+    Some(user) => user.clone(),
+    None => match id.to_user(ctx).await {
+      Ok(user) => user,
+      Err(_) => return Ok(None),
+    },
+  };
+
+  if let Some(member) = &user.member {
+    resolved.members.insert(id, (**member).clone());
+  }
+  resolved.users.insert(id, user);
+
+  Ok(Some(id))
+}
+
+fn role(
+  ctx: &serenity::Context,
+  msg: &serenity::Message,
+  value: &str,
+  resolved: &mut serenity::CommandDataResolved,
+) -> Option<serenity::RoleId> {
+  let id = serenity::RoleId::new(mention(value, &["<@&"], ">")?);
+  let guild = ctx.cache.guild(msg.guild_id?)?;
+  let role = guild.roles.get(&id)?.clone();
+  resolved.roles.insert(id, role);
+  Some(id)
+}
+
+fn channel(
+  ctx: &serenity::Context,
+  msg: &serenity::Message,
+  opt: &CommandOption,
+  value: &str,
+  resolved: &mut serenity::CommandDataResolved,
+) -> Result<Option<serenity::ChannelId>> {
+  let Some(id) = mention(value, &["<#"], ">") else {
+    return Ok(None);
+  };
+  let id = serenity::ChannelId::new(id);
+
+  let channel = msg.guild_id.and_then(|gid| {
+    let guild = ctx.cache.guild(gid)?;
+    let channel = guild
+      .channels
+      .get(&id)
+      .or_else(|| guild.threads.iter().find(|t| t.id == id))?;
+    Some((channel.kind, serde_json::to_value(channel)))
+  });
+
+  match channel {
+    Some((kind, _)) if !opt.channel_types.is_empty() && !opt.channel_types.contains(&kind) => {
+      let types = opt.channel_types.iter().map(|ty| ty.name()).collect::<Vec<_>>();
+      err::message!("`{}` should be a channel of type: {}", opt.name, types.join(", "));
+    }
+    // a partial channel is a subset of a guild channel, so it's easier to just convert it this way
+    Some((_, channel)) => {
+      resolved.channels.insert(id, serde_json::from_value(channel?)?);
+      Ok(Some(id))
+    }
+    None => Ok(None),
+  }
+}
+
+// accepts either a mention (e.g. `<@123>`) or a raw id
+fn mention(value: &str, prefixes: &[&str], suffix: &str) -> Option<u64> {
+  let inner = prefixes
+    .iter()
+    .find_map(|p| value.strip_prefix(p)?.strip_suffix(suffix))
+    .unwrap_or(value);
+  inner.parse().ok().filter(|&id| id != 0)
+}
+
+async fn member(ctx: &serenity::Context, msg: &serenity::Message) -> Result<Option<serenity::Member>> {
+  let Some(gid) = msg.guild_id else {
+    return Ok(None);
+  };
+
+  // slash commands get the member's permissions in the channel, so text commands should get them too
+  let mut member = gid.member(ctx, msg.author.id).await?;
+  member.permissions = ctx.cache.guild(gid).and_then(|guild| {
+    let channel = guild.channels.get(&msg.channel_id)?;
+    Some(guild.user_permissions_in(channel, &member))
+  });
+
+  Ok(Some(member))
+}
+
+fn usage(prefix: &str, path: &[&str], cmd: &Command) -> String {
+  let mut acc = format!("`{}{}", prefix, path.join(" "));
+  for opt in &cmd.options {
+    let (l, r) = if opt.required { ('<', '>') } else { ('[', ']') };
+    write!(acc, " {}{}{}", l, opt.name, r).unwrap();
+  }
+  acc.push('`');
+  acc
+}
+
+fn length(opt: &CommandOption) -> String {
+  match (opt.min_length, opt.max_length) {
+    (Some(min), Some(max)) => format!(" from {min} to {max} characters long"),
+    (Some(min), None) => format!(" at least {min} characters long"),
+    (None, Some(max)) => format!(" at most {max} characters long"),
+    (None, None) => String::new(),
+  }
+}
+
+fn range(opt: &CommandOption) -> String {
+  match (opt.min, opt.max) {
+    (Some(min), Some(max)) => format!(" from {min} to {max}"),
+    (Some(min), None) => format!(" of at least {min}"),
+    (None, Some(max)) => format!(" of at most {max}"),
+    (None, None) => String::new(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use ::serenity::all::{CommandDataOptionValue as OptionValue, User};
+
+  use super::*;
+  use crate::client::testing::Harness;
+  use crate::client::{CommandError, Context};
+
+  #[macros::command(desc = "Repeat something")]
+  async fn echo(
+    _ctx: &Context<'_>,
+    #[desc = "How many times"]
+    #[min = 1]
+    #[max = 5]
+    #[name = "n"]
+    _n: i64,
+    #[desc = "What to repeat"]
+    #[name = "text"]
+    _text: &str,
+  ) -> Result<()> {
+    Ok(())
+  }
+
+  #[macros::command(desc = "Poke someone")]
+  async fn poke(
+    _ctx: &Context<'_>,
+    #[desc = "Who to poke"]
+    #[name = "user"]
+    _user: &User,
+    #[desc = "Whether to poke hard"]
+    #[name = "hard"]
+    _hard: Option<bool>,
+  ) -> Result<()> {
+    Ok(())
+  }
+
+  fn texts(text: &str) -> Vec<(String, bool)> {
+    tokenize(text).into_iter().map(|arg| (arg.text, arg.quoted)).collect()
+  }
+
+  fn args(text: &str) -> Vec<Option<String>> {
+    assign(&echo("echo"), tokenize(text), "usage").unwrap()
+  }
+
+  fn message(content: &str, mentions: Value) -> serenity::Message {
+    let message = json!({
+      "id": "1",
+      "channel_id": "2",
+      "author": { "id": "3", "username": "user", "discriminator": "0000", "avatar": null },
+      "content": content,
+      "embeds": [],
+      "attachments": [],
+      "timestamp": "2024-01-01T00:00:00Z",
+      "edited_timestamp": null,
+      "tts": false,
+      "mention_everyone": false,
+      "mentions": mentions,
+      "mention_roles": [],
+      "pinned": false,
+      "type": 0,
+    });
+    serde_json::from_value(message).unwrap()
+  }
+
+  // what people get told when a message doesn't make a valid command
+  fn error(result: Result<impl Sized>) -> String {
+    match result.err().unwrap().downcast::<CommandError>() {
+      Ok(err) => match *err {
+        CommandError::Message(msg) => msg,
+        err => panic!("unexpected error: {err}"),
+      },
+      Err(err) => panic!("unexpected error: {err}"),
+    }
+  }
+
+  #[test]
+  fn tokens() {
+    let s = |text: &str, quoted| (text.to_owned(), quoted);
+    assert_eq!(texts("  a  b\tc "), [s("a", false), s("b", false), s("c", false)]);
+    assert_eq!(
+      texts(r#"a "b c" d"e f""#),
+      [s("a", false), s("b c", true), s("de f", false)]
+    );
+    assert_eq!(texts(r#"a "" b"#), [s("a", false), s("", true), s("b", false)]);
+    assert_eq!(
+      texts(r#"say \"hi\" c:\\d e\f"#),
+      [s("say", false), s("\"hi\"", false), s("c:\\d", false), s("e\\f", false)]
+    );
+    assert_eq!(texts(r#""a \" b""#), [s("a \" b", true)]);
+    // an unterminated quote goes on until the end
+    assert_eq!(texts(r#"a "b c"#), [s("a", false), s("b c", true)]);
+    assert!(texts("").is_empty());
+  }
+
+  #[test]
+  fn assignment() {
+    let some = |values: &[&str]| values.iter().map(|v| Some((*v).to_owned())).collect::<Vec<_>>();
+    assert_eq!(args("3 hello world"), some(&["3", "hello world"]));
+    assert_eq!(args("text:hi n:2"), some(&["2", "hi"]));
+    assert_eq!(args("hello n:2 world"), some(&["2", "hello world"]));
+    // quoted arguments are never named ones
+    assert_eq!(args(r#"1 "text:hi""#), some(&["1", "text:hi"]));
+    // unknown names are just text
+    assert_eq!(args("1 a:b"), some(&["1", "a:b"]));
+    assert_eq!(args("1"), [Some("1".to_owned()), None]);
+    assert_eq!(args(""), [None, None]);
+
+    // only a string option can take the rest of the line
+    let err = assign(&echo("echo"), tokenize("text:hi 1 2"), "`!echo <n> <text>`");
+    assert_eq!(error(err), "too many arguments, usage: `!echo <n> <text>`");
+  }
+
+  #[test]
+  fn mentions() {
+    assert_eq!(mention("<@123>", &["<@!", "<@"], ">"), Some(123));
+    assert_eq!(mention("<@!123>", &["<@!", "<@"], ">"), Some(123));
+    assert_eq!(mention("123", &["<@!", "<@"], ">"), Some(123));
+    assert_eq!(mention("<#123>", &["<@!", "<@"], ">"), None);
+    assert_eq!(mention("<@&123>", &["<@&"], ">"), Some(123));
+    assert_eq!(mention("0", &["<@"], ">"), None);
+    assert_eq!(mention("me", &["<@"], ">"), None);
+  }
+
+  async fn converted(harness: &Harness, opt: &CommandOption, value: &str) -> Result<Value> {
+    let mentioned = json!([{ "id": "7", "username": "friend", "discriminator": "0000", "avatar": null }]);
+    let msg = message("", mentioned);
+    let mut resolved = serenity::CommandDataResolved::default();
+    convert(&harness.discord.context, &msg, opt, value, &mut resolved).await
+  }
+
+  #[tokio::test]
+  async fn conversions() {
+    let harness = Harness::new().await;
+    let (echo, poke) = (echo("echo"), poke("poke"));
+    let convert = |opt, value| converted(&harness, opt, value);
+
+    assert_eq!(convert(echo.options[0], "5").await.unwrap(), json!(5));
+    assert_eq!(convert(echo.options[1], "hi").await.unwrap(), json!("hi"));
+    assert_eq!(convert(poke.options[1], "Yes").await.unwrap(), json!(true));
+    assert_eq!(convert(poke.options[1], "off").await.unwrap(), json!(false));
+    // mentioned users are resolved from the message
+    assert_eq!(convert(poke.options[0], "<@7>").await.unwrap(), json!("7"));
+    assert_eq!(convert(poke.options[0], "7").await.unwrap(), json!("7"));
+
+    let err = convert(echo.options[0], "6").await;
+    assert_eq!(error(err), "`n` should be a whole number from 1 to 5");
+    let err = convert(echo.options[0], "1.5").await;
+    assert_eq!(error(err), "`n` should be a whole number from 1 to 5");
+    let err = convert(poke.options[1], "maybe").await;
+    assert_eq!(error(err), "`hard` should be either yes or no");
+    let err = convert(poke.options[0], "someone").await;
+    assert_eq!(error(err), "`user` should be a user mention or id");
+  }
+
+  #[tokio::test]
+  async fn parsing() {
+    let mut harness = Harness::new().await;
+    harness.client.commands.extend([
+      ("echo", CommandTree::Command(echo("echo"))),
+      ("poke", CommandTree::Command(poke("poke"))),
+    ]);
+    let (client, ctx) = (&harness.client, &harness.discord.context);
+
+    let text = |content: &str| {
+      let msg = message(content, json!([]));
+      async move { parse(client, ctx, &msg, "!").await }
+    };
+    assert!(text("hello").await.unwrap().is_none());
+    assert!(text("!nope").await.unwrap().is_none());
+
+    let event = text(r#"!ECHO 2 "hi there""#).await.unwrap().unwrap();
+    assert_eq!(event.data.name, "echo");
+    let values = event.data.options.iter().map(|opt| (&*opt.name, &opt.value));
+    assert_eq!(
+      values.collect::<Vec<_>>(),
+      [
+        ("n", &OptionValue::Integer(2)),
+        ("text", &OptionValue::String("hi there".to_owned())),
+      ]
+    );
+
+    let err = text("!echo").await;
+    assert_eq!(error(err), "missing `n`, usage: `!echo <n> <text>`");
+    let err = text("!echo 1").await;
+    assert_eq!(error(err), "missing `text`, usage: `!echo <n> <text>`");
+    let err = text("!poke 7 hard:no extra").await;
+    assert_eq!(error(err), "too many arguments, usage: `!poke <user> [hard]`");
+  }
+}
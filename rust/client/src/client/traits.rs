@@ -1,14 +1,43 @@
 use serenity::all::ResolvedValue as R;
 use serenity::all::*;
 
-use super::CommandOptionChoice;
+use super::{err, CommandOptionChoice, FromOption, Result};
 
-pub trait CommandOptionTrait<'a> {
+pub trait CommandOptionTrait<'a>: Sized {
   const TYPE: CommandOptionType;
   const CHOICES: Option<&'static [CommandOptionChoice]> = None;
   const REQUIRED: bool = true;
 
-  fn extract(value: Option<&'a ResolvedOption<'_>>) -> Self;
+  fn extract(value: Option<&'a ResolvedOption<'_>>) -> Result<Self>;
+}
+
+// what a context menu command (user or message one) has been invoked on
+pub trait CommandTargetTrait<'a> {
+  const TYPE: CommandType;
+
+  fn extract(target: Option<&'a ResolvedTarget<'_>>) -> Self;
+}
+
+impl<'a> CommandTargetTrait<'a> for &'a Message {
+  const TYPE: CommandType = CommandType::Message;
+
+  fn extract(target: Option<&'a ResolvedTarget<'_>>) -> Self {
+    match target {
+      Some(&ResolvedTarget::Message(message)) => message,
+      _ => unreachable!(),
+    }
+  }
+}
+
+impl<'a> CommandTargetTrait<'a> for &'a User {
+  const TYPE: CommandType = CommandType::User;
+
+  fn extract(target: Option<&'a ResolvedTarget<'_>>) -> Self {
+    match target {
+      Some(&ResolvedTarget::User(user, _)) => user,
+      _ => unreachable!(),
+    }
+  }
 }
 
 impl<'a, T> CommandOptionTrait<'a> for Option<T>
@@ -19,8 +48,23 @@ where
   const CHOICES: Option<&'static [CommandOptionChoice]> = T::CHOICES;
   const REQUIRED: bool = false;
 
-  fn extract(value: Option<&'a ResolvedOption<'_>>) -> Self {
-    value.map(|value| T::extract(Some(value)))
+  fn extract(value: Option<&'a ResolvedOption<'_>>) -> Result<Self> {
+    value.map(|value| T::extract(Some(value))).transpose()
+  }
+}
+
+// registered as strings, parse errors go back to the user
+impl<'a, T> CommandOptionTrait<'a> for T
+where
+  T: FromOption,
+{
+  const TYPE: CommandOptionType = CommandOptionType::String;
+
+  fn extract(value: Option<&'a ResolvedOption<'_>>) -> Result<Self> {
+    match T::from_option(<&str>::extract(value)?) {
+      Some(parsed) => Ok(parsed),
+      None => err::message!("`{}` should be {}", value.map_or("", |v| v.name), T::EXPECTED),
+    }
   }
 }
 
@@ -29,9 +73,9 @@ macro_rules! impl_trait {
     impl<$lifetime> CommandOptionTrait<$lifetime> for $T {
       const TYPE: CommandOptionType = CommandOptionType::$type;
 
-      fn extract(value: Option<&'a ResolvedOption<'_>>) -> Self {
+      fn extract(value: Option<&'a ResolvedOption<'_>>) -> Result<Self> {
         match value {
-          Some(&ResolvedOption { value: $pat, .. }) => $expr,
+          Some(&ResolvedOption { value: $pat, .. }) => Ok($expr),
           _ => unreachable!(),
         }
       }
@@ -49,3 +93,72 @@ impl_trait!('a, &'a Role: Role, R::Role(value) => value);
 impl_trait!('a, &'a User: User, R::User(value, _) => value);
 impl_trait!('a, &'a PartialMember: User, R::User(_, Some(value)) => value);
 impl_trait!('a, &'a ResolvedValue<'a>: Mentionable, ref value => value);
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+  use serenity::all::CommandOptionType;
+
+  use crate::client::testing::Harness;
+  use crate::client::{ChoiceValue, Context, Reply, Result};
+
+  #[derive(macros::Choice)]
+  enum Precision {
+    #[value = 0]
+    Whole,
+    #[value = 2]
+    #[default]
+    Two,
+  }
+
+  #[macros::command(desc = "Add numbers up")]
+  async fn sum(
+    ctx: &Context<'_>,
+    #[repeat = 3] n: Vec<f64>,
+    precision: Precision,
+    #[max_length = 5] unit: Option<&str>,
+  ) -> Result<()> {
+    let digits = match precision {
+      Precision::Whole => 0,
+      Precision::Two => 2,
+    };
+    let sum = n.iter().sum::<f64>();
+    let unit = unit.unwrap_or_default();
+    ctx.reply(Reply::new().content(format!("{sum:.digits$}{unit}"))).await?;
+    Ok(())
+  }
+
+  #[test]
+  fn options() {
+    let cmd = sum("sum");
+    let names = cmd.options.iter().map(|o| (o.name, o.required)).collect::<Vec<_>>();
+    assert_eq!(
+      names,
+      [
+        ("n1", true),
+        ("n2", false),
+        ("n3", false),
+        ("precision", false),
+        ("unit", false)
+      ]
+    );
+
+    let precision = cmd.options[3];
+    assert_eq!(precision.ty, CommandOptionType::Integer);
+    let values = precision.choices.unwrap().iter().map(|c| c.value).collect::<Vec<_>>();
+    assert_eq!(values, [ChoiceValue::Integer(0), ChoiceValue::Integer(2)]);
+    assert_eq!(cmd.options[4].max_length, Some(5));
+  }
+
+  #[tokio::test]
+  async fn extract() {
+    let mut harness = Harness::new().await;
+    harness.run(sum, json!({ "n1": 1.25, "n3": 2.0 })).await.unwrap();
+    let options = json!({ "n1": 1.25, "n2": 2.0, "precision": 0, "unit": "kg" });
+    harness.run(sum, options).await.unwrap();
+
+    let replies = harness.replies();
+    assert_eq!(replies[0]["content"], "3.25");
+    assert_eq!(replies[1]["content"], "3kg");
+  }
+}
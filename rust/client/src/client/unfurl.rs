@@ -0,0 +1,188 @@
+use ::serenity::all as serenity;
+use api::_2ch::parse_url as parse_2ch_url;
+use api::_4chan::parse_url as parse_4chan_url;
+use discord::link;
+use serde_json::json;
+use url::Url;
+
+use super::{text, Client, Context, Result};
+use crate::db;
+
+// custom ids look like `unfurl:<message>:<link>`, the links are parsed again from the message when clicked,
+// so the buttons keep working for as long as it's there, restarts included
+pub const ROUTE: &str = "unfurl";
+
+// there can't be more than 5 buttons in a row
+const MAX_LINKS: usize = 5;
+
+// a supported link gets handled by running a regular command with the link as its option,
+// so rate limits and per guild toggles apply to it just the same
+struct Site {
+  label: &'static str,
+  path: &'static [&'static str],
+  option: &'static str,
+  matches: fn(&str) -> bool,
+}
+
+#[rustfmt::skip]
+static SITES: &[Site] = &[
+  Site { label: "Repost from 4chan", path: &["repost", "4chan"], option: "url", matches: |url| parse_4chan_url(url).is_some() },
+  Site { label: "Repost from 2ch", path: &["repost", "2ch"], option: "url", matches: |url| parse_2ch_url(url).is_some() },
+  Site { label: "Download from TikTok", path: &["tiktok"], option: "url", matches: |url| host_is(url, &["tiktok.com"]) },
+  Site { label: "Download from Deezer", path: &["deezer", "as-file"], option: "query", matches: |url| host_is(url, &["deezer.com", "deezer.page.link"]) },
+  Site { label: "Download from Spotify", path: &["deezer", "as-file"], option: "query", matches: |url| host_is(url, &["spotify.com", "spotify.link"]) },
+];
+
+// offers to repost or download supported links, only in channels where it's been enabled
+pub async fn offer(client: &Client, ctx: &serenity::Context, msg: &serenity::Message) -> Result<()> {
+  let links = links(&msg.content);
+  if links.is_empty() || !db::unfurl_channels::get(&client.db, msg.channel_id).await? {
+    return Ok(());
+  }
+
+  tracing::debug!(channel=%msg.channel_id, "unfurling {} links", links.len());
+
+  let buttons = links.iter().enumerate().map(|(i, (_, site))| {
+    let label = match links.len() {
+      1 => site.label.to_owned(),
+      _ => format!("{} #{}", site.label, i + 1),
+    };
+    serenity::CreateButton::new(custom_id(msg.id, i))
+      .style(serenity::ButtonStyle::Secondary)
+      .label(label)
+  });
+  let buttons = serenity::CreateActionRow::Buttons(buttons.collect());
+  let create = serenity::CreateMessage::new()
+    .components(vec![buttons])
+    .reference_message(msg)
+    .allowed_mentions(serenity::CreateAllowedMentions::new());
+  msg.channel_id.send_message(ctx, create).await?;
+
+  Ok(())
+}
+
+// `id` is the message with the links, `key` the index of the one that was clicked
+pub async fn on_click(
+  client: &Client,
+  ctx: &serenity::Context,
+  mci: &serenity::ComponentInteraction,
+  id: i64,
+  key: &str,
+) -> Result<()> {
+  let msg = match &mci.message.referenced_message {
+    Some(msg) => Some((**msg).clone()),
+    None => mci.channel_id.message(ctx, id as u64).await.ok(),
+  };
+  let content = msg.map(|msg| msg.content).unwrap_or_default();
+  let Some((url, site)) = clicked(&content, key) else {
+    return client
+      .reject_component(ctx, mci, "the message with the link is gone")
+      .await;
+  };
+  run(client, ctx, mci, url, site).await
+}
+
+fn custom_id(msg: serenity::MessageId, i: usize) -> String {
+  format!("{ROUTE}:{msg}:{i}")
+}
+
+// the link behind a button, if the message still has it
+fn clicked<'a>(content: &'a str, key: &str) -> Option<(&'a str, &'static Site)> {
+  let i = key.parse::<usize>().ok()?;
+  links(content).get(i).copied()
+}
+
+fn links(content: &str) -> Vec<(&str, &'static Site)> {
+  let mut acc = Vec::<(&str, &Site)>::new();
+  for url in link::urls(content) {
+    let Some(site) = SITES.iter().find(|site| (site.matches)(url)) else {
+      continue;
+    };
+    if !acc.iter().any(|&(u, _)| u == url) {
+      acc.push((url, site));
+    }
+  }
+  acc.truncate(MAX_LINKS);
+  acc
+}
+
+async fn run(
+  client: &Client,
+  ctx: &serenity::Context,
+  mci: &serenity::ComponentInteraction,
+  url: &str,
+  site: &Site,
+) -> Result<()> {
+  // the button click is a real interaction, so the command can respond to it as if it was a slash command
+  let option = json!({ "name": site.option, "type": u8::from(serenity::CommandOptionType::String), "value": url });
+  let event = text::interaction(site.path, vec![option], serde_json::to_value(mci)?)?;
+  let ctx = Context::new(client, ctx, &event);
+  client.handle_command(ctx).await?;
+  Ok(())
+}
+
+fn host_is(url: &str, domains: &[&str]) -> bool {
+  let Ok(url) = Url::parse(url) else {
+    return false;
+  };
+  let host = url.host_str().unwrap_or_default();
+  let is_subdomain = |domain: &str| {
+    host
+      .strip_suffix(domain)
+      .is_some_and(|s| s.is_empty() || s.ends_with('.'))
+  };
+  domains.iter().any(|&domain| is_subdomain(domain))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::client::router;
+
+  fn labels(content: &str) -> Vec<(&str, &str)> {
+    links(content)
+      .into_iter()
+      .map(|(url, site)| (url, site.label))
+      .collect()
+  }
+
+  #[test]
+  fn extraction() {
+    let content = "look https://boards.4chan.org/g/thread/123 and <https://vm.tiktok.com/abc> \
+      https://evil-tiktok.com/x https://example.com https://boards.4chan.org/g/thread/123 \
+      https://2ch.hk/b/res/5.html https://open.spotify.com/track/1";
+    assert_eq!(
+      labels(content),
+      [
+        ("https://boards.4chan.org/g/thread/123", "Repost from 4chan"),
+        ("https://vm.tiktok.com/abc", "Download from TikTok"),
+        ("https://2ch.hk/b/res/5.html", "Repost from 2ch"),
+        ("https://open.spotify.com/track/1", "Download from Spotify"),
+      ]
+    );
+
+    let many = (0..7).map(|i| format!("https://tiktok.com/@a/video/{i}"));
+    assert_eq!(links(&many.collect::<Vec<_>>().join(" ")).len(), MAX_LINKS);
+    assert!(links("nothing to see here https://example.com").is_empty());
+  }
+
+  #[test]
+  fn routing() {
+    let id = custom_id(serenity::MessageId::new(42), 1);
+    assert_eq!(id, "unfurl:42:1");
+    let (route, msg, key) = router::parse(&id).unwrap();
+    assert_eq!((route, msg, key), (ROUTE, 42, "1"));
+
+    let content = "https://tiktok.com/@a/video/1 https://deezer.com/track/2";
+    let (url, site) = clicked(content, key).unwrap();
+    assert_eq!(
+      (url, site.path),
+      ("https://deezer.com/track/2", &["deezer", "as-file"][..])
+    );
+
+    // the message got edited or deleted since
+    assert!(clicked(content, "2").is_none());
+    assert!(clicked("", "0").is_none());
+    assert!(clicked(content, "x").is_none());
+  }
+}
@@ -2,10 +2,13 @@ use std::any::Any;
 use std::borrow::Cow;
 use std::fmt::{self, Display, Formatter};
 
+use pyo3::PyErr;
+use python::gil;
 use serenity::all::*;
 
 pub struct SlashCommandDisplay<'a>(pub &'a CommandData);
 pub struct SlashCommandOptionsDisplay<'a>(pub &'a [CommandDataOption]);
+pub struct CommandPathDisplay<'a>(pub &'a CommandData);
 
 impl Display for SlashCommandDisplay<'_> {
   fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -49,6 +52,25 @@ impl Display for SlashCommandOptionsDisplay<'_> {
   }
 }
 
+impl Display for CommandPathDisplay<'_> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    use CommandDataOptionValue::*;
+
+    write!(f, "/{}", self.0.name)?;
+
+    let mut options = &self.0.options[..];
+    while let [option] = options {
+      let (SubCommand(next) | SubCommandGroup(next)) = &option.value else {
+        break;
+      };
+      write!(f, " {}", option.name)?;
+      options = next;
+    }
+
+    Ok(())
+  }
+}
+
 pub fn panic_message(panic: Box<dyn Any + Send + '_>) -> Cow<'_, str> {
   if let Some(s) = panic.downcast_ref() {
     return Cow::Borrowed(*s);
@@ -58,3 +80,11 @@ pub fn panic_message(panic: Box<dyn Any + Send + '_>) -> Cow<'_, str> {
   }
   unreachable!()
 }
+
+// the same thing `PyErr::print` would print, but as a string
+pub fn python_traceback(err: &PyErr) -> String {
+  gil::with_gil(|py| {
+    let traceback = err.traceback(py).and_then(|tb| tb.format().ok());
+    format!("{}{}", traceback.unwrap_or_default(), err)
+  })
+}
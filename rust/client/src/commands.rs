@@ -6,6 +6,7 @@ mod deezer;
 mod download;
 mod imgur;
 mod random;
+mod repost;
 mod tiktok;
 mod weather;
 mod lookup {
@@ -15,10 +16,14 @@ mod lookup {
   pub mod wikipedia;
 }
 mod meta {
+  pub mod config;
+  pub mod error;
+  pub mod help;
   pub mod info;
   pub mod shell;
   pub mod speed;
   pub mod speed_to_discord;
+  pub mod unfurl;
 }
 mod text {
   pub mod style;
@@ -57,11 +62,18 @@ pub fn tree() -> client::Commands {
         "dictionary" => lookup::urban_dictionary::run,
       },
     },
+    "help" => meta::help::run,
     "meta" => {
+      "config" => meta::config::run,
       "info" => meta::info::run,
+      "speed-to-discord" => meta::speed_to_discord::run,
+      "unfurl" => meta::unfurl::run,
+    },
+    // only on the dev server, kept apart so that the rest of `/meta` can be global
+    "dev" => {
+      "error" => meta::error::run,
       "shell" => meta::shell::run,
       "speed" => meta::speed::run,
-      "speed-to-discord" => meta::speed_to_discord::run,
     },
     "random" => {
       "int" => random::int,
@@ -91,5 +103,33 @@ pub fn tree() -> client::Commands {
         "history" => user::status::history::run,
       },
     },
+
+    // context menu
+    "Download media" => download::menu,
+    "Repost" => repost::menu,
+    "Avatar" => user::profile::avatar_menu,
+    "Status history" => user::status::history::menu,
+  }
+}
+
+pub fn components() -> client::Components {
+  client::components! {
+    "download/video" => download::on_video,
+    "download/format" => download::on_format,
+    "deezer/track" => deezer::on_track,
+    "deezer/quality" => deezer::on_quality,
+    "tiktok/quality" => tiktok::on_quality,
+    "help/page" => meta::help::on_page,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::client;
+
+  #[test]
+  fn limits() {
+    let errors = client::validate_commands(&super::tree());
+    assert!(errors.is_empty(), "{errors:#?}");
   }
 }
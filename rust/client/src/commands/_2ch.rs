@@ -6,11 +6,15 @@ use fmt::plural::Plural;
 use serenity::all::*;
 use util::html;
 
-use crate::client::{err, Context, Result};
+use crate::client::{err, Context, Reply, Result};
 
-#[macros::command(desc = "Repost something from 2ch")]
+#[macros::command(desc = "Repost something from 2ch", register = "guild")]
 pub async fn repost(ctx: &Context<'_>, url: &str) -> Result<()> {
-  ctx.event.defer(ctx).await?;
+  repost_url(ctx, url).await
+}
+
+pub async fn repost_url(ctx: &Context<'_>, url: &str) -> Result<()> {
+  ctx.defer().await?;
 
   tracing::debug!("parsing url…");
   let Some((domain, board_id, thread_id, post_id)) = parse_url(url) else {
@@ -18,30 +22,30 @@ pub async fn repost(ctx: &Context<'_>, url: &str) -> Result<()> {
   };
 
   tracing::debug!("getting thread…");
-  let thread = Thread::get(domain, board_id, thread_id).await?;
+  let thread = Thread::get(&ctx.client.http, domain, board_id, thread_id).await?;
 
   let post_id = post_id.unwrap_or(thread_id);
   reply(ctx, domain, board_id, post_id, &thread).await
 }
 
-#[macros::command(desc = "Random 2ch post")]
+#[macros::command(desc = "Random 2ch post", register = "guild")]
 pub async fn random(
   ctx: &Context<'_>,
   #[desc = "2ch board id"] board: &str,
   #[desc = "Only threads where subject matches this regex"] include: Option<&str>,
   #[desc = "Only threads where subject doesn't match this regex"] exclude: Option<&str>,
 ) -> Result<()> {
-  ctx.event.defer(ctx).await?;
+  ctx.defer().await?;
 
   tracing::debug!("getting catalog…");
-  let catalog = Catalog::get(board).await?;
+  let catalog = Catalog::get(&ctx.client.http, board).await?;
   let filter = catalog::thread_filter(include, exclude)?;
   let Some((thread, file_index)) = catalog.random(filter, |t| t.files_count as usize) else {
     err::message!("no results");
   };
 
   tracing::debug!("getting thread…");
-  let thread = Thread::get("2ch.hk", board, thread.id).await?;
+  let thread = Thread::get(&ctx.client.http, "2ch.hk", board, thread.id).await?;
   let posts = thread.posts.iter();
   let (post, _) = posts
     .scan(0, |acc, thread| {
@@ -86,19 +90,18 @@ async fn reply(ctx: &Context<'_>, domain: &str, board_id: &str, post_id: u64, th
     .author(CreateEmbedAuthor::new(title).url(url))
     .footer(CreateEmbedFooter::new(footer));
 
-  let mut files = EditAttachments::new();
+  let mut reply = Reply::new().clear_attachments();
   if let Some(post_files) = &post.files {
     tracing::debug!("attaching files…");
     for file in post_files {
       let url = format!("https://{}{}", domain, file.path);
       let att = CreateAttachment::url(ctx, &url).await?;
-      files = files.add(att);
+      reply = reply.new_attachment(att);
     }
   }
 
   tracing::debug!("sending response…");
-  let edit = EditInteractionResponse::new().attachments(files);
-  ctx.event.edit_response(ctx, edit.embed(embed)).await?;
+  ctx.edit_reply(reply.embed(embed)).await?;
 
   Ok(())
 }
@@ -1,22 +1,27 @@
+use api::_4chan::{boards::Boards, catalog::Catalog, thread::Thread};
 use api::_4chan::{catalog, parse_url};
-use api::_4chan::{catalog::Catalog, thread::Thread};
 use fmt::plural::Plural;
 use serenity::all::*;
 use util::html;
 
-use crate::client::{err, Context, Result};
+use crate::client::{err, tr, Context, Reply, Result};
 
 #[macros::command(desc = "Repost something from 4chan")]
 pub async fn repost(ctx: &Context<'_>, #[desc = "4chan thread url"] url: &str) -> Result<()> {
-  ctx.event.defer(ctx).await?;
+  repost_url(ctx, url).await
+}
+
+pub async fn repost_url(ctx: &Context<'_>, url: &str) -> Result<()> {
+  ctx.defer().await?;
 
   tracing::debug!("parsing url…");
   let Some((domain, board_id, thread_id, post_id)) = parse_url(url) else {
     err::message!("failed to parse url");
   };
+  check_board(ctx, board_id).await?;
 
   tracing::debug!("getting thread…");
-  let thread = Thread::get(board_id, thread_id).await?;
+  let thread = Thread::get(&ctx.client.http, board_id, thread_id).await?;
 
   let post_id = post_id.unwrap_or(thread_id);
   reply(ctx, domain, board_id, post_id, &thread).await
@@ -25,29 +30,72 @@ pub async fn repost(ctx: &Context<'_>, #[desc = "4chan thread url"] url: &str) -
 #[macros::command(desc = "Random 4chan post")]
 pub async fn random(
   ctx: &Context<'_>,
-  #[desc = "4chan board id"] board: &str,
+  #[desc = "4chan board id"]
+  #[autocomplete = boards]
+  board: &str,
   #[desc = "Only threads where subject matches this regex"] include: Option<&str>,
   #[desc = "Only threads where subject doesn't match this regex"] exclude: Option<&str>,
 ) -> Result<()> {
-  ctx.event.defer(ctx).await?;
+  ctx.defer().await?;
+
+  check_board(ctx, board).await?;
 
   tracing::debug!("getting catalog…");
-  let catalog = Catalog::get(board).await?;
+  let catalog = Catalog::get(&ctx.client.http, board).await?;
   let filter = catalog::thread_filter(include, exclude)?;
   let Some((thread, file_index)) = catalog.random(filter, |t| 1 + t.images as usize) else {
     err::message!("no results");
   };
 
   tracing::debug!("getting thread…");
-  let thread = Thread::get(board, thread.id).await?;
+  let thread = Thread::get(&ctx.client.http, board, thread.id).await?;
   let posts = thread.posts.iter();
   let post = posts.filter(|p| p.file.is_some()).nth(file_index).unwrap();
 
   reply(ctx, "boards.4chan.org", board, post.id, &thread).await
 }
 
+async fn boards(ctx: &Context<'_>, board: &str) -> Result<Vec<AutocompleteChoice>> {
+  let query = board.trim().trim_matches('/').to_lowercase();
+  let boards = Boards::get(&ctx.client.http).await?.boards;
+
+  // boards with a matching id go first, then the ones with a matching title
+  let (mut ids, mut titles) = (Vec::new(), Vec::new());
+  for board in &boards {
+    if board.id.starts_with(&query) {
+      ids.push(board);
+    } else if board.title.to_lowercase().contains(&query) {
+      titles.push(board);
+    }
+  }
+  ids.append(&mut titles);
+
+  let choices = ids.into_iter().map(|b| {
+    let name = format!("/{}/ \u{2013} {}", b.id, b.title);
+    AutocompleteChoice::new(fmt::ellipsis(&name, 100), &*b.id)
+  });
+
+  Ok(choices.collect())
+}
+
 // ---
 
+// the commands themselves aren't NSFW, only some of the boards are
+async fn check_board(ctx: &Context<'_>, board: &str) -> Result<()> {
+  tracing::debug!("getting boards…");
+  let boards = Boards::get(&ctx.client.http).await?.boards;
+  let Some(info) = boards.iter().find(|b| b.id == board) else {
+    err::message!("{}", tr!(ctx, "unknown board: {}", board));
+  };
+  if info.worksafe == 0 && !ctx.is_nsfw().await? {
+    err::message!(
+      "{}",
+      tr!(ctx, "/{}/ is NSFW, it can only be used in NSFW channels", board)
+    );
+  }
+  Ok(())
+}
+
 async fn reply(ctx: &Context<'_>, domain: &str, board_id: &str, post_id: u64, thread: &Thread) -> Result<()> {
   let (post, post_index) = thread.get_post_by_id(post_id).unwrap();
   let op = &thread.posts[0];
@@ -74,19 +122,18 @@ async fn reply(ctx: &Context<'_>, domain: &str, board_id: &str, post_id: u64, th
     .author(CreateEmbedAuthor::new(title).url(url))
     .footer(CreateEmbedFooter::new(footer));
 
-  let files = match &post.file {
+  let reply = match &post.file {
     Some(file) => {
       tracing::debug!("attaching files…");
       let url = format!("https://i.4cdn.org/{}/{}{}", board_id, file.id, file.ext);
       let att = CreateAttachment::url(ctx, &url).await?;
-      EditAttachments::new().add(att)
+      Reply::new().new_attachment(att)
     }
-    None => EditAttachments::new(),
+    None => Reply::new().clear_attachments(),
   };
 
   tracing::debug!("sending response…");
-  let edit = EditInteractionResponse::new().attachments(files);
-  ctx.event.edit_response(ctx, edit.embed(embed)).await?;
+  ctx.edit_reply(reply.embed(embed)).await?;
 
   Ok(())
 }
@@ -5,45 +5,94 @@ use std::time::Duration;
 use ::cache::Name;
 use discord::link::{self, Link};
 use fmt::num::Format as _;
-use futures::StreamExt;
+use gcra::Rate;
 use python::lib::dz;
+use python::progress::Tracker;
+use serde::{Deserialize, Serialize};
 use serenity::all::*;
+use tokio::sync::Semaphore;
 use url::Url;
 use util::task;
 
-use crate::client::{err, Context, Result};
-
-#[macros::command(desc = "Download a song from Deezer (tries to upload it directly to Discord)")]
-pub async fn as_file(ctx: &Context<'_>, #[desc = "A search query or a Deezer/Spotify link"] query: &str) -> Result<()> {
+use crate::client::{err, Component, ComponentId, Context, Job, Ratelimit, Reply, Result, Scope};
+
+// both commands download the same things, so they share limits
+const RATELIMITS: &[Ratelimit] = &[
+  Ratelimit::new(Scope::User, Rate::new(10.0, gcra::minutes(10))).shared("deezer"),
+  Ratelimit::new(Scope::Global, Rate::new(60.0, gcra::minutes(10))).shared("deezer"),
+];
+
+// autocomplete searches on every keystroke, and then the command searches again for what's been picked,
+// so searches are cached, only a few run at once, and there's nothing to suggest for the first letters
+const SEARCH_TTL: Duration = Duration::from_secs(10 * 60);
+static SEARCHING: Semaphore = Semaphore::const_new(4);
+const MIN_QUERY: usize = 3;
+
+#[macros::command(
+  desc = "Download a song from Deezer (tries to upload it directly to Discord)",
+  requires = "deezer",
+  ratelimits = RATELIMITS
+)]
+pub async fn as_file(
+  ctx: &Context<'_>,
+  #[desc = "A search query or a Deezer/Spotify link"]
+  #[autocomplete = tracks]
+  query: &str,
+) -> Result<()> {
   deezer(ctx, query, false).await
 }
 
-#[macros::command(desc = "Download a song from Deezer (gives a direct link and a nice looking banner)")]
+#[macros::command(
+  desc = "Download a song from Deezer (gives a direct link and a nice looking banner)",
+  requires = "deezer",
+  ratelimits = RATELIMITS
+)]
 pub async fn as_direct_link(
   ctx: &Context<'_>,
-  #[desc = "A search query or a Deezer/Spotify link"] query: &str,
+  #[desc = "A search query or a Deezer/Spotify link"]
+  #[autocomplete = tracks]
+  query: &str,
 ) -> Result<()> {
   deezer(ctx, query, true).await
 }
 
 async fn deezer(ctx: &Context<'_>, query: &str, with_banner: bool) -> Result<()> {
-  ctx.event.defer(ctx).await?;
+  ctx.defer().await?;
+
+  match Url::parse(query) {
+    Ok(_) => select_track_quality(ctx, query, with_banner).await,
+    Err(_) => select_track_url(ctx, query, with_banner).await,
+  }
+}
+
+// the user has picked one of the search results
+pub async fn on_track(ctx: &Context<'_>, component: &Component) -> Result<()> {
+  ctx.defer().await?;
 
-  tracing::debug!("converting query to url…");
-  let url = query_to_url(ctx, query).await?;
+  let url = component.value();
   tracing::debug!(%url, "selected");
+  select_track_quality(ctx, url, component.state()?).await
+}
 
-  tracing::debug!("selecting track quality…");
-  let quality = select_track_quality(ctx).await?;
+pub async fn on_quality(ctx: &Context<'_>, component: &Component) -> Result<()> {
+  ctx.defer().await?;
+
+  let Track { url, with_banner } = component.state()?;
+  let quality = component.key.clone();
   tracing::debug!(%quality, "selected");
 
+  let _permit = ctx.client.queue.acquire(ctx, Job::Deezer).await?;
+
   tracing::debug!("downloading…");
-  ctx.progress("downloading…").await?;
   let tempdir = tempfile::tempdir()?;
+  let tracker = Tracker::new();
   let info = {
-    let tempdir = tempdir.path().to_owned();
-    let info = task::spawn_blocking(move || dz::download(&url, &quality, &tempdir)).await?;
-    info? // FIXME: should figure out a way to handle that PyErr
+    let (tempdir, progress) = (tempdir.path().to_owned(), tracker.clone());
+    let download = task::spawn_blocking(move || dz::download(&url, &quality, &tempdir, &progress));
+    let Some(info) = ctx.track("downloading…", &tracker, download).await? else {
+      return Ok(());
+    };
+    info?? // FIXME: should figure out a way to handle that PyErr
   };
 
   let Some(Ok(file)) = fs::read_dir(&tempdir)?.next() else {
@@ -81,13 +130,13 @@ async fn deezer(ctx: &Context<'_>, query: &str, with_banner: bool) -> Result<()>
     .await??;
 
     let banner = CreateAttachment::bytes(banner, "banner.png");
-    let edit = EditInteractionResponse::new()
+    let edit = Reply::new()
       .components(Default::default()) // remove components
       .content(content)
       .new_attachment(banner);
 
     tracing::debug!("sending response…");
-    ctx.event.edit_response(ctx, edit).await?;
+    ctx.edit_reply(edit).await?;
   } else {
     let content = {
       let (artist, track) = (link::Name(&info.artist.name), link::Name(&info.title));
@@ -99,20 +148,20 @@ async fn deezer(ctx: &Context<'_>, query: &str, with_banner: bool) -> Result<()>
       acc
     };
 
-    let edit = EditInteractionResponse::new()
+    let edit = Reply::new()
       .components(Default::default()) // remove components
       .content(content);
 
     if fsize > ctx.filesize_limit().await? {
       tracing::debug!("sending response…");
-      ctx.event.edit_response(ctx, edit).await?;
+      ctx.edit_reply(edit).await?;
     } else {
       let file = CreateAttachment::path(&fpath).await?;
       let edit = edit.new_attachment(file);
 
       tracing::debug!("uploading…");
       ctx.progress("uploading…").await?;
-      if ctx.event.edit_response(ctx, edit).await.is_err() {
+      if ctx.edit_reply(edit).await.is_err() {
         err::message!("failed to upload, most likely the file is too big ({fsize}B)");
       }
     }
@@ -121,35 +170,50 @@ async fn deezer(ctx: &Context<'_>, query: &str, with_banner: bool) -> Result<()>
   Ok(())
 }
 
-async fn query_to_url(ctx: &Context<'_>, query: &str) -> Result<String> {
-  let query = query.to_owned();
-  match Url::parse(&query) {
-    Ok(_) => Ok(query),
-    Err(_) => {
-      tracing::debug!("searching…");
-      let tracks = task::spawn_blocking(move || dz::search(&query)).await??;
-      tracing::debug!("found {} tracks", tracks.len());
-
-      if tracks.is_empty() {
-        err::message!("could not find anything");
-      }
-
-      tracing::debug!("selecting track url…");
-      let url = select_track_url(ctx, &tracks).await?;
-      Ok(url)
-    }
+async fn tracks(ctx: &Context<'_>, query: &str) -> Result<Vec<AutocompleteChoice>> {
+  if query.trim().chars().count() < MIN_QUERY || Url::parse(query).is_ok() {
+    return Ok(Vec::new());
   }
+
+  let tracks = search(ctx, query).await?;
+  let choices = tracks.iter().take(25).map(|e| {
+    let name = format!("{} \u{2013} {} ({})", e.artist, e.title, fmt::duration(e.duration));
+    AutocompleteChoice::new(fmt::ellipsis(&name, 100), &*e.link)
+  });
+
+  Ok(choices.collect())
+}
+
+async fn search(ctx: &Context<'_>, query: &str) -> Result<Vec<Found>> {
+  let query = query.trim().to_owned();
+  let key = query.to_lowercase();
+  let search = async move {
+    let _permit = SEARCHING.acquire().await.expect("never closed");
+    let tracks = task::spawn_blocking(move || dz::search(&query)).await??;
+    Ok(tracks.into_iter().map(Found::from).collect())
+  };
+
+  ctx.client.http.memoize("deezer", &key, SEARCH_TTL, search).await
 }
 
-async fn select_track_url(ctx: &Context<'_>, tracks: &[dz::Track]) -> Result<String> {
+async fn select_track_url(ctx: &Context<'_>, query: &str, with_banner: bool) -> Result<()> {
+  tracing::debug!("searching…");
+  let tracks = search(ctx, query).await?;
+  tracing::debug!("found {} tracks", tracks.len());
+
+  if tracks.is_empty() {
+    err::message!("could not find anything");
+  }
+
   if tracks.len() == 1 {
-    return Ok(tracks[0].link.clone());
+    return select_track_quality(ctx, &tracks[0].link, with_banner).await;
   }
 
+  let id = ComponentId::new(ctx, "deezer/track", &with_banner).await?;
   let selector = CreateActionRow::SelectMenu({
     let options = tracks.iter().take(25).map(|e| {
       let label = format!("{} ({})", e.title, fmt::duration(e.duration));
-      let desc = format!("{} · {}", e.artist.name, e.album.title);
+      let desc = format!("{} · {}", e.artist, e.album);
 
       let label = fmt::ellipsis(&label, 100);
       let desc = fmt::ellipsis(&desc, 100);
@@ -158,66 +222,71 @@ async fn select_track_url(ctx: &Context<'_>, tracks: &[dz::Track]) -> Result<Str
 
     let options = options.collect();
     let menu = CreateSelectMenuKind::String { options };
-    let menu = CreateSelectMenu::new("url", menu);
+    let menu = CreateSelectMenu::new(id.custom_id("url"), menu);
     menu.placeholder("Select track")
   });
 
+  tracing::debug!("selecting track url…");
   let components = vec![selector];
-  let edit = EditInteractionResponse::new().components(components);
-  let msg = ctx.event.edit_response(ctx, edit).await?;
-
-  tracing::debug!("waiting for user interaction…");
-  let mut collector = msg
-    .await_component_interaction(ctx)
-    .author_id(ctx.event.user.id)
-    .timeout(Duration::from_secs(60))
-    .stream();
-
-  let Some(mut mci) = collector.next().await else {
-    err::timeout!();
-  };
+  let edit = Reply::new().components(components);
+  ctx.edit_reply(edit).await?;
 
-  mci.defer(ctx).await?;
+  Ok(())
+}
 
-  let url = match &mut mci.data.kind {
-    ComponentInteractionDataKind::StringSelect { values } => values.swap_remove(0),
-    _ => unreachable!(),
+async fn select_track_quality(ctx: &Context<'_>, url: &str, with_banner: bool) -> Result<()> {
+  let state = Track {
+    url: url.to_owned(),
+    with_banner,
   };
+  let id = ComponentId::new(ctx, "deezer/quality", &state).await?;
 
-  Ok(url)
-}
-
-async fn select_track_quality(ctx: &Context<'_>) -> Result<String> {
   let buttons = CreateActionRow::Buttons({
     let values = [("lossless", "lossless"), ("320", "320k"), ("128", "128k")];
-    let buttons = values.into_iter().enumerate().map(|(i, (id, label))| {
+    let buttons = values.into_iter().enumerate().map(|(i, (key, label))| {
       let style = match i {
         0 => ButtonStyle::Primary,
         _ => ButtonStyle::Secondary,
       };
-      CreateButton::new(id).label(label).style(style)
+      CreateButton::new(id.custom_id(key)).label(label).style(style)
     });
     buttons.collect()
   });
 
+  tracing::debug!("selecting track quality…");
   let components = vec![buttons];
-  let edit = EditInteractionResponse::new().components(components);
-  let msg = ctx.event.edit_response(ctx, edit).await?;
-
-  tracing::debug!("waiting for user interaction…");
-  let mut collector = msg
-    .await_component_interaction(ctx)
-    .author_id(ctx.event.user.id)
-    .timeout(Duration::from_secs(60))
-    .stream();
-
-  let Some(mci) = collector.next().await else {
-    err::timeout!();
-  };
+  let edit = Reply::new().components(components);
+  ctx.edit_reply(edit).await?;
+
+  Ok(())
+}
 
-  mci.defer(ctx).await?;
+#[derive(Serialize, Deserialize)]
+struct Track {
+  url: String,
+  with_banner: bool,
+}
+
+// the parts of a search result that get shown, kept in the response cache
+#[derive(Serialize, Deserialize)]
+struct Found {
+  link: String,
+  title: String,
+  artist: String,
+  album: String,
+  duration: u64,
+}
 
-  Ok(mci.data.custom_id)
+impl From<dz::Track> for Found {
+  fn from(track: dz::Track) -> Self {
+    Self {
+      link: track.link,
+      title: track.title,
+      artist: track.artist.name,
+      album: track.album.title,
+      duration: track.duration,
+    }
+  }
 }
 
 // ---
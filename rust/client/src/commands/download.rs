@@ -1,118 +1,101 @@
-use std::borrow::Cow;
-use std::time::Duration;
-use std::{fs, mem};
+use std::fs;
 
 use ::cache::Name;
 use discord::link::{self, Link};
 use fmt::num::Format as _;
-use futures::StreamExt;
+use gcra::Rate;
 use python::lib::dl::{self, *};
+use serde::{Deserialize, Serialize};
 use serenity::all::*;
+use tempfile::TempDir;
 use url::Url;
 use util::task;
 
-use crate::client::{err, Context, Result};
+use crate::client::{err, Component, ComponentId, Context, Job, Ratelimit, Reply, Result, Scope};
 
-#[macros::command(desc = "Download a media file from YouTube, Twitch, Twitter, etc.")]
+const RATELIMITS: &[Ratelimit] = &[
+  Ratelimit::new(Scope::User, Rate::new(5.0, gcra::minutes(10))),
+  Ratelimit::new(Scope::Guild, Rate::new(15.0, gcra::minutes(10))),
+];
+
+#[macros::command(desc = "Download a media file from YouTube, Twitch, Twitter, etc.", ratelimits = RATELIMITS)]
 pub async fn run(
   ctx: &Context<'_>,
   #[desc = "A YouTube search query or a link to something"] query: &str,
 ) -> Result<()> {
-  ctx.event.defer(ctx).await?;
+  download(ctx, query).await
+}
 
-  tracing::debug!("converting query to url…");
-  let url = query_to_url(ctx, query).await?;
+#[macros::command(context_menu, ratelimits = RATELIMITS)]
+pub async fn menu(ctx: &Context<'_>, message: &Message) -> Result<()> {
+  let Some(url) = link::urls(&message.content).next() else {
+    err::message!("there are no links in this message");
+  };
 
-  tracing::debug!("initializing donwloader…");
-  let tempdir = tempfile::tempdir()?;
-  let dl = Downloader::new(url, &tempdir);
+  download(ctx, url).await
+}
 
-  tracing::debug!("extracting formats…");
-  ctx.progress("extracting formats…").await?;
-  let Ok(dl_ctx) = dl.context.await else {
-    err::message!("failed to extract formats");
-  };
-  tracing::debug!("extracted {} formats", dl_ctx.formats.len());
+async fn download(ctx: &Context<'_>, query: &str) -> Result<()> {
+  ctx.defer().await?;
 
-  let selected_ids = select_format_ids(ctx, &dl_ctx).await?;
-  let selected = dl_ctx.resolve(&selected_ids).unwrap();
-  tracing::debug!("selected {} formats", selected_ids.len());
+  match Url::parse(query) {
+    Ok(_) => select_formats(ctx, query).await,
+    Err(_) => select_video(ctx, query).await,
+  }
+}
 
-  tracing::debug!("downloading…");
-  ctx.progress("downloading…").await?;
-  dl.selected.send(selected_ids).unwrap();
-  let info = dl.finish.await??;
+// the user has picked one of the search results
+pub async fn on_video(ctx: &Context<'_>, component: &Component) -> Result<()> {
+  ctx.defer().await?;
 
-  let Some(Ok(file)) = fs::read_dir(&tempdir)?.next() else {
-    err::message!("failed to download");
-  };
-  let fpath = file.path();
-  let fname = fpath.file_name().unwrap().to_string_lossy();
-  let fsize = fpath.metadata()?.len();
-  tracing::debug!(file = ?fname, "downloaded {}B", fsize.iec());
+  let url = component.value();
+  tracing::debug!(%url, "selected");
+  select_formats(ctx, url).await
+}
 
-  let page_link = Link(&info.title, &info.webpage_url);
+// both formats have to be selected, in any order, before downloading
+pub async fn on_format(ctx: &Context<'_>, component: &Component) -> Result<()> {
+  ctx.defer().await?;
 
-  if fsize > ctx.filesize_limit().await? {
-    let fext = fpath.extension().and_then(|e| e.to_str()).unwrap();
+  let (key, value) = (&*component.key, component.value());
+  tracing::debug!(id = %value, "selected {}", key);
+  let state = component.set::<Formats>(ctx, key, &value).await?;
 
-    tracing::debug!("caching…");
-    let mut url = {
-      let fpath = fpath.clone();
-      let fname = Name::Set(format!("{}.{}", info.title, fext));
-      ctx.client.cache.store_file(fpath, fname).await?.unwrap()
-    };
-    if let Some(params) = fmt_embed_params(&info, &selected) {
-      url.set_query(Some(&params))
-    }
+  let (Some(video), Some(audio)) = (state.video, state.audio) else {
+    return Ok(());
+  };
+  // both selections can complete it at the same time
+  if !component.finish(ctx).await? {
+    return Ok(());
+  }
+  tracing::debug!(%video, %audio, "selected formats");
 
-    let file_link = link::Embed(fext, url.as_str());
-    let content = format!("{} \u{205D} {} {}B", page_link, file_link, fsize.iec());
-    let edit = EditInteractionResponse::new()
-      .components(Default::default()) // remove components
-      .content(content);
+  let _permit = ctx.client.queue.acquire(ctx, Job::Download).await?;
 
-    tracing::debug!("sending response…");
-    ctx.event.edit_response(ctx, edit).await?;
-  } else {
-    let attachment = CreateAttachment::path(&fpath).await?;
-    let edit = EditInteractionResponse::new()
-      .components(Default::default()) // remove components
-      .content(page_link.to_string())
-      .new_attachment(attachment);
+  tracing::debug!("initializing donwloader…");
+  let tempdir = tempfile::tempdir()?;
+  let mut dl = Downloader::new(&state.url, &tempdir);
 
-    tracing::debug!("uploading…");
-    ctx.progress("uploading…").await?;
-    if ctx.event.edit_response(ctx, edit).await.is_err() {
-      err::message!("failed to upload, most likely the file is too big ({fsize}B)");
-    }
-  }
+  tracing::debug!("extracting formats…");
+  ctx.progress("extracting formats…").await?;
+  let Ok(dl_ctx) = (&mut dl.context).await else {
+    err::message!("failed to extract formats");
+  };
 
-  Ok(())
+  fetch(ctx, dl, &dl_ctx, &tempdir, vec![video, audio]).await
 }
 
-async fn query_to_url<'a>(ctx: &Context<'_>, query: &'a str) -> Result<Cow<'a, str>> {
-  match Url::parse(query) {
-    Ok(_) => Ok(Cow::Borrowed(query)),
-    Err(_) => {
-      tracing::debug!("searching…");
-      let query = query.to_owned();
-      let videos = task::spawn_blocking(move || dl::search(&query)).await??;
-      tracing::debug!("found {} videos", videos.len());
-
-      tracing::debug!("selecting video url…");
-      let url = select_video_url(ctx, &videos).await?;
-      tracing::debug!(%url, "selected");
-      Ok(Cow::Owned(url))
-    }
-  }
-}
+async fn select_video(ctx: &Context<'_>, query: &str) -> Result<()> {
+  tracing::debug!("searching…");
+  let query = query.to_owned();
+  let videos = task::spawn_blocking(move || dl::search(&query)).await??;
+  tracing::debug!("found {} videos", videos.len());
 
-async fn select_video_url(ctx: &Context<'_>, videos: &[search::Result]) -> Result<String> {
   if videos.len() == 1 {
-    return Ok(videos[0].url.clone());
+    return select_formats(ctx, &videos[0].url).await;
   }
 
+  let id = ComponentId::new(ctx, "download/video", &()).await?;
   let selector = CreateActionRow::SelectMenu({
     let options = videos.iter().take(25).map(|video| {
       let label = fmt::ellipsis(&video.title, 100);
@@ -125,47 +108,51 @@ async fn select_video_url(ctx: &Context<'_>, videos: &[search::Result]) -> Resul
 
     let options = options.collect();
     let menu = CreateSelectMenuKind::String { options };
-    let menu = CreateSelectMenu::new("url", menu);
+    let menu = CreateSelectMenu::new(id.custom_id("url"), menu);
     menu.placeholder("Select video")
   });
 
+  tracing::debug!("selecting video url…");
   let components = vec![selector];
-  let edit = EditInteractionResponse::new().components(components);
-  let msg = ctx.event.edit_response(ctx, edit).await?;
-
-  tracing::debug!("waiting for user interaction…");
-  let mut collector = msg
-    .await_component_interaction(ctx)
-    .author_id(ctx.event.user.id)
-    .timeout(Duration::from_secs(60))
-    .stream();
-
-  let Some(mut mci) = collector.next().await else {
-    err::timeout!();
-  };
+  let edit = Reply::new().components(components);
+  ctx.edit_reply(edit).await?;
 
-  mci.defer(ctx).await?;
+  Ok(())
+}
 
-  let url = match &mut mci.data.kind {
-    ComponentInteractionDataKind::StringSelect { values } => values.swap_remove(0),
-    _ => unreachable!(),
-  };
+async fn select_formats(ctx: &Context<'_>, url: &str) -> Result<()> {
+  let _permit = ctx.client.queue.acquire(ctx, Job::Download).await?;
 
-  Ok(url)
-}
+  tracing::debug!("initializing donwloader…");
+  let tempdir = tempfile::tempdir()?;
+  let mut dl = Downloader::new(url, &tempdir);
+
+  tracing::debug!("extracting formats…");
+  ctx.progress("extracting formats…").await?;
+  let Ok(dl_ctx) = (&mut dl.context).await else {
+    err::message!("failed to extract formats");
+  };
+  tracing::debug!("extracted {} formats", dl_ctx.formats.len());
 
-async fn select_format_ids(ctx: &Context<'_>, dl_ctx: &download::Context) -> Result<Vec<String>> {
   let formats = dl_ctx.formats.iter().rev().collect::<Vec<_>>();
   let videos = formats.iter().filter(|f| f.is_video()).collect::<Vec<_>>();
   let audios = formats.iter().filter(|f| f.is_audio()).collect::<Vec<_>>();
 
   if videos.is_empty() || audios.is_empty() {
-    return Ok(vec![formats[0].format_id.clone()]);
+    let selected_ids = vec![formats[0].format_id.clone()];
+    return fetch(ctx, dl, &dl_ctx, &tempdir, selected_ids).await;
   }
 
+  let state = Formats {
+    url: url.to_owned(),
+    video: None,
+    audio: None,
+  };
+  let id = ComponentId::new(ctx, "download/format", &state).await?;
+
   let video_selector = CreateActionRow::SelectMenu({
     let options = videos.into_iter().take(25).map(|&f| {
-      let size = fmt_size(dl_ctx, f);
+      let size = fmt_size(&dl_ctx, f);
       let codec = fmt_codec(f.vcodec.as_deref());
       let desc = format!("{} · {} · {}", size, codec, f.ext);
 
@@ -176,13 +163,13 @@ async fn select_format_ids(ctx: &Context<'_>, dl_ctx: &download::Context) -> Res
 
     let options = options.collect();
     let menu = CreateSelectMenuKind::String { options };
-    let menu = CreateSelectMenu::new("video", menu);
+    let menu = CreateSelectMenu::new(id.custom_id("video"), menu);
     menu.placeholder("Select video format")
   });
 
   let audio_selector = CreateActionRow::SelectMenu({
     let options = audios.into_iter().take(25).map(|&f| {
-      let size = fmt_size(dl_ctx, f);
+      let size = fmt_size(&dl_ctx, f);
       let codec = fmt_codec(f.acodec.as_deref());
       let desc = format!("{} · {} · {}", size, codec, f.ext);
 
@@ -193,52 +180,91 @@ async fn select_format_ids(ctx: &Context<'_>, dl_ctx: &download::Context) -> Res
 
     let options = options.collect();
     let menu = CreateSelectMenuKind::String { options };
-    let menu = CreateSelectMenu::new("audio", menu);
+    let menu = CreateSelectMenu::new(id.custom_id("audio"), menu);
     menu.placeholder("Select audio format")
   });
 
+  tracing::debug!("selecting formats…");
   let components = vec![video_selector, audio_selector];
-  let edit = EditInteractionResponse::new().components(components);
-  let msg = ctx.event.edit_response(ctx, edit).await?;
-
-  let mut video_id = None;
-  let mut audio_id = None;
-
-  tracing::debug!("waiting for user interaction…");
-  let mut collector = msg
-    .await_component_interaction(ctx)
-    .author_id(ctx.event.user.id)
-    .timeout(Duration::from_secs(60))
-    .stream();
-
-  loop {
-    let Some(mut mci) = collector.next().await else {
-      err::timeout!();
-    };
+  let edit = Reply::new().components(components);
+  ctx.edit_reply(edit).await?;
+
+  Ok(())
+}
 
-    mci.defer(ctx).await?;
+async fn fetch(
+  ctx: &Context<'_>,
+  dl: Downloader,
+  dl_ctx: &download::Context,
+  tempdir: &TempDir,
+  selected_ids: Vec<String>,
+) -> Result<()> {
+  // formats might have changed since they were selected
+  let Some(selected) = dl_ctx.resolve(&selected_ids) else {
+    err::message!("selected formats are not available anymore");
+  };
+  tracing::debug!("selected {} formats", selected_ids.len());
 
-    let key = &mci.data.custom_id;
-    let value = match &mut mci.data.kind {
-      ComponentInteractionDataKind::StringSelect { values } => values.swap_remove(0),
-      _ => unreachable!(),
-    };
-    tracing::debug!(id = %value, "selected {}", key);
+  tracing::debug!("downloading…");
+  dl.selected.send(selected_ids).unwrap();
+  let Some(info) = ctx.track("downloading…", &dl.tracker, dl.finish).await? else {
+    return Ok(());
+  };
+  let info = info??;
+
+  let Some(Ok(file)) = fs::read_dir(tempdir)?.next() else {
+    err::message!("failed to download");
+  };
+  let fpath = file.path();
+  let fname = fpath.file_name().unwrap().to_string_lossy();
+  let fsize = fpath.metadata()?.len();
+  tracing::debug!(file = ?fname, "downloaded {}B", fsize.iec());
+
+  let page_link = Link(&info.title, &info.webpage_url);
+
+  if fsize > ctx.filesize_limit().await? {
+    let fext = fpath.extension().and_then(|e| e.to_str()).unwrap();
 
-    match key.as_ref() {
-      "video" => video_id = Some(value),
-      "audio" => audio_id = Some(value),
-      _ => unreachable!(),
+    tracing::debug!("caching…");
+    let mut url = {
+      let fpath = fpath.clone();
+      let fname = Name::Set(format!("{}.{}", info.title, fext));
+      ctx.client.cache.store_file(fpath, fname).await?.unwrap()
+    };
+    if let Some(params) = fmt_embed_params(&info, &selected) {
+      url.set_query(Some(&params))
     }
 
-    if let (Some(video), Some(audio)) = (&mut video_id, &mut audio_id) {
-      let video = mem::take(video);
-      let audio = mem::take(audio);
-      tracing::debug!(%video, %audio, "selected formats");
+    let file_link = link::Embed(fext, url.as_str());
+    let content = format!("{} \u{205D} {} {}B", page_link, file_link, fsize.iec());
+    let edit = Reply::new()
+      .components(Default::default()) // remove components
+      .content(content);
+
+    tracing::debug!("sending response…");
+    ctx.edit_reply(edit).await?;
+  } else {
+    let attachment = CreateAttachment::path(&fpath).await?;
+    let edit = Reply::new()
+      .components(Default::default()) // remove components
+      .content(page_link.to_string())
+      .new_attachment(attachment);
 
-      return Ok(vec![video, audio]);
+    tracing::debug!("uploading…");
+    ctx.progress("uploading…").await?;
+    if ctx.edit_reply(edit).await.is_err() {
+      err::message!("failed to upload, most likely the file is too big ({fsize}B)");
     }
   }
+
+  Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+struct Formats {
+  url: String,
+  video: Option<String>,
+  audio: Option<String>,
 }
 
 // ---
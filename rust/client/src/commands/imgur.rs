@@ -1,37 +1,48 @@
-use reqwest::{header, multipart};
+use std::time::Duration;
+
+use reqwest::{header, multipart, Body};
 use serde::Deserialize;
 use serenity::all::*;
 use url::Url;
 
-use crate::client::{err, Context, Result};
+use crate::client::{err, Context, Reply, Result};
+
+// the most imgur takes, that's for videos, images have a much lower one
+const LIMIT: u64 = 200 << 20;
+// the file is streamed right into the upload, so this covers both
+const TIMEOUT: Duration = Duration::from_secs(300);
 
-#[macros::command(desc = "Upload a media file to Imgur")]
+#[macros::command(desc = "Upload a media file to Imgur", requires = "imgur")]
 pub async fn file(ctx: &Context<'_>, file: &Attachment) -> Result<()> {
   upload(ctx, &file.url, &file.filename).await
 }
 
-#[macros::command(desc = "Upload a media file to Imgur by URL")]
-pub async fn url(ctx: &Context<'_>, url: &str) -> Result<()> {
-  let parsed = Url::parse(url)?;
-  let filename = parsed.path_segments().and_then(|s| s.last()).unwrap();
-  upload(ctx, url, filename).await
+#[macros::command(desc = "Upload a media file to Imgur by URL", requires = "imgur")]
+pub async fn url(ctx: &Context<'_>, url: Url) -> Result<()> {
+  let filename = url.path_segments().and_then(|s| s.last()).unwrap_or_default();
+  upload(ctx, url.as_str(), filename).await
 }
 
 async fn upload(ctx: &Context<'_>, url: &str, filename: &str) -> Result<()> {
-  ctx.event.defer(ctx).await?;
+  ctx.defer().await?;
 
-  let resp = reqwest::get(url).await?.error_for_status()?;
-  let body = reqwest::Body::wrap_stream(resp.bytes_stream());
+  let http = &ctx.client.http;
+  let get = http.get(url).limit(LIMIT).timeout(TIMEOUT);
+  let resp = get.send().await?.error_for_status()?;
+  let body = Body::wrap_stream(resp.bytes_stream());
   let image = multipart::Part::stream(body).file_name(filename.to_owned());
   let form = multipart::Form::new().part("image", image);
 
-  let auth = format!("Client-ID {}", ctx.client.env.imgur_app_id);
+  let auth = format!(
+    "Client-ID {}",
+    ctx.client.config.imgur_app_id.as_deref().unwrap_or_default()
+  );
 
-  let post = reqwest::Client::builder()
-    .build()?
+  let post = http
     .post("https://api.imgur.com/3/upload")
     .header(header::AUTHORIZATION, auth)
     .multipart(form)
+    .timeout(TIMEOUT)
     .send();
 
   tracing::debug!("uploading…");
@@ -46,8 +57,8 @@ async fn upload(ctx: &Context<'_>, url: &str, filename: &str) -> Result<()> {
   // TODO: probably should insert some kind of delay
   // to avoid the 404 thumbnail issue
   tracing::debug!("sending response…");
-  let edit = EditInteractionResponse::new().content(url);
-  ctx.event.edit_response(ctx, edit).await?;
+  let edit = Reply::new().content(url);
+  ctx.edit_reply(edit).await?;
 
   Ok(())
 }
@@ -1,20 +1,21 @@
-use serenity::all::*;
+use crate::client::{err, tr, Context, Reply, Result};
 
-use crate::client::{err, Context, Result};
-
-#[macros::command(desc = "Look up a movie on IMDB")]
+#[macros::command(
+  desc = "Look up a movie on IMDB",
+  l10n(ru(desc = "Найти фильм на IMDB"), ja(desc = "IMDBで映画を検索"))
+)]
 pub async fn run(ctx: &Context<'_>, movie: &str) -> Result<()> {
-  ctx.event.defer(ctx).await?;
+  ctx.defer().await?;
 
   tracing::debug!("fetching json…");
-  let json = match api::query(movie).await {
+  let json = match api::query(&ctx.client.http, movie).await {
     Ok(Some(html)) => api::extract_json(&html)?,
-    _ => err::message!("could not find anything"),
+    _ => err::message!("{}", tr!(ctx, "could not find anything")),
   };
 
   tracing::debug!("sending response…");
-  let edit = EditInteractionResponse::new().embed(json.embed()?);
-  ctx.event.edit_response(ctx, edit).await?;
+  let edit = Reply::new().embed(json.embed()?);
+  ctx.edit_reply(edit).await?;
 
   Ok(())
 }
@@ -22,6 +23,7 @@ pub async fn run(ctx: &Context<'_>, movie: &str) -> Result<()> {
 mod api {
   use std::borrow::Cow;
   use std::fmt::Write;
+  use std::time::Duration;
 
   use fmt::num::Format as _;
   use reqwest::header;
@@ -30,6 +32,9 @@ mod api {
   use serenity::all::*;
   use url::Url;
   use util::html;
+  use util::network::{self, Http};
+
+  const TTL: Duration = Duration::from_secs(24 * 60 * 60);
 
   // ---
 
@@ -93,13 +98,11 @@ mod api {
 
   // ---
 
-  pub async fn query(movie: &str) -> reqwest::Result<Option<String>> {
-    let client = reqwest::Client::builder().build()?;
-
+  pub async fn query(http: &Http, movie: &str) -> network::Result<Option<String>> {
     let url = "https://v2.sg.media-imdb.com/suggestion/h";
     let mut url = Url::parse(url).unwrap();
     url.path_segments_mut().unwrap().push(&format!("{}.json", movie));
-    let req = client.get(url);
+    let req = http.get(url).cached("imdb", TTL);
     let res = req.send().await?.error_for_status()?;
     let json: Search = res.json().await?;
 
@@ -108,7 +111,7 @@ mod api {
     };
 
     let url = format!("https://www.imdb.com/title/{}", movie.id);
-    let req = client.get(url).header(header::ACCEPT_LANGUAGE, "en");
+    let req = http.get(url).header(header::ACCEPT_LANGUAGE, "en").cached("imdb", TTL);
     let res = req.send().await?.error_for_status()?;
     let html = res.text().await?;
 
@@ -185,3 +188,42 @@ mod api {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::client::testing::Harness;
+
+  #[tokio::test]
+  async fn run() {
+    let mut harness = Harness::new().await;
+    let stub = &harness.stub;
+    stub.serve(
+      "v2.sg.media-imdb.com/suggestion/h/spirited.json",
+      "imdb/suggestion.json",
+    );
+    stub.serve("www.imdb.com/title/tt0245429", "imdb/title.html");
+    harness.run(super::run, json!({ "movie": "spirited" })).await.unwrap();
+
+    // people come first in suggestions, they're skipped
+    let embed = &harness.replies()[0]["embeds"][0];
+    assert_eq!(embed["title"], "Spirited Away (2003)");
+    assert_eq!(embed["url"], "https://www.imdb.com/title/tt0245429/");
+    let desc = embed["description"].as_str().unwrap();
+    assert!(desc.starts_with("During her family's move"), "{desc}");
+    assert!(desc.contains("\nDirectors: [Hayao Miyazaki](https://www.imdb.com/name/nm0594503/)\n"));
+    assert!(
+      desc.ends_with("8.6/10 (826k) · 2h5m · Animation, Adventure, Family"),
+      "{desc}"
+    );
+  }
+
+  #[tokio::test]
+  async fn nothing() {
+    let mut harness = Harness::new().await;
+    harness.stub.fail("v2.sg.media-imdb.com/suggestion/h/asdf.json", 404);
+    let err = harness.run(super::run, json!({ "movie": "asdf" })).await.unwrap_err();
+    assert_eq!(err.to_string(), r#"Message("could not find anything")"#);
+  }
+}
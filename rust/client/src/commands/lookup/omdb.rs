@@ -1,51 +1,64 @@
-use serenity::all::*;
-
-use crate::client::{err, Context, Result};
+use crate::client::{err, Context, Reply, Result};
 
 use self::api::Response::*;
 
-#[macros::command(desc = "Look up a movie on OMDB")]
+#[macros::command(
+  desc = "Look up a movie on OMDB",
+  requires = "omdb",
+  l10n(ru(desc = "Найти фильм на OMDB"), ja(desc = "OMDBで映画を検索"))
+)]
 pub async fn run(ctx: &Context<'_>, movie: &str) -> Result<()> {
-  ctx.event.defer(ctx).await?;
+  ctx.defer().await?;
 
   tracing::debug!("fetching json…");
-  let api = api::Api::new(&ctx.client.env.omdb_api_key);
+  let api = api::Api::new(
+    &ctx.client.http,
+    ctx.client.config.omdb_api_key.as_deref().unwrap_or_default(),
+  );
   let json = match api.query(movie).await? {
     Success(json) => json,
     Error(err) => err::message!("{}", err.error),
   };
 
   tracing::debug!("sending response…");
-  let edit = EditInteractionResponse::new().embed(json.embed()?);
-  ctx.event.edit_response(ctx, edit).await?;
+  let edit = Reply::new().embed(json.embed()?);
+  ctx.edit_reply(edit).await?;
 
   Ok(())
 }
 
 mod api {
   use std::fmt::Write;
+  use std::time::Duration;
 
   use serde::Deserialize;
   use serenity::all::*;
   use url::Url;
+  use util::network::{self, Http};
+
+  // movies don't change much once they're out
+  const TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
 
   pub struct Api<'a> {
+    http: &'a Http,
     key: &'a str,
   }
 
   impl<'a> Api<'a> {
-    pub fn new(key: &'a str) -> Self {
-      Self { key }
+    pub fn new(http: &'a Http, key: &'a str) -> Self {
+      Self { http, key }
     }
 
-    pub async fn query(&self, title: &str) -> reqwest::Result<Response> {
+    pub async fn query(&self, title: &str) -> network::Result<Response> {
       let params = &[("apikey", self.key), ("t", title)];
       let url = "https://www.omdbapi.com";
       let url = Url::parse_with_params(url, params).unwrap();
       tracing::debug!(%url);
 
-      let resp = reqwest::get(url).await?.error_for_status()?;
-      let json = resp.json().await?;
+      let get = self.http.get(url).cached("omdb", TTL);
+      let resp = get.send().await?.error_for_status()?;
+      // not finding anything is still a 200
+      let json = resp.json_cached_if(|json| matches!(json, Response::Success(_))).await?;
       Ok(json)
     }
   }
@@ -168,3 +181,45 @@ mod api {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::client::testing::Harness;
+
+  #[tokio::test]
+  async fn run() {
+    let mut harness = Harness::new().await;
+    harness.stub.serve("www.omdbapi.com/", "omdb/movie.json");
+    harness
+      .run(super::run, json!({ "movie": "spirited away" }))
+      .await
+      .unwrap();
+
+    let embed = &harness.replies()[0]["embeds"][0];
+    assert_eq!(embed["title"], "Spirited Away (2001)");
+    assert_eq!(embed["url"], "https://www.imdb.com/title/tt0245429");
+    assert_eq!(embed["footer"]["text"], "125 min · Animation, Adventure, Family");
+
+    let fields = embed["fields"].as_array().unwrap();
+    let details = fields[1]["value"].as_str().unwrap();
+    assert!(
+      details.starts_with("**Countries:** Japan\n**Languages:** Japanese\n"),
+      "{details}"
+    );
+    let ratings = fields[2]["value"].as_str().unwrap();
+    assert_eq!(
+      ratings,
+      "**IMDB:** 8.6 (826,435 votes)\n**Rotten Tomatoes:** 96%\n**Metacritic:** 96/100\n"
+    );
+  }
+
+  #[tokio::test]
+  async fn not_found() {
+    let mut harness = Harness::new().await;
+    harness.stub.serve("www.omdbapi.com/", "omdb/not_found.json");
+    let err = harness.run(super::run, json!({ "movie": "asdf" })).await.unwrap_err();
+    assert_eq!(err.to_string(), r#"Message("Movie not found!")"#);
+  }
+}
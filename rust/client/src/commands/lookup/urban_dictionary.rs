@@ -1,5 +1,6 @@
 use std::fmt::{self, Display, Formatter};
 use std::sync::OnceLock;
+use std::time::Duration;
 
 use chrono::DateTime;
 use regex::Regex;
@@ -7,23 +8,32 @@ use regex_ext::RegexExt;
 use serde::Deserialize;
 use serenity::all::*;
 use url::Url;
+use util::network::{self, Http};
 
-use crate::client::{err, Context, Result};
+use crate::client::{err, tr, Context, Reply, Result};
 
-#[macros::command(desc = "Look up a term on Urban Dictionary")]
+const TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[macros::command(
+  desc = "Look up a term on Urban Dictionary",
+  l10n(
+    ru(desc = "Найти термин в Urban Dictionary"),
+    ja(desc = "Urban Dictionaryで用語を検索")
+  )
+)]
 pub async fn run(ctx: &Context<'_>, term: &str) -> Result<()> {
-  ctx.event.defer(ctx).await?;
+  ctx.defer().await?;
 
   tracing::debug!("fetching json…");
-  let json = Json::get(term, 1).await?;
+  let json = Json::get(&ctx.client.http, term, 1).await?;
 
   let Some(def) = json.list.iter().find(|d| d.thumbs_up >= d.thumbs_down) else {
-    err::message!("could not find anything");
+    err::message!("{}", tr!(ctx, "could not find anything"));
   };
 
   tracing::debug!("sending response…");
-  let edit = EditInteractionResponse::new().embed(def.embed());
-  ctx.event.edit_response(ctx, edit).await?;
+  let edit = Reply::new().embed(def.embed());
+  ctx.edit_reply(edit).await?;
 
   Ok(())
 }
@@ -48,7 +58,7 @@ struct Definition {
 // ---
 
 impl Json {
-  async fn get(term: &str, pages: usize) -> reqwest::Result<Self> {
+  async fn get(http: &Http, term: &str, pages: usize) -> network::Result<Self> {
     let url = "https://api.urbandictionary.com/v0/define";
     let url = Url::parse_with_params(url, &[("term", &term)]).unwrap();
 
@@ -59,7 +69,8 @@ impl Json {
       url.query_pairs_mut().append_pair("page", &page.to_string());
       tracing::trace!(%url);
 
-      let resp = reqwest::get(url).await?.error_for_status()?;
+      let get = http.get(url).cached("urban_dictionary", TTL);
+      let resp = get.send().await?.error_for_status()?;
       let json = resp.json::<Self>().await?;
 
       if json.list.is_empty() {
@@ -113,3 +124,40 @@ impl Display for TermLinks<'_> {
     })
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::client::testing::Harness;
+
+  const DEFINE: &str = "api.urbandictionary.com/v0/define";
+
+  #[tokio::test]
+  async fn run() {
+    let mut harness = Harness::new().await;
+    harness.stub.serve(DEFINE, "urban_dictionary/define.json");
+    harness
+      .run(super::run, json!({ "term": "borrow checker" }))
+      .await
+      .unwrap();
+
+    let requests = harness.stub.requests();
+    assert_eq!(requests[0].query(), Some("term=borrow+checker&page=1"));
+
+    // the first definition has more downvotes than upvotes
+    let embed = &harness.replies()[0]["embeds"][0];
+    assert_eq!(embed["title"], "borrow checker");
+    assert_eq!(embed["footer"]["text"], "👍42 👎7");
+    let desc = embed["description"].as_str().unwrap();
+    assert!(desc.starts_with("The part of the [Rust](https://urbandictionary.com/define.php?term=Rust) compiler"));
+  }
+
+  #[tokio::test]
+  async fn nothing() {
+    let mut harness = Harness::new().await;
+    harness.stub.serve(DEFINE, "urban_dictionary/empty.json");
+    let err = harness.run(super::run, json!({ "term": "asdf" })).await.unwrap_err();
+    assert_eq!(err.to_string(), r#"Message("could not find anything")"#);
+  }
+}
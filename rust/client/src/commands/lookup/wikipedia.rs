@@ -1,30 +1,46 @@
+use std::time::Duration;
+
 use ego_tree::NodeRef;
 use scraper::{Html, Node};
 use serde::Deserialize;
 use serenity::all::*;
 use url::Url;
+use util::network::{self, Http};
+
+use crate::client::{err, tr, Context, Reply, Result};
 
-use crate::client::{err, Context, Result};
+const TTL: Duration = Duration::from_secs(24 * 60 * 60);
 
-#[macros::command(desc = "Look up a term on Wikipedia")]
+#[macros::command(
+  desc = "Look up a term on Wikipedia",
+  l10n(ru(desc = "Найти статью в Википедии"), ja(desc = "ウィキペディアで検索"))
+)]
 pub async fn run(ctx: &Context<'_>, term: &str) -> Result<()> {
-  ctx.event.defer(ctx).await?;
+  ctx.defer().await?;
+
+  // look in the wikipedia of the user's language first, then fall back to english
+  let lang = ctx.event.locale.split('-').next().unwrap_or("en");
 
-  tracing::debug!("fetching json…");
-  let Ok(json) = Json::get(term, "en").await else {
-    err::message!("could not find anything");
+  tracing::debug!(lang, "fetching json…");
+  let http = &ctx.client.http;
+  let json = match Json::get(http, term, lang).await {
+    Err(_) if lang != "en" => Json::get(http, term, "en").await,
+    json => json,
+  };
+  let Ok(json) = json else {
+    err::message!("{}", tr!(ctx, "could not find anything"));
   };
 
   let json = match json {
     Json::Standard(json) => json,
     Json::Disambiguation => {
-      err::message!("too ambiguous, be more specific");
+      err::message!("{}", tr!(ctx, "too ambiguous, be more specific"));
     }
   };
 
   tracing::debug!("sending response…");
-  let edit = EditInteractionResponse::new().embed(json.embed());
-  ctx.event.edit_response(ctx, edit).await?;
+  let edit = Reply::new().embed(json.embed());
+  ctx.edit_reply(edit).await?;
 
   Ok(())
 }
@@ -66,7 +82,7 @@ struct Desktop {
 // ---
 
 impl Json {
-  async fn get(term: &str, lang: &str) -> reqwest::Result<Self> {
+  async fn get(http: &Http, term: &str, lang: &str) -> network::Result<Self> {
     // let mut cyrillic = term.matches(|c| matches!(c, 'а'..='я' | 'А'..='Я'));
     // let lang = if cyrillic.next().is_some() { "ru" } else { "en" };
 
@@ -75,7 +91,8 @@ impl Json {
     url.path_segments_mut().unwrap().push(term);
     tracing::debug!(%url);
 
-    let resp = reqwest::get(url).await?.error_for_status()?;
+    let get = http.get(url).cached("wikipedia", TTL);
+    let resp = get.send().await?.error_for_status()?;
     let json = resp.json().await?;
     Ok(json)
   }
@@ -131,3 +148,40 @@ impl Standard {
     acc
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::client::testing::Harness;
+
+  #[tokio::test]
+  async fn run() {
+    let mut harness = Harness::new().await;
+    let path = "en.wikipedia.org/api/rest_v1/page/summary/rust";
+    harness.stub.serve(path, "wikipedia/summary.json");
+    harness.run(super::run, json!({ "term": "rust" })).await.unwrap();
+
+    let embed = &harness.replies()[0]["embeds"][0];
+    assert_eq!(embed["title"], "Rust (programming language)");
+    assert_eq!(
+      embed["url"],
+      "https://en.wikipedia.org/wiki/Rust_(programming_language)"
+    );
+    let desc = embed["description"].as_str().unwrap();
+    assert!(
+      desc.starts_with("**Rust** is a *general-purpose* programming language"),
+      "{desc}"
+    );
+    assert!(embed["thumbnail"]["url"].as_str().unwrap().ends_with(".svg"));
+  }
+
+  #[tokio::test]
+  async fn disambiguation() {
+    let mut harness = Harness::new().await;
+    let path = "en.wikipedia.org/api/rest_v1/page/summary/rust";
+    harness.stub.serve(path, "wikipedia/disambiguation.json");
+    let err = harness.run(super::run, json!({ "term": "rust" })).await.unwrap_err();
+    assert_eq!(err.to_string(), r#"Message("too ambiguous, be more specific")"#);
+  }
+}
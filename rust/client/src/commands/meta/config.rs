@@ -0,0 +1,130 @@
+use std::fmt::Write;
+
+use serenity::all::*;
+
+use crate::client::{err, walk, Command, Context, Registration, Reply, Result};
+use crate::db;
+
+// disabling this one would lock server admins out of the configuration
+const SELF: &str = "/meta config";
+
+#[macros::command(
+  desc = "Enable, disable or restrict my commands on this server (admins only)",
+  guild_only,
+  permissions = "manage_guild"
+)]
+pub async fn run(
+  ctx: &Context<'_>,
+  #[desc = "The command to configure (e.g.: /deezer as-file)"]
+  #[autocomplete = commands]
+  command: Option<&str>,
+  #[desc = "Whether the command should be enabled, omit to see the current state"] enabled: Option<bool>,
+  #[desc = "Allow or disallow a role, once any are allowed only they can use the command"] role: Option<&Role>,
+) -> Result<()> {
+  // it's `guild_only`
+  let gid = ctx.event.guild_id.unwrap();
+
+  let commands = configurable(ctx);
+  if command.is_none() && (enabled.is_some() || role.is_some()) {
+    err::message!("pick a command to configure");
+  }
+
+  // registering commands takes a while, so the reply is deferred then
+  let mut deferred = false;
+  let text = match command {
+    None => {
+      let enabled = ctx.client.enabled_commands(Some(gid)).await?;
+      let allowlists = db::guild_command_roles::all(&ctx.client.db, gid).await?;
+      let mut acc = String::new();
+      for (path, cmd) in commands {
+        let enabled = enabled.iter().any(|(p, _)| *p == path);
+        let opt_in = if cmd.registration == Registration::Guild {
+          " (opt-in)"
+        } else {
+          ""
+        };
+        let roles = roles(allowlists.get(&path).map_or(&[], Vec::as_slice));
+        writeln!(acc, "{} `{}`{}{}", mark(enabled), path, opt_in, roles)?;
+      }
+      acc
+    }
+    Some(path) => {
+      let Some((path, cmd)) = commands.into_iter().find(|(p, _)| p == path.trim()) else {
+        err::message!("unknown command: {}", path);
+      };
+
+      if let Some(enabled) = enabled {
+        if path == SELF {
+          err::message!("{} can't be disabled", SELF);
+        }
+
+        tracing::debug!(%path, enabled, "updating guild config…");
+        db::guild_commands::set(&ctx.client.db, gid, &path, enabled).await?;
+
+        tracing::debug!("registering guild commands…");
+        ctx.defer_ephemeral().await?;
+        deferred = true;
+        ctx.client.register_guild_commands(&ctx.serenity.http, gid).await?;
+      }
+
+      if let Some(role) = role {
+        let allowed = db::guild_command_roles::toggle(&ctx.client.db, gid, &path, role.id).await?;
+        tracing::debug!(%path, role = %role.id, allowed, "updated allowed roles");
+      }
+
+      let enabled = ctx.client.is_command_enabled(Some(gid), &path, cmd).await?;
+      let allowed = db::guild_command_roles::get(&ctx.client.db, gid, &path).await?;
+      format!(
+        "{} `{}` is {}{}",
+        mark(enabled),
+        path,
+        if enabled { "enabled" } else { "disabled" },
+        roles(&allowed),
+      )
+    }
+  };
+
+  let embed = CreateEmbed::new().description(text);
+
+  tracing::debug!("sending response…");
+  if deferred {
+    ctx.edit_reply(Reply::new().embed(embed)).await?;
+  } else {
+    ctx.reply(Reply::new().embed(embed).ephemeral(true)).await?;
+  }
+
+  Ok(())
+}
+
+async fn commands(ctx: &Context<'_>, command: &str) -> Result<Vec<AutocompleteChoice>> {
+  let query = command.trim().trim_start_matches('/').to_lowercase();
+  let choices = configurable(ctx).into_iter().filter(|(path, _)| path.contains(&query));
+  let choices = choices.map(|(path, _)| AutocompleteChoice::new(&path, path.clone()));
+  Ok(choices.collect())
+}
+
+// ---
+
+fn configurable<'a>(ctx: &Context<'a>) -> Vec<(String, &'a Command)> {
+  let commands = walk(&ctx.client.commands).into_iter();
+  commands
+    .filter(|(_, cmd)| cmd.registration != Registration::Dev)
+    .collect()
+}
+
+// members with the Manage Server permission can use it either way
+fn roles(roles: &[RoleId]) -> String {
+  if roles.is_empty() {
+    return String::new();
+  }
+  let roles = roles.iter().map(|id| id.mention().to_string());
+  format!(", only for {}", roles.collect::<Vec<_>>().join(", "))
+}
+
+fn mark(enabled: bool) -> &'static str {
+  if enabled {
+    "\u{2705}"
+  } else {
+    "\u{274c}"
+  }
+}
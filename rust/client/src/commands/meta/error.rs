@@ -0,0 +1,59 @@
+use std::fmt::Write;
+
+use serenity::all::*;
+use util::hash::splitmix64;
+
+use crate::client::{err, Context, Reply, Result};
+use crate::db;
+
+#[macros::command(desc = "Show the full report of an error (owner only)", owner_only, register = "dev")]
+pub async fn run(ctx: &Context<'_>, #[desc = "The ERROR ID shown to the user"] id: &str) -> Result<()> {
+  let Ok(mixed) = u64::from_str_radix(id.trim(), 16) else {
+    err::message!("invalid error id: {}", id);
+  };
+  let id = splitmix64::unmix(mixed);
+
+  tracing::debug!(id, "fetching error…");
+  let Some(row) = db::errors::get(&ctx.client.db, id).await? else {
+    err::message!("could not find error {:016X}", mixed);
+  };
+
+  let content = {
+    let mut acc = String::new();
+    write!(acc, "`{}` by <@{}>", fmt::ellipsis(&row.command, 200), row.user)?;
+    if let Some(guild) = row.guild {
+      write!(acc, " on `{}`", guild)?;
+    }
+    writeln!(acc, " <t:{}:R>", row.time)?;
+    if let Some(display) = row.error.display.as_ref().or(row.error.panic.as_ref()) {
+      write!(acc, "```\n{}\n```", fmt::ellipsis(display, 1500))?;
+    }
+    acc
+  };
+
+  let report = {
+    let error = &row.error;
+    let sections = [
+      ("display", &error.display),
+      ("debug", &error.debug),
+      ("panic", &error.panic),
+      ("traceback", &error.traceback),
+    ];
+    let mut acc = String::new();
+    writeln!(acc, "{}\n", row.command)?;
+    for (name, text) in sections {
+      if let Some(text) = text {
+        writeln!(acc, "--- {} ---\n{}\n", name, text)?;
+      }
+    }
+    acc
+  };
+
+  let file = CreateAttachment::bytes(report, format!("error-{:016X}.txt", mixed));
+  let reply = Reply::new().content(content).new_attachment(file).ephemeral(true);
+
+  tracing::debug!("sending response…");
+  ctx.reply(reply).await?;
+
+  Ok(())
+}
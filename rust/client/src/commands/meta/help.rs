@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use serenity::all::*;
+
+use crate::client::{denial, details, err, notes, type_name, Command, Component, ComponentId, Context};
+use crate::client::{Invoker, Reply, Result};
+use crate::db::guild_command_roles;
+
+const ROUTE: &str = "help/page";
+// commands on a page of the overview
+const PER_PAGE: usize = 10;
+
+#[macros::command(desc = "Show what my commands do")]
+pub async fn run(
+  ctx: &Context<'_>,
+  #[desc = "The command to show in detail (e.g.: /deezer as-file)"]
+  #[autocomplete = commands]
+  command: Option<&str>,
+) -> Result<()> {
+  let commands = visible(ctx).await?;
+
+  let reply = match command {
+    None => {
+      let id = ComponentId::new(ctx, ROUTE, &()).await?;
+      page(&commands, id, 0)
+    }
+    Some(path) => {
+      let Some((path, cmd)) = commands.iter().find(|(p, _)| p == path.trim()) else {
+        err::message!("unknown command: {}", path);
+      };
+      Reply::new().embed(command_embed(path, cmd))
+    }
+  };
+
+  tracing::debug!("sending response…");
+  ctx.reply(reply.ephemeral(true)).await?;
+
+  Ok(())
+}
+
+// the key is the page to go to
+pub async fn on_page(ctx: &Context<'_>, component: &Component) -> Result<()> {
+  ctx.defer().await?;
+
+  let commands = visible(ctx).await?;
+  let id = ComponentId::of(ROUTE, component);
+  let reply = page(&commands, id, component.key.parse()?);
+
+  tracing::debug!("turning the page…");
+  ctx.edit_reply(reply).await?;
+
+  Ok(())
+}
+
+async fn commands(ctx: &Context<'_>, command: &str) -> Result<Vec<AutocompleteChoice>> {
+  let query = command.trim().trim_start_matches('/').to_lowercase();
+  let commands = visible(ctx).await?.into_iter();
+  let choices = commands
+    .filter(|(path, _)| path.to_lowercase().contains(&query))
+    .take(25)
+    .map(|(path, _)| AutocompleteChoice::new(&path, path.clone()));
+  Ok(choices.collect())
+}
+
+// ---
+
+// only what can be used here, by whoever is asking, going by the permissions commands require by default,
+// since there's no telling how admins have overridden them
+async fn visible<'a>(ctx: &Context<'a>) -> Result<Vec<(String, &'a Command)>> {
+  let mut commands = ctx.client.enabled_commands(ctx.event.guild_id).await?;
+
+  let who = {
+    let commands = commands.iter().map(|&(_, cmd)| cmd).collect::<Vec<_>>();
+    Invoker::new(ctx, &commands).await?
+  };
+  let allowlists = match ctx.event.guild_id {
+    Some(gid) => guild_command_roles::all(&ctx.client.db, gid).await?,
+    None => HashMap::new(),
+  };
+
+  commands.retain(|(path, cmd)| {
+    let allowed = allowlists.get(path).map_or(&[][..], Vec::as_slice);
+    denial(cmd, &who, allowed, None).is_none()
+  });
+  Ok(commands)
+}
+
+fn page(commands: &[(String, &Command)], id: ComponentId, page: usize) -> Reply {
+  let pages = commands.len().div_ceil(PER_PAGE).max(1);
+  let page = page.min(pages - 1);
+
+  let fields = commands.iter().skip(page * PER_PAGE).take(PER_PAGE).map(|(path, cmd)| {
+    let desc = match cmd.description {
+      Some(desc) => desc.to_owned(),
+      None => notes(cmd).join(", "),
+    };
+    (format!("`{path}`"), fmt::ellipsis(&desc, 1024).into_owned(), false)
+  });
+  let footer = format!("Page {}/{} · /help <command> for more", page + 1, pages);
+  let embed = CreateEmbed::new()
+    .title("Commands")
+    .fields(fields)
+    .footer(CreateEmbedFooter::new(footer));
+
+  // custom ids have to be unique, and they are, since the previous page is always before the next one
+  let buttons = CreateActionRow::Buttons(vec![
+    CreateButton::new(id.custom_id(&page.saturating_sub(1).to_string()))
+      .label("←")
+      .style(ButtonStyle::Secondary)
+      .disabled(page == 0),
+    CreateButton::new(id.custom_id(&(page + 1).to_string()))
+      .label("→")
+      .style(ButtonStyle::Secondary)
+      .disabled(page + 1 >= pages),
+  ]);
+
+  Reply::new().embed(embed).components(vec![buttons])
+}
+
+fn command_embed(path: &str, cmd: &Command) -> CreateEmbed {
+  let notes = notes(cmd).into_iter().map(|note| format!("-# {note}"));
+  let desc = cmd.description.map(str::to_owned).into_iter().chain(notes);
+  let desc = desc.collect::<Vec<_>>().join("\n");
+
+  let fields = cmd.options.iter().map(|opt| {
+    let name = match opt.required {
+      true => format!("`{}`", opt.name),
+      false => format!("`{}` (optional)", opt.name),
+    };
+    let lines = [opt.description.unwrap_or("…").to_owned(), type_name(opt.ty).to_owned()];
+    let value = lines.into_iter().chain(details(opt)).collect::<Vec<_>>().join("\n");
+    (name, fmt::ellipsis(&value, 1024).into_owned(), false)
+  });
+
+  let embed = CreateEmbed::new().title(path).fields(fields);
+  match desc.is_empty() {
+    true => embed,
+    false => embed.description(desc),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+  use serenity::all::{Permissions, RoleId};
+
+  use crate::client::testing::Harness;
+  use crate::client::{CommandTree, Context, Result};
+  use crate::db::{guild_command_roles, guild_commands};
+
+  #[macros::command(desc = "Has to be enabled", register = "guild")]
+  async fn opt_in(_ctx: &Context<'_>) -> Result<()> {
+    Ok(())
+  }
+
+  #[macros::command(desc = "Only on servers", guild_only)]
+  async fn server(_ctx: &Context<'_>) -> Result<()> {
+    Ok(())
+  }
+
+  #[macros::command(desc = "Bans", permissions = "ban_members")]
+  async fn ban(_ctx: &Context<'_>) -> Result<()> {
+    Ok(())
+  }
+
+  // the commands on the first page of the last reply
+  fn listed(harness: &Harness) -> Vec<String> {
+    let replies = harness.replies();
+    let fields = replies.last().unwrap()["embeds"][0]["fields"]
+      .as_array()
+      .unwrap()
+      .iter();
+    fields.map(|field| field["name"].as_str().unwrap().to_owned()).collect()
+  }
+
+  #[tokio::test]
+  async fn overview() {
+    let mut harness = Harness::new().await;
+    harness.run(super::run, json!({})).await.unwrap();
+
+    // the harness registers only the command it runs
+    let reply = &harness.replies()[0];
+    assert_eq!(reply["embeds"][0]["fields"][0]["name"], "`/command`");
+    assert_eq!(reply["embeds"][0]["fields"][0]["value"], "Show what my commands do");
+    assert_eq!(
+      reply["embeds"][0]["footer"]["text"],
+      "Page 1/1 · /help <command> for more"
+    );
+    assert_eq!(reply["components"][0]["components"][1]["disabled"], true);
+  }
+
+  #[tokio::test]
+  async fn details() {
+    let mut harness = Harness::new().await;
+    harness.run(super::run, json!({ "command": "/command" })).await.unwrap();
+
+    let embed = &harness.replies()[0]["embeds"][0];
+    assert_eq!(embed["title"], "/command");
+    assert_eq!(embed["fields"][0]["name"], "`command` (optional)");
+    assert!(embed["fields"][0]["value"]
+      .as_str()
+      .unwrap()
+      .starts_with("The command to show in detail"));
+
+    let err = harness
+      .run(super::run, json!({ "command": "/nope" }))
+      .await
+      .unwrap_err();
+    assert_eq!(err.to_string(), r#"Message("unknown command: /nope")"#);
+  }
+
+  #[tokio::test]
+  async fn hides_what_cant_be_used() {
+    let mut harness = Harness::new().await;
+    harness.client.commands.extend([
+      ("opt-in", CommandTree::Command(opt_in("opt-in"))),
+      ("server", CommandTree::Command(server("server"))),
+      ("ban", CommandTree::Command(ban("ban"))),
+    ]);
+
+    // opt-in commands are never in DMs, and neither are the server ones, permissions only apply on servers
+    harness.run(super::run, json!({})).await.unwrap();
+    assert_eq!(listed(&harness), ["`/ban`", "`/command`"]);
+
+    harness.in_guild(Permissions::empty(), &[]);
+    harness.run(super::run, json!({})).await.unwrap();
+    assert_eq!(listed(&harness), ["`/command`", "`/server`"]);
+
+    let gid = harness.in_guild(Permissions::BAN_MEMBERS, &[]);
+    guild_commands::set(&harness.client.db, gid, "/opt-in", true)
+      .await
+      .unwrap();
+    harness.run(super::run, json!({})).await.unwrap();
+    assert_eq!(listed(&harness), ["`/ban`", "`/command`", "`/opt-in`", "`/server`"]);
+
+    // an allowlist hides it from everyone without one of its roles
+    let role = RoleId::new(1);
+    let gid = harness.in_guild(Permissions::empty(), &[]);
+    guild_command_roles::toggle(&harness.client.db, gid, "/server", role)
+      .await
+      .unwrap();
+    harness.run(super::run, json!({})).await.unwrap();
+    assert_eq!(listed(&harness), ["`/command`"]);
+
+    let err = harness
+      .run(super::run, json!({ "command": "/server" }))
+      .await
+      .unwrap_err();
+    assert_eq!(err.to_string(), r#"Message("unknown command: /server")"#);
+  }
+}
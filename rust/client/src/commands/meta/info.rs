@@ -5,15 +5,16 @@ use std::{env, iter, process, str};
 use fmt::num::Format as _;
 use procfs::{process::*, *};
 use serenity::all::*;
+use util::network::cache;
 
-use crate::client::{Context, Result};
+use crate::client::{Context, QueueStats, Reply, Result};
 use crate::db::{self, counters::Counter};
 
 #[macros::command(desc = "Show some technical info about me")]
 pub async fn run(ctx: &Context<'_>) -> Result<()> {
   let prev = KernelStats::current()?;
   let rtt = Instant::now();
-  ctx.event.defer(ctx).await?;
+  ctx.defer().await?;
   let rtt = rtt.elapsed().as_secs_f64();
   let curr = KernelStats::current()?;
 
@@ -36,13 +37,19 @@ pub async fn run(ctx: &Context<'_>) -> Result<()> {
     .field("System", system(&meminfo, &uptime)?, true)
     .field("Process", process(&stat, &statm, &uptime)?, true)
     .field("Discord", discord(servers, channels, users, rtt)?, true)
+    .field("Queue", queue(&ctx.client.queue.stats())?, false)
+    .field(
+      "Response Cache (hits)",
+      response_cache(&ctx.client.http.cache_stats())?,
+      false,
+    )
     .field("Runtime Info", runtime_info(&versions()?)?, false)
     .field("Build Info", build_info()?, false)
     .footer(footer(&counters)?);
 
   tracing::debug!("sending response…");
-  let edit = EditInteractionResponse::new().embed(embed);
-  ctx.event.edit_response(ctx, edit).await?;
+  let edit = Reply::new().embed(embed);
+  ctx.edit_reply(edit).await?;
 
   Ok(())
 }
@@ -103,6 +110,32 @@ fn discord(servers: usize, channels: usize, users: usize, rtt: f64) -> fmt::Resu
   Ok(acc)
 }
 
+fn queue(stats: &[QueueStats]) -> fmt::Result<String> {
+  let mut acc = String::new();
+  for (i, stats) in stats.iter().enumerate() {
+    let sep = if i == 0 { "" } else { " \u{00B7} " };
+    let (name, limit) = (stats.job.name(), stats.job.limit());
+    write!(acc, "{sep}{name}\u{00A0}`{}/{limit}`", stats.running)?;
+    if stats.waiting > 0 {
+      write!(acc, "\u{00A0}`+{}`", stats.waiting)?;
+    }
+  }
+  Ok(acc)
+}
+
+fn response_cache(stats: &[(&str, cache::Stats)]) -> fmt::Result<String> {
+  let mut acc = String::new();
+  for (i, (source, stats)) in stats.iter().enumerate() {
+    let sep = if i == 0 { "" } else { " \u{00B7} " };
+    let requests = stats.hits + stats.misses;
+    write!(acc, "{sep}{source}\u{00A0}`{}/{}`", stats.hits.k(), requests.k())?;
+  }
+  if acc.is_empty() {
+    acc.push_str("nothing requested yet");
+  }
+  Ok(acc)
+}
+
 fn runtime_info(versions: &[(String, String)]) -> fmt::Result<String> {
   let mut acc = String::new();
   for (i, (k, v)) in versions.iter().enumerate() {
@@ -4,11 +4,11 @@ use std::{fs, io};
 use serenity::all::*;
 use util::task;
 
-use crate::client::{err, Context, Result};
+use crate::client::{err, Context, Reply, Result};
 
-#[macros::command(desc = "Run a shell command (owner only)", owner_only)]
+#[macros::command(desc = "Run a shell command (owner only)", owner_only, register = "dev")]
 pub async fn run(ctx: &Context<'_>, command: &str) -> Result<()> {
-  ctx.event.defer(ctx).await?;
+  ctx.defer().await?;
 
   let tempdir = tempfile::tempdir()?;
 
@@ -22,7 +22,7 @@ pub async fn run(ctx: &Context<'_>, command: &str) -> Result<()> {
     .map(|e| Ok(e?.path()))
     .collect::<io::Result<Vec<_>>>()?;
 
-  let mut edit = EditInteractionResponse::new();
+  let mut edit = Reply::new();
 
   for path in files {
     let file = CreateAttachment::path(path).await?;
@@ -39,7 +39,7 @@ pub async fn run(ctx: &Context<'_>, command: &str) -> Result<()> {
   }
 
   tracing::debug!("sending response…");
-  if ctx.event.edit_response(ctx, edit).await.is_err() {
+  if ctx.edit_reply(edit).await.is_err() {
     err::message!("failed to send response, most likely files are too big");
   }
 
@@ -1,13 +1,12 @@
 use std::process::Command;
 
-use serenity::all::*;
 use util::task;
 
-use crate::client::{Context, Result};
+use crate::client::{Context, Reply, Result};
 
-#[macros::command(desc = "Measure my connection speed (owner only)", owner_only)]
+#[macros::command(desc = "Measure my connection speed (owner only)", owner_only, register = "dev")]
 pub async fn run(ctx: &Context<'_>) -> Result<()> {
-  ctx.event.defer(ctx).await?;
+  ctx.defer().await?;
 
   tracing::debug!("measuring…");
   let measure = task::spawn_blocking(|| {
@@ -20,8 +19,8 @@ pub async fn run(ctx: &Context<'_>) -> Result<()> {
   let url = format!("{}.png", json.result.url);
 
   tracing::debug!("sending response…");
-  let edit = EditInteractionResponse::new().content(url);
-  ctx.event.edit_response(ctx, edit).await?;
+  let edit = Reply::new().content(url);
+  ctx.edit_reply(edit).await?;
 
   Ok(())
 }
@@ -1,16 +1,20 @@
 use std::fmt::Write;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use fmt::num::Format as _;
 use futures::StreamExt;
 use random::{Random, XorShift64};
 use serenity::all::*;
 
-use crate::client::{Context, Result};
+use crate::client::{Context, Reply, Result};
 
-#[macros::command(desc = "Measure my connection speed to Discord servers")]
+#[macros::command(
+  desc = "Measure my connection speed to Discord servers",
+  rate = "2/10min",
+  scope = "global"
+)]
 pub async fn run(ctx: &Context<'_>) -> Result<()> {
-  ctx.event.defer(ctx).await?;
+  ctx.defer().await?;
 
   let n_bytes = ctx.filesize_limit().await? - 512;
 
@@ -21,13 +25,15 @@ pub async fn run(ctx: &Context<'_>) -> Result<()> {
   tracing::debug!("uploading…");
   let upload = Instant::now();
   let att = CreateAttachment::bytes(buffer, "nudes.rar");
-  let edit = EditInteractionResponse::new().new_attachment(att);
-  let msg = ctx.event.edit_response(ctx, edit).await?;
+  let edit = Reply::new().new_attachment(att);
+  let msg = ctx.edit_reply(edit).await?;
   let upload = upload.elapsed().as_secs_f64();
 
   tracing::debug!("downloading…");
   let download = Instant::now();
-  let resp = reqwest::get(&msg.attachments[0].url).await?.error_for_status()?;
+  let get = ctx.client.http.get(&msg.attachments[0].url);
+  let get = get.limit(n_bytes).timeout(Duration::from_secs(300));
+  let resp = get.send().await?.error_for_status()?;
   let mut stream = resp.bytes_stream();
   while let Some(bytes) = stream.next().await {
     bytes?;
@@ -35,10 +41,10 @@ pub async fn run(ctx: &Context<'_>) -> Result<()> {
   let download = download.elapsed().as_secs_f64();
 
   tracing::debug!("sending response…");
-  let edit = EditInteractionResponse::new()
+  let edit = Reply::new()
     .clear_attachments()
     .content(content(n_bytes, upload, download)?);
-  ctx.event.edit_response(ctx, edit).await?;
+  ctx.edit_reply(edit).await?;
 
   Ok(())
 }
@@ -0,0 +1,29 @@
+use crate::client::{Context, Reply, Result};
+use crate::db;
+
+// permissions only apply on servers, anyone can do that in DMs
+#[macros::command(
+  desc = "Offer to repost or download supported links posted in this channel",
+  permissions = "manage_channels"
+)]
+pub async fn run(
+  ctx: &Context<'_>,
+  #[desc = "Whether links should be unfurled here, omit to see the current state"] enabled: Option<bool>,
+) -> Result<()> {
+  let channel = ctx.event.channel_id;
+
+  if let Some(enabled) = enabled {
+    tracing::debug!(%channel, enabled, "updating channel config…");
+    db::unfurl_channels::set(&ctx.client.db, channel, enabled).await?;
+  }
+
+  let text = match db::unfurl_channels::get(&ctx.client.db, channel).await? {
+    true => "\u{2705} links posted in this channel will be unfurled",
+    false => "\u{274c} links posted in this channel won't be unfurled",
+  };
+
+  tracing::debug!("sending response…");
+  ctx.reply(Reply::new().content(text).ephemeral(true)).await?;
+
+  Ok(())
+}
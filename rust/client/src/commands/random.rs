@@ -5,21 +5,72 @@ use rand::prelude::*;
 use random::{Random, XorShift64};
 use serenity::all::*;
 
-use crate::client::{Context, Result};
+use crate::client::{Context, Reply, Result};
+
+#[derive(macros::Choice)]
+enum Base {
+  #[name = "binary"]
+  #[value = 2]
+  Binary,
+  #[name = "octal"]
+  #[value = 8]
+  Octal,
+  #[name = "decimal"]
+  #[value = 10]
+  #[default]
+  Decimal,
+  #[name = "hexadecimal"]
+  #[value = 16]
+  Hexadecimal,
+}
 
 #[macros::command(desc = "Random integer in [min, max] range, defaults to [1, 100]")]
-pub async fn int(ctx: &Context<'_>, min: Option<i64>, max: Option<i64>) -> Result<()> {
+pub async fn int(
+  ctx: &Context<'_>,
+  min: Option<i64>,
+  max: Option<i64>,
+  #[desc = "What to write it in, defaults to decimal"] base: Base,
+) -> Result<()> {
   let (min, max) = (min.unwrap_or(1), max.unwrap_or(100));
   let n = thread_rng().gen_range(min..=max);
-  let text = format!("{}", n);
+  let (sign, abs) = (if n < 0 { "-" } else { "" }, n.unsigned_abs());
+  let text = match base {
+    Base::Binary => format!("{sign}0b{abs:b}"),
+    Base::Octal => format!("{sign}0o{abs:o}"),
+    Base::Decimal => format!("{}", n),
+    Base::Hexadecimal => format!("{sign}0x{abs:x}"),
+  };
   reply(ctx, |msg| msg.content(text)).await
 }
 
+#[derive(macros::Choice)]
+enum Step {
+  #[name = "0.01"]
+  #[value = 0.01]
+  Hundredth,
+  #[name = "0.1"]
+  #[value = 0.1]
+  Tenth,
+  #[name = "0.5"]
+  #[value = 0.5]
+  Half,
+}
+
 #[macros::command(desc = "Random real number in [min, max) range, defaults to [0, 1)")]
-pub async fn real(ctx: &Context<'_>, min: Option<f64>, max: Option<f64>) -> Result<()> {
+pub async fn real(
+  ctx: &Context<'_>,
+  min: Option<f64>,
+  max: Option<f64>,
+  #[desc = "Round down to a multiple of this"] step: Option<Step>,
+) -> Result<()> {
   let (min, max) = (min.unwrap_or(0.0), max.unwrap_or(1.0));
   let n = thread_rng().gen_range(min..max);
-  let text = format!("{}", n);
+  let text = match step {
+    Some(Step::Hundredth) => format!("{:.2}", (n * 100.0).floor() / 100.0),
+    Some(Step::Tenth) => format!("{:.1}", (n * 10.0).floor() / 10.0),
+    Some(Step::Half) => format!("{:.1}", (n * 2.0).floor() / 2.0),
+    None => format!("{}", n),
+  };
   reply(ctx, |msg| msg.content(text)).await
 }
 
@@ -174,11 +225,70 @@ pub async fn eightball(ctx: &Context<'_>, #[desc = "A yes–no question"] questi
 
 async fn reply<F>(ctx: &Context<'_>, f: F) -> Result<()>
 where
-  F: FnOnce(CreateInteractionResponseMessage) -> CreateInteractionResponseMessage,
+  F: FnOnce(Reply) -> Reply,
 {
   tracing::debug!("sending response…");
-  let msg = CreateInteractionResponseMessage::new();
-  let msg = CreateInteractionResponse::Message(f(msg));
-  ctx.event.create_response(ctx, msg).await?;
+  ctx.reply(f(Reply::new())).await?;
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::client::testing::Harness;
+
+  #[tokio::test]
+  async fn die() {
+    let mut harness = Harness::new().await;
+    harness.run(super::die, json!({ "n": 3 })).await.unwrap();
+
+    let replies = harness.replies();
+    assert_eq!(replies.len(), 1);
+    let content = replies[0]["content"].as_str().unwrap();
+    let (head, body) = content.split_once('\n').unwrap();
+    assert!(head.ends_with("(3 dice)"), "{head}");
+    assert_eq!(body.chars().count(), 3);
+  }
+
+  #[tokio::test]
+  async fn int() {
+    let mut harness = Harness::new().await;
+    let options = json!({ "min": -5, "max": -5, "base": 2 });
+    harness.run(super::int, options).await.unwrap();
+    harness
+      .run(super::int, json!({ "min": 255, "max": 255 }))
+      .await
+      .unwrap();
+
+    let replies = harness.replies();
+    assert_eq!(replies[0]["content"], "-0b101");
+    assert_eq!(replies[1]["content"], "255");
+  }
+
+  #[tokio::test]
+  async fn real() {
+    let mut harness = Harness::new().await;
+    let options = json!({ "min": 1.0, "max": 2.0, "step": 0.5 });
+    harness.run(super::real, options).await.unwrap();
+
+    let replies = harness.replies();
+    assert!(
+      matches!(replies[0]["content"].as_str(), Some("1.0" | "1.5")),
+      "{replies:?}"
+    );
+  }
+
+  #[tokio::test]
+  async fn eightball() {
+    let mut harness = Harness::new().await;
+    harness
+      .run(super::eightball, json!({ "question": "is it?" }))
+      .await
+      .unwrap();
+
+    let replies = harness.replies();
+    let content = replies[0]["content"].as_str().unwrap();
+    assert!(content.starts_with("❔ is it?\n🎱 "), "{content}");
+  }
+}
@@ -0,0 +1,20 @@
+use api::_2ch::parse_url as parse_2ch_url;
+use api::_4chan::parse_url as parse_4chan_url;
+use discord::link;
+use serenity::all::*;
+
+use crate::client::{err, Context, Result};
+use crate::commands::{_2ch, _4chan};
+
+#[macros::command(context_menu)]
+pub async fn menu(ctx: &Context<'_>, message: &Message) -> Result<()> {
+  let mut urls = link::urls(&message.content);
+  let Some(url) = urls.find(|url| parse_4chan_url(url).is_some() || parse_2ch_url(url).is_some()) else {
+    err::message!("there are no 4chan or 2ch links in this message");
+  };
+
+  match parse_4chan_url(url) {
+    Some(_) => _4chan::repost_url(ctx, url).await,
+    None => _2ch::repost_url(ctx, url).await,
+  }
+}
@@ -1,9 +1,8 @@
 use std::borrow::Cow;
 
-use serenity::all::*;
 use util::text::style;
 
-use crate::client::{Context, Result};
+use crate::client::{Context, Reply, Result};
 
 #[derive(macros::Choice)]
 enum Style {
@@ -67,9 +66,7 @@ pub async fn run(ctx: &Context<'_>, style: Style, input: &str) -> Result<()> {
   let output = input.chars().map(f).collect::<String>();
 
   tracing::debug!("sending response…");
-  let msg = CreateInteractionResponseMessage::new().content(output);
-  let msg = CreateInteractionResponse::Message(msg);
-  ctx.event.create_response(ctx, msg).await?;
+  ctx.reply(Reply::new().content(output)).await?;
 
   Ok(())
 }
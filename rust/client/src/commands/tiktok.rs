@@ -4,30 +4,63 @@ use ::cache::Name;
 use discord::link;
 use fmt::num::Format as _;
 use futures::StreamExt;
-use serde::Deserialize;
+use gcra::Rate;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use serenity::all::*;
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
+use util::network::{self, Http};
 
-use crate::client::{err, Context, Result};
+use crate::client::{err, Component, ComponentId, Context, Job, Ratelimit, Reply, Result, Scope};
 
-#[macros::command(desc = "Download a video from TikTok")]
+// way more than any tiktok, it's only there so that a bad link from tikwm can't fill up the disk
+const DOWNLOAD_LIMIT: u64 = 1 << 30;
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(600);
+
+const RATELIMITS: &[Ratelimit] = &[
+  Ratelimit::new(Scope::User, Rate::new(10.0, gcra::minutes(10))),
+  Ratelimit::new(Scope::Guild, Rate::new(30.0, gcra::minutes(10))),
+];
+
+#[macros::command(desc = "Download a video from TikTok", ratelimits = RATELIMITS)]
 pub async fn run(ctx: &Context<'_>, url: &str) -> Result<()> {
-  ctx.event.defer(ctx).await?;
+  ctx.defer().await?;
 
   tracing::debug!("fetching json…");
-  let json = Json::get(url).await?;
-  let Some(data) = json.data else {
-    err::message!("something went wrong");
-  };
+  let data = Json::get(&ctx.client.http, url).await?.data()?;
 
   tracing::debug!("selecting quality…");
-  let file_url = select_quality(ctx, &data).await?;
+  select_quality(ctx, url, &data).await
+}
+
+pub async fn on_quality(ctx: &Context<'_>, component: &Component) -> Result<()> {
+  ctx.defer().await?;
+
+  let Video { url } = component.state()?;
+  tracing::debug!(quality = %component.key, "selected");
+
+  // the links expire after a while, so they have to be fetched again
+  tracing::debug!("fetching json…");
+  let data = Json::get(&ctx.client.http, &url).await?.data()?;
+  let file_url = match &*component.key {
+    "play" => &data.play,
+    "hdplay" => &data.hdplay,
+    // "wmplay" => &data.wmplay,
+    _ => unreachable!(),
+  };
+
+  let _permit = ctx.client.queue.acquire(ctx, Job::TikTok).await?;
 
   tracing::debug!("downloading…");
   ctx.progress("downloading…").await?;
-  let Ok(resp) = reqwest::get(file_url).await?.error_for_status() else {
+  let get = ctx
+    .client
+    .http
+    .get(file_url)
+    .limit(DOWNLOAD_LIMIT)
+    .timeout(DOWNLOAD_TIMEOUT);
+  let Ok(resp) = get.send().await?.error_for_status() else {
     err::message!("failed to download");
   };
 
@@ -56,22 +89,22 @@ pub async fn run(ctx: &Context<'_>, url: &str) -> Result<()> {
     let url = link::Url(url.as_str());
 
     let content = format!("{} \u{205D} [mp4]({}) {}B", content, url, fsize.iec());
-    let edit = EditInteractionResponse::new()
+    let edit = Reply::new()
       .components(Default::default()) // remove components
       .content(content);
 
     tracing::debug!("sending response…");
-    ctx.event.edit_response(ctx, edit).await?;
+    ctx.edit_reply(edit).await?;
   } else {
     let file = CreateAttachment::path(&fpath).await?;
-    let edit = EditInteractionResponse::new()
+    let edit = Reply::new()
       .components(Default::default()) // remove components
       .content(content)
       .new_attachment(file);
 
     tracing::debug!("uploading…");
     ctx.progress("uploading…").await?;
-    if ctx.event.edit_response(ctx, edit).await.is_err() {
+    if ctx.edit_reply(edit).await.is_err() {
       err::message!("failed to upload, most likely the file is too big");
     }
   }
@@ -79,7 +112,9 @@ pub async fn run(ctx: &Context<'_>, url: &str) -> Result<()> {
   Ok(())
 }
 
-async fn select_quality<'a>(ctx: &Context<'_>, data: &'a Data) -> Result<&'a str> {
+async fn select_quality(ctx: &Context<'_>, url: &str, data: &Data) -> Result<()> {
+  let id = ComponentId::new(ctx, "tiktok/quality", &Video { url: url.to_owned() }).await?;
+
   let buttons = CreateActionRow::Buttons({
     let play = format!("{}B ", data.size.iec());
     let hdplay = format!("{}B source quality", data.hd_size.iec());
@@ -93,51 +128,42 @@ async fn select_quality<'a>(ctx: &Context<'_>, data: &'a Data) -> Result<&'a str
 
     values
       .into_iter()
-      .map(|(id, label, style)| CreateButton::new(id).label(label).style(style))
+      .map(|(key, label, style)| CreateButton::new(id.custom_id(key)).label(label).style(style))
       .collect()
   });
 
   let components = vec![buttons];
-  let edit = EditInteractionResponse::new().components(components);
-  let msg = ctx.event.edit_response(ctx, edit).await?;
-
-  tracing::debug!("waiting for user interaction…");
-  let mut collector = msg
-    .await_component_interaction(ctx)
-    .author_id(ctx.event.user.id)
-    .timeout(Duration::from_secs(60))
-    .stream();
-
-  let Some(mci) = collector.next().await else {
-    err::timeout!();
-  };
-
-  mci.defer(ctx).await?;
+  let edit = Reply::new().components(components);
+  ctx.edit_reply(edit).await?;
 
-  let url = match &*mci.data.custom_id {
-    "play" => &data.play,
-    "hdplay" => &data.hdplay,
-    // "wmplay" => &data.wmplay,
-    _ => unreachable!(),
-  };
-
-  Ok(url)
+  Ok(())
 }
 
 // ---
 
 impl Json {
-  async fn get(url: &str) -> reqwest::Result<Self> {
+  async fn get(http: &Http, url: &str) -> network::Result<Self> {
     let form = json!({ "url": url, "hd": 1 });
-    let client = reqwest::Client::builder().build()?;
-    let post = client.post("https://tikwm.com/api/").form(&form);
+    let post = http.post("https://tikwm.com/api/").form(&form);
     let resp = post.send().await?.error_for_status()?;
     resp.json().await
   }
+
+  fn data(self) -> Result<Data> {
+    let Some(data) = self.data else {
+      err::message!("something went wrong");
+    };
+    Ok(data)
+  }
 }
 
 // ---
 
+#[derive(Serialize, Deserialize)]
+struct Video {
+  url: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct Json {
   data: Option<Data>,
@@ -205,3 +231,32 @@ struct Author {
 //     }
 //   }
 // }
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::client::testing::Harness;
+
+  const URL: &str = "https://www.tiktok.com/@ferris/video/7301234567890123456";
+
+  #[tokio::test]
+  async fn run() {
+    let mut harness = Harness::new().await;
+    harness.stub.serve("tikwm.com/api/", "tikwm/video.json");
+    harness.run(super::run, json!({ "url": URL })).await.unwrap();
+
+    let replies = harness.replies();
+    let buttons = replies[0]["components"][0]["components"].as_array().unwrap();
+    let labels = buttons.iter().map(|b| b["label"].as_str().unwrap()).collect::<Vec<_>>();
+    assert_eq!(labels, ["1.00MiB ", "3.00MiB source quality"]);
+  }
+
+  #[tokio::test]
+  async fn error() {
+    let mut harness = Harness::new().await;
+    harness.stub.serve("tikwm.com/api/", "tikwm/error.json");
+    let err = harness.run(super::run, json!({ "url": URL })).await.unwrap_err();
+    assert_eq!(err.to_string(), r#"Message("something went wrong")"#);
+  }
+}
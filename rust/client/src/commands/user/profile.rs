@@ -1,9 +1,18 @@
 use serenity::all::*;
 
-use crate::client::{err, Context, Result};
+use crate::client::{err, Context, Reply, Result};
 
 #[macros::command(desc = "Get someone's avatar")]
 pub async fn avatar(ctx: &Context<'_>, user: &User) -> Result<()> {
+  send_avatar(ctx, user).await
+}
+
+#[macros::command(context_menu)]
+pub async fn avatar_menu(ctx: &Context<'_>, user: &User) -> Result<()> {
+  send_avatar(ctx, user).await
+}
+
+async fn send_avatar(ctx: &Context<'_>, user: &User) -> Result<()> {
   let Some(url) = user.avatar_url() else {
     err::message!("the user doesn't have a custom avatar");
   };
@@ -23,9 +32,7 @@ pub async fn banner(ctx: &Context<'_>, user: &User) -> Result<()> {
 
 async fn reply(ctx: &Context<'_>, content: String) -> Result<()> {
   tracing::debug!("sending response…");
-  let msg = CreateInteractionResponseMessage::new().content(content);
-  let msg = CreateInteractionResponse::Message(msg);
-  ctx.event.create_response(ctx, msg).await?;
+  ctx.reply(Reply::new().content(content)).await?;
 
   Ok(())
 }
@@ -1,20 +1,29 @@
+use chrono::{FixedOffset, Utc};
 use serenity::all::*;
 use util::task;
 
-use crate::client::{err, Context, Result};
+use crate::client::{Context, Reply, Result};
 use crate::db::statuses;
 
 #[macros::command(desc = "Show one month of someone's status history")]
 pub async fn run(
   ctx: &Context<'_>,
   #[desc = "The user of interest"] user: &User,
-  #[desc = "The time zone (UTC offset in hours or in ±HHMM format, e.g.: -7, +3, +0530, +1245)"] tz: i64,
+  #[desc = "The time zone (UTC offset or name, e.g.: -7, +3, +0530, +12:45, Europe/London)"] tz: FixedOffset,
 ) -> Result<()> {
-  let Some(now) = time::tz_offset(tz as i32).and_then(time::now) else {
-    err::message!("invalid UTC offset");
-  };
+  history(ctx, user, tz).await
+}
+
+// there are no options in context menus, so it's always in UTC
+#[macros::command(context_menu)]
+pub async fn menu(ctx: &Context<'_>, user: &User) -> Result<()> {
+  history(ctx, user, FixedOffset::east_opt(0).unwrap()).await
+}
 
-  ctx.event.defer(ctx).await?;
+async fn history(ctx: &Context<'_>, user: &User, tz: FixedOffset) -> Result<()> {
+  let now = Utc::now().with_timezone(&tz);
+
+  ctx.defer().await?;
 
   tracing::debug!("querying database…");
   let statuses = statuses::query(&ctx.client.db, user.id, "-30 days").await?;
@@ -28,10 +37,10 @@ pub async fn run(
   .await??;
 
   let file = CreateAttachment::bytes(png, "status history.png");
-  let edit = EditInteractionResponse::new().new_attachment(file);
+  let edit = Reply::new().new_attachment(file);
 
   tracing::debug!("sending response…");
-  ctx.event.edit_response(ctx, edit).await?;
+  ctx.edit_reply(edit).await?;
 
   Ok(())
 }
@@ -41,28 +50,11 @@ pub async fn run(
 mod time {
   use chrono::{prelude::*, Days};
 
-  pub fn now(tz_offset: i32) -> Option<DateTime<FixedOffset>> {
-    let offset = FixedOffset::east_opt(tz_offset)?;
-    Some(Utc::now().with_timezone(&offset))
-  }
-
   pub fn next_day<Tz: TimeZone>(dt: DateTime<Tz>) -> DateTime<Tz> {
     dt.checked_add_days(Days::new(1))
       .and_then(|dt| dt.with_hour(0)?.with_minute(0)?.with_second(0)?.with_nanosecond(0))
       .unwrap()
   }
-
-  pub fn tz_offset(input: i32) -> Option<i32> {
-    let (h, m) = match (input / 100, input % 100) {
-      (0, h) => (h, 0),
-      (h, m) => (h, m),
-    };
-
-    match (h, m) {
-      (-23..=23, -59..=59) => Some(60 * 60 * h + 60 * m),
-      _ => None,
-    }
-  }
 }
 
 mod status_history {
@@ -2,21 +2,37 @@ use serenity::all::*;
 use util::task;
 use weather::{api::Api, render::render};
 
-use crate::client::{err, Context, Result};
+use crate::client::{err, tr, Context, Reply, Result};
 
-#[macros::command(desc = "Weather forecast")]
+#[macros::command(
+  desc = "Weather forecast",
+  requires = "openweathermap",
+  l10n(ru(name = "погода", desc = "Прогноз погоды"), ja(name = "天気", desc = "天気予報"))
+)]
 pub async fn run(
   ctx: &Context<'_>,
-  #[desc = "The city name, with an optional country code (e.g.: London, GB; Москва; 東京)"] location: &str,
+  #[desc = "The city name, with an optional country code (e.g.: London, GB; Москва; 東京)"]
+  #[l10n(
+    ru(
+      name = "место",
+      desc = "Название города, можно с кодом страны (например: Москва; London, GB; 東京)"
+    ),
+    ja(name = "場所", desc = "都市名、国コードも指定可能（例：東京; London, GB; Москва）")
+  )]
+  #[autocomplete = locations]
+  location: &str,
 ) -> Result<()> {
-  ctx.event.defer(ctx).await?;
+  ctx.defer().await?;
 
-  let api = Api::new(&ctx.client.env.openweathermap_api_key);
+  let api = Api::new(
+    &ctx.client.http,
+    ctx.client.config.openweathermap_api_key.as_deref().unwrap_or_default(),
+  );
 
   tracing::debug!("api: geo…");
   let loc = match api.geo(location).await {
     Ok(mut list) if !list.is_empty() => list.swap_remove(0),
-    _ => err::message!("unknown location: {}", location),
+    _ => err::message!("{}", tr!(ctx, "unknown location: {}", location)),
   };
 
   tracing::debug!("api: onecall…");
@@ -31,10 +47,72 @@ pub async fn run(
   .await??;
 
   let file = CreateAttachment::bytes(png, "weather.png");
-  let edit = EditInteractionResponse::new().new_attachment(file);
+  let edit = Reply::new().new_attachment(file);
 
   tracing::debug!("sending response…");
-  ctx.event.edit_response(ctx, edit).await?;
+  ctx.edit_reply(edit).await?;
 
   Ok(())
 }
+
+async fn locations(ctx: &Context<'_>, location: &str) -> Result<Vec<AutocompleteChoice>> {
+  if location.trim().is_empty() {
+    return Ok(Vec::new());
+  }
+
+  let api = Api::new(
+    &ctx.client.http,
+    ctx.client.config.openweathermap_api_key.as_deref().unwrap_or_default(),
+  );
+  let choices = api.geo(location).await?.into_iter().map(|loc| {
+    let value = format!("{}, {}", loc.name, loc.country);
+    let name = match &loc.state {
+      Some(state) => format!("{}, {}, {}", loc.name, state, loc.country),
+      None => value.clone(),
+    };
+    AutocompleteChoice::new(fmt::ellipsis(&name, 100), fmt::ellipsis(&value, 100))
+  });
+
+  Ok(choices.collect())
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use crate::client::testing::{File, Harness};
+
+  #[tokio::test]
+  async fn run() {
+    let mut harness = Harness::new().await;
+    harness
+      .stub
+      .serve("api.openweathermap.org/geo/1.0/direct", "openweathermap/geo.json")
+      .serve("api.openweathermap.org/data/2.5/onecall", "openweathermap/onecall.json");
+    harness.run(super::run, json!({ "location": "tokyo" })).await.unwrap();
+
+    // the forecast is for the first place found
+    let onecall = &harness.stub.requests()[1];
+    assert_eq!(onecall.path(), "/data/2.5/onecall");
+    let query = onecall.query().unwrap_or_default();
+    assert!(query.contains("lat=35.6828387&lon=139.7594549"), "{query}");
+
+    let requests = harness.requests();
+    let [File { name, data }] = &requests.last().unwrap().files[..] else {
+      panic!("expected one file, got {:?}", requests.last().unwrap().files);
+    };
+    assert_eq!(name, "weather.png");
+    assert!(data.starts_with(b"\x89PNG"));
+  }
+
+  #[tokio::test]
+  async fn unknown_location() {
+    let mut harness = Harness::new().await;
+    harness.stub.fail("api.openweathermap.org/geo/1.0/direct", 404);
+    let err = harness
+      .run(super::run, json!({ "location": "asdf" }))
+      .await
+      .unwrap_err();
+    assert_eq!(err.to_string(), r#"Message("unknown location: asdf")"#);
+  }
+}
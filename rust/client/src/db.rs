@@ -8,9 +8,15 @@ pub type Pool = sqlx::Pool<Sqlite>;
 pub type QueryBuilder<'a> = sqlx::QueryBuilder<'a, Sqlite>;
 pub type QueryResult = SqliteQueryResult;
 
+pub mod components;
 pub mod counters;
+pub mod errors;
+pub mod guild_command_roles;
+pub mod guild_commands;
 pub mod ratelimits;
+pub mod responses;
 pub mod statuses;
+pub mod unfurl_channels;
 pub mod users;
 
 pub async fn init(url: &str) -> sqlx::Result<Pool> {
@@ -31,3 +37,17 @@ pub async fn init(url: &str) -> sqlx::Result<Pool> {
 
   Ok(pool)
 }
+
+// a fresh database that only lives as long as its pool, without the extensions
+#[cfg(test)]
+pub async fn memory() -> sqlx::Result<Pool> {
+  // every connection would get its own database otherwise
+  let pool = SqlitePoolOptions::new()
+    .max_connections(1)
+    .idle_timeout(None)
+    .max_lifetime(None)
+    .connect("sqlite::memory:")
+    .await?;
+  sqlx::migrate!("../../migrations").run(&pool).await?;
+  Ok(pool)
+}
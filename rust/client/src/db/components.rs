@@ -0,0 +1,44 @@
+use serenity::all::*;
+
+use super::*;
+
+#[derive(sqlx::FromRow)]
+pub struct Row {
+  pub user: i64,
+  pub command: String,
+  pub state: String,
+}
+
+pub async fn insert(pool: &Pool, uid: UserId, command: &str, state: &str) -> sqlx::Result<i64> {
+  let q = sqlx::query_scalar(
+    " insert into components (user, command, state) values (?, ?, ?)
+      returning id ",
+  );
+  let uid = uid.get() as i64;
+  q.bind(uid).bind(command).bind(state).fetch_one(pool).await
+}
+
+pub async fn get(pool: &Pool, id: i64) -> sqlx::Result<Option<Row>> {
+  let q = sqlx::query_as("select user, command, state from components where id = ?");
+  q.bind(id).fetch_optional(pool).await
+}
+
+// updates a single field atomically, so that concurrent interactions don't overwrite each other
+pub async fn set(pool: &Pool, id: i64, field: &str, value: &str) -> sqlx::Result<Option<String>> {
+  let q = sqlx::query_scalar(
+    " update components set state = json_set(state, '$.' || ?, json(?)) where id = ?
+      returning state ",
+  );
+  q.bind(field).bind(value).bind(id).fetch_optional(pool).await
+}
+
+// returns whether it was still there
+pub async fn delete(pool: &Pool, id: i64) -> sqlx::Result<bool> {
+  let q = sqlx::query("delete from components where id = ?");
+  Ok(q.bind(id).execute(pool).await?.rows_affected() > 0)
+}
+
+pub async fn prune(pool: &Pool, range: &str) -> sqlx::Result<QueryResult> {
+  let q = sqlx::query("delete from components where time < unixepoch('now', ?)");
+  q.bind(range).execute(pool).await
+}
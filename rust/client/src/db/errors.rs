@@ -0,0 +1,51 @@
+use serenity::all::*;
+
+use super::*;
+
+#[derive(Default, sqlx::FromRow)]
+pub struct Error {
+  pub display: Option<String>,
+  pub debug: Option<String>,
+  pub panic: Option<String>,
+  pub traceback: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct Row {
+  pub time: i64,
+  pub command: String,
+  pub user: i64,
+  pub guild: Option<i64>,
+  #[sqlx(flatten)]
+  pub error: Error,
+}
+
+pub async fn insert(
+  pool: &Pool,
+  id: u64,
+  command: &str,
+  uid: UserId,
+  gid: Option<GuildId>,
+  error: &Error,
+) -> sqlx::Result<QueryResult> {
+  let q = sqlx::query(
+    " insert or ignore into errors (id, command, user, guild, display, debug, panic, traceback)
+      values (?, ?, ?, ?, ?, ?, ?, ?) ",
+  );
+  let (id, uid, gid) = (id as i64, uid.get() as i64, gid.map(|id| id.get() as i64));
+  let q = q.bind(id).bind(command).bind(uid).bind(gid);
+  let q = q
+    .bind(&error.display)
+    .bind(&error.debug)
+    .bind(&error.panic)
+    .bind(&error.traceback);
+  q.execute(pool).await
+}
+
+pub async fn get(pool: &Pool, id: u64) -> sqlx::Result<Option<Row>> {
+  let q = sqlx::query_as(
+    " select time, command, user, guild, display, debug, panic, traceback
+      from errors where id = ? ",
+  );
+  q.bind(id as i64).fetch_optional(pool).await
+}
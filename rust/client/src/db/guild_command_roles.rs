@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+use serenity::all::*;
+
+use super::*;
+
+pub async fn get(pool: &Pool, gid: GuildId, command: &str) -> sqlx::Result<Vec<RoleId>> {
+  let q = sqlx::query_scalar("select role from guild_command_roles where guild = ? and command = ?");
+  let gid = gid.get() as i64;
+  let roles: Vec<i64> = q.bind(gid).bind(command).fetch_all(pool).await?;
+  Ok(roles.into_iter().map(|role| RoleId::new(role as u64)).collect())
+}
+
+// the allowlists of every command that has one
+pub async fn all(pool: &Pool, gid: GuildId) -> sqlx::Result<HashMap<String, Vec<RoleId>>> {
+  let q = sqlx::query_as("select command, role from guild_command_roles where guild = ?");
+  let rows: Vec<(String, i64)> = q.bind(gid.get() as i64).fetch_all(pool).await?;
+  let mut acc = HashMap::<_, Vec<_>>::new();
+  for (command, role) in rows {
+    acc.entry(command).or_default().push(RoleId::new(role as u64));
+  }
+  Ok(acc)
+}
+
+// adds the role if it isn't there yet, removes it otherwise, returns whether it's there now
+pub async fn toggle(pool: &Pool, gid: GuildId, command: &str, rid: RoleId) -> sqlx::Result<bool> {
+  let (gid, rid) = (gid.get() as i64, rid.get() as i64);
+  let q = sqlx::query("delete from guild_command_roles where guild = ? and command = ? and role = ?");
+  let deleted = q.bind(gid).bind(command).bind(rid).execute(pool).await?;
+  if deleted.rows_affected() > 0 {
+    return Ok(false);
+  }
+
+  let q = sqlx::query("insert into guild_command_roles (guild, command, role) values (?, ?, ?)");
+  q.bind(gid).bind(command).bind(rid).execute(pool).await?;
+  Ok(true)
+}
@@ -0,0 +1,30 @@
+use serenity::all::*;
+
+use super::*;
+
+#[derive(sqlx::FromRow)]
+pub struct Row {
+  pub command: String,
+  pub enabled: bool,
+}
+
+pub async fn all(pool: &Pool, gid: GuildId) -> sqlx::Result<Vec<Row>> {
+  let q = sqlx::query_as("select command, enabled from guild_commands where guild = ?");
+  let gid = gid.get() as i64;
+  q.bind(gid).fetch_all(pool).await
+}
+
+pub async fn get(pool: &Pool, gid: GuildId, command: &str) -> sqlx::Result<Option<bool>> {
+  let q = sqlx::query_scalar("select enabled from guild_commands where guild = ? and command = ?");
+  let gid = gid.get() as i64;
+  q.bind(gid).bind(command).fetch_optional(pool).await
+}
+
+pub async fn set(pool: &Pool, gid: GuildId, command: &str, enabled: bool) -> sqlx::Result<QueryResult> {
+  let q = sqlx::query(
+    " insert into guild_commands (guild, command, enabled) values (?, ?, ?)
+      on conflict do update set enabled = excluded.enabled ",
+  );
+  let gid = gid.get() as i64;
+  q.bind(gid).bind(command).bind(enabled).execute(pool).await
+}
@@ -0,0 +1,59 @@
+use std::time::{Duration, SystemTime};
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use util::network::cache::{self, Entry, StoreResult};
+
+use super::*;
+
+#[derive(sqlx::FromRow)]
+pub struct Row {
+  pub body: Vec<u8>,
+  pub expires: i64,
+}
+
+pub async fn get(pool: &Pool, key: &str) -> sqlx::Result<Option<Row>> {
+  let q = sqlx::query_as("select body, expires from responses where key = ? and expires > unixepoch()");
+  q.bind(key).fetch_optional(pool).await
+}
+
+pub async fn upsert(pool: &Pool, key: &str, source: &str, body: &[u8], expires: i64) -> sqlx::Result<QueryResult> {
+  let q = sqlx::query(
+    " insert into responses (key, source, body, expires) values (?, ?, ?, ?)
+      on conflict do update set source = excluded.source, body = excluded.body, expires = excluded.expires ",
+  );
+  q.bind(key).bind(source).bind(body).bind(expires).execute(pool).await
+}
+
+pub async fn prune(pool: &Pool) -> sqlx::Result<QueryResult> {
+  let q = sqlx::query("delete from responses where expires <= unixepoch()");
+  q.execute(pool).await
+}
+
+// ---
+
+// backs the in-memory response cache, so that it survives restarts
+#[derive(Debug)]
+pub struct Store(pub Pool);
+
+impl cache::Store for Store {
+  fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, StoreResult<Option<Entry>>> {
+    async move {
+      let row = get(&self.0, key).await?;
+      Ok(row.map(|row| Entry {
+        body: row.body.into(),
+        expires: SystemTime::UNIX_EPOCH + Duration::from_secs(row.expires as u64),
+      }))
+    }
+    .boxed()
+  }
+
+  fn put<'a>(&'a self, key: &'a str, source: &'a str, entry: &'a Entry) -> BoxFuture<'a, StoreResult<()>> {
+    async move {
+      let expires = entry.expires.duration_since(SystemTime::UNIX_EPOCH)?.as_secs() as i64;
+      upsert(&self.0, key, source, &entry.body, expires).await?;
+      Ok(())
+    }
+    .boxed()
+  }
+}
@@ -0,0 +1,19 @@
+use serenity::all::*;
+
+use super::*;
+
+pub async fn get(pool: &Pool, cid: ChannelId) -> sqlx::Result<bool> {
+  let q = sqlx::query_scalar("select enabled from unfurl_channels where channel = ?");
+  let cid = cid.get() as i64;
+  let enabled = q.bind(cid).fetch_optional(pool).await?;
+  Ok(enabled.unwrap_or(false))
+}
+
+pub async fn set(pool: &Pool, cid: ChannelId, enabled: bool) -> sqlx::Result<QueryResult> {
+  let q = sqlx::query(
+    " insert into unfurl_channels (channel, enabled) values (?, ?)
+      on conflict do update set enabled = excluded.enabled ",
+  );
+  let cid = cid.get() as i64;
+  q.bind(cid).bind(enabled).execute(pool).await
+}
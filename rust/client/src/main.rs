@@ -4,6 +4,8 @@
 // https://stackoverflow.com/a/57049687/8802501
 // extern crate self as riamu;
 
+use std::env;
+
 use tokio::runtime::Builder as Runtime;
 
 mod client;
@@ -12,7 +14,14 @@ mod db;
 mod tracing;
 
 fn main() -> client::Result<()> {
-  tracing::init()?;
+  let args = env::args().skip(1).collect::<Vec<_>>();
+  match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+    [] => {}
+    ["export", format] => return export(format),
+    _ => Err("usage: riamu [export markdown|json]")?,
+  }
+
+  let _guard = tracing::init()?;
 
   tracing::debug!("initializing python…");
   python::init()?;
@@ -28,3 +37,15 @@ fn main() -> client::Result<()> {
 
   res
 }
+
+// prints the commands that get registered, for the wiki, without starting anything
+fn export(format: &str) -> client::Result<()> {
+  let commands = commands::tree();
+  let text = match format {
+    "markdown" => client::to_markdown(&commands)?,
+    "json" => serde_json::to_string_pretty(&client::to_json(&commands))?,
+    _ => Err(format!("expected markdown or json, got {format:?}"))?,
+  };
+  println!("{text}");
+  Ok(())
+}
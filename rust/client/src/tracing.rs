@@ -1,19 +1,104 @@
-use std::io;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::{env, error};
 
 pub use tracing::*;
 
-use tracing_subscriber::util::TryInitError;
-use tracing_subscriber::EnvFilter;
-use tracing_subscriber::{fmt, prelude::*};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::{fmt, prelude::*, EnvFilter, Layer, Registry};
 
-pub fn init() -> Result<(), TryInitError> {
-  let stderr = fmt::layer()
-    .without_time()
-    .with_writer(io::stderr)
-    .with_filter(EnvFilter::from_default_env());
+// logging is set up before the config is loaded, so these only come from env vars
+const FORMAT: &str = "LOG_FORMAT";
+const DIR: &str = "LOG_DIR";
+const ROTATION: &str = "LOG_ROTATION";
+const MAX_FILES: &str = "LOG_MAX_FILES";
+
+const FILE_PREFIX: &str = "riamu";
+const FILE_SUFFIX: &str = "log";
+const DEFAULT_MAX_FILES: usize = 14;
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+// has to be kept around, whatever's still buffered for the log file is flushed when it's dropped
+#[must_use]
+pub struct Guard {
+  _worker: Option<WorkerGuard>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Format {
+  Text,
+  Json,
+}
+
+// stderr, and a file rotated in `LOG_DIR` if it's set, both filtered by `RUST_LOG`
+pub fn init() -> Result<Guard, Box<dyn error::Error + Send + Sync>> {
+  let format = var(FORMAT)?.unwrap_or(Format::Text);
+  let mut layers = vec![layer(format, io::stderr, io::stderr().is_terminal())];
+
+  let mut guard = None;
+  if let Some(dir) = var::<PathBuf>(DIR)? {
+    let rotation = match env::var(ROTATION).as_deref() {
+      Err(_) | Ok("daily") => Rotation::DAILY,
+      Ok("hourly") => Rotation::HOURLY,
+      Ok("never") => Rotation::NEVER,
+      Ok(value) => Err(format!("{ROTATION}: expected daily, hourly or never, got {value:?}"))?,
+    };
+    let appender = RollingFileAppender::builder()
+      .rotation(rotation)
+      .filename_prefix(FILE_PREFIX)
+      .filename_suffix(FILE_SUFFIX)
+      .max_log_files(var(MAX_FILES)?.unwrap_or(DEFAULT_MAX_FILES))
+      .build(dir)?;
+    let (writer, worker) = tracing_appender::non_blocking(appender);
+    layers.push(layer(format, writer, false));
+    guard = Some(worker);
+  }
 
   tracing_subscriber::registry()
     // .with(console_subscriber::ConsoleLayer::builder().spawn())
-    .with(stderr)
-    .try_init()
+    .with(layers)
+    .try_init()?;
+
+  Ok(Guard { _worker: guard })
+}
+
+// ---
+
+fn layer<W>(format: Format, writer: W, ansi: bool) -> BoxedLayer
+where
+  W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+  let layer = fmt::layer().with_writer(writer).with_ansi(ansi);
+  let layer = match format {
+    Format::Text => layer.boxed(),
+    // fields of the event at the top level, those of the spans it's in (e.g. the command) under `spans`
+    Format::Json => layer.json().flatten_event(true).with_span_list(true).boxed(),
+  };
+  layer.with_filter(EnvFilter::from_default_env()).boxed()
+}
+
+fn var<T: FromStr>(key: &str) -> Result<Option<T>, String> {
+  match env::var(key) {
+    Ok(value) => match value.parse() {
+      Ok(value) => Ok(Some(value)),
+      Err(_) => Err(format!("{key}: invalid value {value:?}")),
+    },
+    Err(_) => Ok(None),
+  }
+}
+
+impl FromStr for Format {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "text" => Ok(Self::Text),
+      "json" => Ok(Self::Json),
+      _ => Err(()),
+    }
+  }
 }
@@ -4,6 +4,7 @@ use std::sync::OnceLock;
 use regex::Regex;
 
 pub mod colors;
+pub mod limits;
 pub mod link;
 
 // TODO: should use this function in each place of the codebase
@@ -0,0 +1,11 @@
+// what discord rejects commands for when they're registered, see
+// https://discord.com/developers/docs/interactions/application-commands#application-command-object,
+// checked by `macros::command` and `macros::Choice` at compile time, and for the whole tree by `client::validate_commands`
+pub const NAME_LEN: usize = 32;
+pub const DESCRIPTION_LEN: usize = 100;
+pub const OPTIONS: usize = 25;
+pub const CHOICES: usize = 25;
+pub const CHOICE_LEN: usize = 100;
+pub const STRING_LEN: u16 = 6000;
+// names, descriptions and choices of a top-level command and everything under it, all together
+pub const TOTAL_LEN: usize = 4000;
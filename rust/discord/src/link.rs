@@ -52,6 +52,13 @@ impl<'a> Display for Url<'a> {
   }
 }
 
+// finds plain links in message text, ignoring the markdown around them
+pub fn urls(text: &str) -> impl Iterator<Item = &str> {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  let re = RE.get_or_init(|| Regex::new(r"(?i)https?://[^\s<>()\[\]]+").unwrap());
+  re.find_iter(text).map(|m| m.as_str())
+}
+
 fn link_fmt(f: &mut Formatter<'_>, name: &str, url: &str, parts: [&str; 3]) -> fmt::Result {
   let [p0, p1, p2] = parts;
   f.write_str(p0)?;
@@ -61,7 +61,7 @@ impl Info {
 // I = period / quota
 // L = period
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub struct Rate {
   pub quota: f64,  // some abstract units
   pub period: u64, // nanoseconds
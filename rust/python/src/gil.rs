@@ -0,0 +1,35 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use pyo3::Python;
+
+static ACQUIRED: AtomicU64 = AtomicU64::new(0);
+static WAITED_NS: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy)]
+pub struct Stats {
+  pub acquired: u64,
+  pub waited: Duration,
+}
+
+// `Python::with_gil` that keeps track of how long it took to get the gil,
+// downloads hold onto it for long stretches, so everything else in python ends up waiting
+pub fn with_gil<F, R>(f: F) -> R
+where
+  F: for<'py> FnOnce(Python<'py>) -> R,
+{
+  let start = Instant::now();
+  Python::with_gil(|py| {
+    let waited = start.elapsed().as_nanos() as u64;
+    ACQUIRED.fetch_add(1, Ordering::Relaxed);
+    WAITED_NS.fetch_add(waited, Ordering::Relaxed);
+    f(py)
+  })
+}
+
+pub fn stats() -> Stats {
+  Stats {
+    acquired: ACQUIRED.load(Ordering::Relaxed),
+    waited: Duration::from_nanos(WAITED_NS.load(Ordering::Relaxed)),
+  }
+}
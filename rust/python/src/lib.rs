@@ -1,10 +1,12 @@
 use pyo3::prelude::*;
 
 mod ext;
+pub mod gil;
 mod logs;
 
 #[path = "lib/lib.rs"]
 pub mod lib;
+pub mod progress;
 
 pub fn init() -> PyResult<()> {
   pyo3::prepare_freethreaded_python();
@@ -1,4 +1,5 @@
 use std::path::Path;
+use std::sync::Arc;
 
 use pyo3::{exceptions::*, prelude::*, types::*};
 use tokio::sync::oneshot;
@@ -6,6 +7,8 @@ use tokio::task::JoinHandle;
 use util::task;
 
 use super::download::*;
+use crate::gil;
+use crate::progress::Tracker;
 
 pub struct Downloader {
   /// STEP 1: get context with all available formats
@@ -14,6 +17,8 @@ pub struct Downloader {
   pub selected: oneshot::Sender<Vec<String>>,
   /// STEP 3: finish downloading
   pub finish: JoinHandle<PyResult<Info>>,
+  /// can be used to watch the progress or cancel at any step
+  pub tracker: Arc<Tracker>,
 }
 
 impl Downloader {
@@ -42,14 +47,17 @@ impl Downloader {
     let url = url.to_string();
     let out_dir = out_dir.as_ref().to_owned();
     let fs = FormatSelector { f: Some(Box::new(fs)) };
+    let tracker = Tracker::new();
 
-    let join = task::spawn_blocking(|| {
-      Python::with_gil(|py| {
+    let progress = tracker.clone();
+
+    let join = task::spawn_blocking(move || {
+      gil::with_gil(|py| {
         let dl = py.import("lib.dl")?;
         let fs = PyCell::new(py, fs)?;
 
         tracing::trace!("downloading…");
-        let res = dl.call_method1("download", (url, out_dir, fs));
+        let res = dl.call_method1("download", (url, out_dir, fs, progress.hook(py)?));
 
         // make sure format selector gets dropped
         // to prevent channel-related deadlocks
@@ -64,6 +72,7 @@ impl Downloader {
       context: context_out,
       selected: selected_in,
       finish: join,
+      tracker,
     }
   }
 }
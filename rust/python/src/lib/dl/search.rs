@@ -1,5 +1,7 @@
 use pyo3::prelude::*;
 
+use crate::gil;
+
 #[derive(FromPyObject)]
 #[pyo3(from_item_all)]
 pub struct Result {
@@ -9,7 +11,7 @@ pub struct Result {
 }
 
 pub fn search(query: &str) -> PyResult<Vec<Result>> {
-  Python::with_gil(|py| {
+  gil::with_gil(|py| {
     let dl = py.import("lib.dl")?;
     let info = dl.call_method1("ytsearch", (query,))?;
     info.get_item("entries")?.extract()
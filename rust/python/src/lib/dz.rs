@@ -1,7 +1,11 @@
 use std::path::Path;
+use std::sync::Arc;
 
 use pyo3::prelude::*;
 
+use crate::gil;
+use crate::progress::Tracker;
+
 #[derive(FromPyObject)]
 #[pyo3(from_item_all)]
 pub struct Track {
@@ -27,19 +31,27 @@ pub struct Album {
   pub title: String,
 }
 
+pub fn configure(arl: &str, spotify: Option<(&str, &str)>) -> PyResult<()> {
+  gil::with_gil(|py| {
+    let dz = py.import("lib.dz")?;
+    dz.call_method1("configure", (arl, spotify))?;
+    Ok(())
+  })
+}
+
 pub fn search(query: &str) -> PyResult<Vec<Track>> {
-  Python::with_gil(|py| {
+  gil::with_gil(|py| {
     let dz = py.import("lib.dz")?;
     let tracks = dz.call_method1("search", (query,))?;
     tracks.get_item("data")?.extract()
   })
 }
 
-pub fn download(url: &str, bitrate: &str, out_dir: &Path) -> PyResult<Track> {
-  Python::with_gil(|py| {
+pub fn download(url: &str, bitrate: &str, out_dir: &Path, tracker: &Arc<Tracker>) -> PyResult<Track> {
+  gil::with_gil(|py| {
     let dz = py.import("lib.dz")?;
     let dl_obj = dz.call_method1("generate_download_object", (url, bitrate))?;
-    dz.call_method1("download", (dl_obj, out_dir))?;
+    dz.call_method1("download", (dl_obj, out_dir, tracker.hook(py)?))?;
     dl_obj.getattr("single")?.get_item("trackAPI")?.extract()
   })
 }
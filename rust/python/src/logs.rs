@@ -1,10 +1,11 @@
+use std::env;
 use std::path::Path;
-use std::{env, iter};
 
-use itertools::Itertools;
 use log::Level;
 use pyo3::{prelude::*, types::*};
 
+use crate::gil;
+
 const REDIRECT: &str = r#"
 import logging
 
@@ -17,7 +18,7 @@ logging.debug('redirected logs: python -> rust')
 "#;
 
 pub fn redirect() -> PyResult<()> {
-  Python::with_gil(|py| {
+  gil::with_gil(|py| {
     let receive = PyCFunction::new_closure(py, None, None, |args, _kwargs| {
       let (record,) = args.extract::<(Record<'_>,)>()?;
       record.dispatch();
@@ -49,13 +50,17 @@ impl<'a> Record<'a> {
     };
 
     if let Some(reason) = ignore_reason {
-      let target = self.name;
-      let level = self.level();
-      return tracing::trace!(?level, %target, "ignored log entry ({})", reason);
+      let (target, level) = (self.target(), self.level());
+      return tracing::trace!(?level, %target, logger = self.name, "ignored log entry ({})", reason);
     }
 
-    // wanted to use `tracing` directly but there seems to be a blocker:
-    // https://github.com/tokio-rs/tracing/pull/2048
+    // the logger's name is kept as a field too, in a span since the `log` bridge only passes the message on,
+    // it's at the error level so that it's there for whatever the filter lets through
+    let _span = tracing::error_span!(target: "python", "python", logger = self.name).entered();
+
+    // `tracing` wants static targets (https://github.com/tokio-rs/tracing/pull/2048),
+    // `log` doesn't, and its records get turned into events with theirs,
+    // so every logger can be filtered on its own, e.g. `RUST_LOG=python::yt_dlp=warn`
     log::logger().log(
       &log::Record::builder()
         .args(format_args!("{}", self.message))
@@ -77,9 +82,9 @@ impl<'a> Record<'a> {
     }
   }
 
+  // e.g. `python::yt_dlp::extractor`
   fn target(&self) -> String {
-    let segments = self.name.split('.');
-    iter::once("python").chain(segments).join("::")
+    format!("python::{}", self.name.replace('.', "::"))
   }
 
   fn file(&self) -> Option<&str> {
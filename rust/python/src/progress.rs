@@ -0,0 +1,86 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use pyo3::{exceptions::*, prelude::*, types::*};
+use tokio::sync::watch;
+
+use crate::ext::DictExt;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Progress {
+  pub downloaded: Option<u64>,
+  pub total: Option<u64>,
+  /// bytes per second
+  pub speed: Option<f64>,
+  /// seconds
+  pub eta: Option<f64>,
+  /// some downloaders don't know anything besides that
+  pub fraction: Option<f64>,
+}
+
+impl Progress {
+  pub fn fraction(&self) -> Option<f64> {
+    let from_bytes = || match (self.downloaded, self.total) {
+      (Some(downloaded), Some(total)) if total > 0 => Some(downloaded as f64 / total as f64),
+      _ => None,
+    };
+    self.fraction.or_else(from_bytes).map(|f| f.clamp(0.0, 1.0))
+  }
+}
+
+impl<'a> FromPyObject<'a> for Progress {
+  fn extract(any: &'a PyAny) -> PyResult<Self> {
+    let dict: &PyDict = any.extract()?;
+    Ok(Self {
+      downloaded: dict.extract_optional("downloaded")?,
+      total: dict.extract_optional("total")?,
+      speed: dict.extract_optional("speed")?,
+      eta: dict.extract_optional("eta")?,
+      fraction: dict.extract_optional("fraction")?,
+    })
+  }
+}
+
+// ---
+
+/// Shared between a download running on the python side and whoever is watching it.
+pub struct Tracker {
+  progress: watch::Sender<Progress>,
+  cancelled: AtomicBool,
+}
+
+impl Tracker {
+  pub fn new() -> Arc<Self> {
+    Arc::new(Self {
+      progress: watch::Sender::new(Progress::default()),
+      cancelled: AtomicBool::new(false),
+    })
+  }
+
+  pub fn subscribe(&self) -> watch::Receiver<Progress> {
+    self.progress.subscribe()
+  }
+
+  /// The download stops the next time it reports its progress.
+  pub fn cancel(&self) {
+    self.cancelled.store(true, Ordering::Relaxed);
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.cancelled.load(Ordering::Relaxed)
+  }
+
+  /// A python callable to be passed as a progress hook, it raises once the download is cancelled.
+  pub(crate) fn hook(self: &Arc<Self>, py: Python<'_>) -> PyResult<Py<PyCFunction>> {
+    let tracker = self.clone();
+    let hook = PyCFunction::new_closure(py, None, None, move |args, _kwargs| {
+      if tracker.is_cancelled() {
+        return Err(PyException::new_err("cancelled"));
+      }
+      let (progress,) = args.extract::<(Progress,)>()?;
+      tracker.progress.send_replace(progress);
+      PyResult::Ok(())
+    })?;
+    Ok(hook.into())
+  }
+}
@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{header, Body, Request, Response, StatusCode};
+use url::Url;
+use util::network::Http;
+
+// trimmed down responses of the real services, laid out by host, see `fixtures/README.md`
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../fixtures");
+
+// a local server that answers with fixtures instead of third party services,
+// requests get to it through `Http::proxied`, so their paths start with the original host
+#[derive(Debug, Clone)]
+pub struct Stub {
+  addr: SocketAddr,
+  state: Arc<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+  routes: Mutex<HashMap<String, Route>>,
+  requests: Mutex<Vec<Url>>,
+}
+
+#[derive(Debug, Clone)]
+struct Route {
+  status: StatusCode,
+  fixture: Option<PathBuf>,
+}
+
+impl Stub {
+  // has to be called from within a tokio runtime
+  pub fn start() -> Self {
+    let state = Arc::<State>::default();
+
+    let make_service = make_service_fn({
+      let state = state.clone();
+      move |_| {
+        let state = state.clone();
+        let service = service_fn(move |req| {
+          let state = state.clone();
+          async move { Ok::<_, Infallible>(respond(&state, req)) }
+        });
+        async move { Ok::<_, Infallible>(service) }
+      }
+    });
+
+    let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+    let addr = server.local_addr();
+    tokio::spawn(server);
+
+    Self { addr, state }
+  }
+
+  pub fn url(&self) -> Url {
+    Url::parse(&format!("http://{}", self.addr)).unwrap()
+  }
+
+  // retries don't wait, a failing route just gets requested a few more times
+  pub fn http(&self) -> Http {
+    Http::new().proxy(self.url()).backoff(Duration::ZERO)
+  }
+
+  // `path` is the original url without the scheme and the query, e.g. `a.4cdn.org/g/catalog.json`,
+  // and `fixture` is relative to the fixtures directory
+  pub fn serve(&self, path: &str, fixture: &str) -> &Self {
+    let fixture = PathBuf::from(FIXTURES).join(fixture);
+    assert!(fixture.is_file(), "no such fixture: {}", fixture.display());
+    self.route(path, StatusCode::OK, Some(fixture))
+  }
+
+  pub fn fail(&self, path: &str, status: u16) -> &Self {
+    self.route(path, StatusCode::from_u16(status).unwrap(), None)
+  }
+
+  // everything that's been requested so far, as it would've been requested from the real services
+  pub fn requests(&self) -> Vec<Url> {
+    self.state.requests.lock().unwrap().clone()
+  }
+
+  fn route(&self, path: &str, status: StatusCode, fixture: Option<PathBuf>) -> &Self {
+    let route = Route { status, fixture };
+    self.state.routes.lock().unwrap().insert(path.to_owned(), route);
+    self
+  }
+}
+
+pub fn fixture(name: &str) -> String {
+  let path = PathBuf::from(FIXTURES).join(name);
+  fs::read_to_string(&path).unwrap_or_else(|err| panic!("{}: {}", path.display(), err))
+}
+
+// ---
+
+fn respond(state: &State, req: Request<Body>) -> Response<Body> {
+  let path = req.uri().path().trim_start_matches('/');
+  let query = req.uri().query().map(|q| format!("?{q}")).unwrap_or_default();
+  if let Ok(url) = Url::parse(&format!("https://{path}{query}")) {
+    state.requests.lock().unwrap().push(url);
+  }
+
+  let route = state.routes.lock().unwrap().get(path).cloned();
+  let Some(Route { status, fixture }) = route else {
+    tracing::warn!(path, "no fixture");
+    return response(
+      StatusCode::NOT_FOUND,
+      "text/plain",
+      format!("no fixture for {path}").into(),
+    );
+  };
+
+  let Some(fixture) = fixture else {
+    return response(status, "text/plain", Body::empty());
+  };
+
+  let content_type = match fixture.extension().and_then(|ext| ext.to_str()) {
+    Some("json") => "application/json",
+    Some("html") => "text/html; charset=utf-8",
+    _ => "application/octet-stream",
+  };
+  response(status, content_type, fs::read(&fixture).unwrap().into())
+}
+
+fn response(status: StatusCode, content_type: &str, body: Body) -> Response<Body> {
+  let response = Response::builder().status(status);
+  response.header(header::CONTENT_TYPE, content_type).body(body).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  use futures::future::{self, BoxFuture};
+  use futures::FutureExt;
+  use util::network::cache::{self, Entry, StoreResult};
+  use util::network::{self, Cache};
+
+  use super::*;
+
+  #[tokio::test]
+  async fn routes() {
+    let stub = Stub::start();
+    stub.serve("www.omdbapi.com/", "omdb/not_found.json");
+    stub.fail("a.4cdn.org/g/catalog.json", 403);
+    let http = stub.http();
+
+    let resp = http.get("https://www.omdbapi.com?t=a+b").send().await.unwrap();
+    assert_eq!(resp.headers()[header::CONTENT_TYPE], "application/json");
+    assert_eq!(resp.text().await.unwrap(), fixture("omdb/not_found.json"));
+
+    let resp = http.get("https://a.4cdn.org/g/catalog.json").send().await.unwrap();
+    assert_eq!(resp.status(), 403);
+    let resp = http.post("http://example.com/").send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+
+    let requests = stub.requests().into_iter().map(String::from).collect::<Vec<_>>();
+    assert_eq!(
+      requests,
+      [
+        "https://www.omdbapi.com/?t=a+b",
+        "https://a.4cdn.org/g/catalog.json",
+        "https://example.com/",
+      ]
+    );
+  }
+
+  #[tokio::test]
+  async fn retries() {
+    let stub = Stub::start();
+    stub.fail("a.4cdn.org/g/catalog.json", 503);
+    stub.fail("a.4cdn.org/g/thread/1.json", 404);
+    let http = stub.http().retries(2);
+
+    let resp = http.get("https://a.4cdn.org/g/catalog.json").send().await.unwrap();
+    assert_eq!(resp.status(), 503);
+    assert_eq!(stub.requests().len(), 3);
+
+    let resp = http.get("https://a.4cdn.org/g/thread/1.json").send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+    assert_eq!(stub.requests().len(), 4, "client errors aren't retried");
+
+    let resp = http.post("https://a.4cdn.org/g/catalog.json").send().await.unwrap();
+    assert_eq!(resp.status(), 503);
+    assert_eq!(stub.requests().len(), 5, "posts aren't retried");
+    http
+      .post("https://a.4cdn.org/g/catalog.json")
+      .retry()
+      .send()
+      .await
+      .unwrap();
+    assert_eq!(stub.requests().len(), 8, "unless asked to");
+  }
+
+  #[tokio::test]
+  async fn limit() {
+    let stub = Stub::start();
+    stub.serve("www.omdbapi.com/", "omdb/not_found.json");
+    let http = stub.http();
+
+    let len = fixture("omdb/not_found.json").len() as u64;
+    let resp = http.get("https://www.omdbapi.com").limit(len).send().await.unwrap();
+    assert!(resp.bytes().await.is_ok());
+
+    let resp = http.get("https://www.omdbapi.com").limit(len - 1).send().await.unwrap();
+    let err = resp.json::<serde_json::Value>().await.unwrap_err();
+    assert!(matches!(err, network::Error::TooLarge(_)), "{err:?}");
+  }
+
+  #[derive(Debug, Default)]
+  struct Store(Mutex<HashMap<String, Entry>>);
+
+  impl cache::Store for Store {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, StoreResult<Option<Entry>>> {
+      let entry = self.0.lock().unwrap().get(key).cloned();
+      future::ready(Ok(entry)).boxed()
+    }
+
+    fn put<'a>(&'a self, key: &'a str, _: &'a str, entry: &'a Entry) -> BoxFuture<'a, StoreResult<()>> {
+      self.0.lock().unwrap().insert(key.to_owned(), entry.clone());
+      future::ready(Ok(())).boxed()
+    }
+  }
+
+  #[tokio::test]
+  async fn cache() {
+    let stub = Stub::start();
+    stub.serve("www.omdbapi.com/", "omdb/not_found.json");
+    stub.fail("a.4cdn.org/g/catalog.json", 404);
+    let store = Arc::new(Store::default());
+    let http = stub.http().cache(Cache::new(8).store(store.clone()));
+
+    let get = |url| http.get(url).cached("test", Duration::from_secs(60));
+    let body = get("https://www.omdbapi.com?t=a&apikey=b")
+      .send()
+      .await
+      .unwrap()
+      .text()
+      .await;
+    // same request, different order
+    let cached = get("https://www.omdbapi.com?apikey=b&t=a")
+      .send()
+      .await
+      .unwrap()
+      .text()
+      .await;
+    assert_eq!(body.unwrap(), cached.unwrap());
+    assert_eq!(stub.requests().len(), 1);
+
+    // without opting in, and with errors, nothing is cached
+    http.get("https://www.omdbapi.com?t=a&apikey=b").send().await.unwrap();
+    for _ in 0..2 {
+      let resp = get("https://a.4cdn.org/g/catalog.json").send().await.unwrap();
+      assert_eq!(resp.status(), 404);
+      resp.bytes().await.unwrap();
+    }
+    assert_eq!(stub.requests().len(), 4);
+
+    let stats = http.cache_stats();
+    assert_eq!(stats.len(), 1);
+    assert_eq!((stats[0].0, stats[0].1.hits, stats[0].1.misses), ("test", 1, 3));
+
+    // a fresh memory cache gets it from the store
+    let http = stub.http().cache(Cache::new(8).store(store));
+    let get = http
+      .get("https://www.omdbapi.com?t=a&apikey=b")
+      .cached("test", Duration::from_secs(60));
+    get.send().await.unwrap().bytes().await.unwrap();
+    assert_eq!(stub.requests().len(), 4);
+  }
+
+  #[tokio::test]
+  async fn memoize() {
+    let stub = Stub::start();
+    let http = stub.http().cache(Cache::new(8));
+
+    let calls = &AtomicUsize::new(0);
+    let search = |query: &'static str| {
+      http.memoize("test", query, Duration::from_secs(60), async move {
+        calls.fetch_add(1, Ordering::Relaxed);
+        match query {
+          "fail" => Err(()),
+          _ => Ok(vec![query.to_owned()]),
+        }
+      })
+    };
+
+    assert_eq!(search("a").await, Ok(vec!["a".to_owned()]));
+    assert_eq!(search("a").await, Ok(vec!["a".to_owned()]));
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+    // failures are tried again
+    assert_eq!(search("fail").await, Err(()));
+    assert_eq!(search("fail").await, Err(()));
+    assert_eq!(calls.load(Ordering::Relaxed), 3);
+
+    let stats = http.cache_stats();
+    assert_eq!((stats[0].0, stats[0].1.hits, stats[0].1.misses), ("test", 1, 3));
+  }
+
+  #[tokio::test]
+  async fn expiry() {
+    let stub = Stub::start();
+    stub.serve("www.omdbapi.com/", "omdb/not_found.json");
+    let http = stub.http().cache(Cache::new(8));
+
+    for _ in 0..2 {
+      let get = http.get("https://www.omdbapi.com").cached("test", Duration::ZERO);
+      get.send().await.unwrap().bytes().await.unwrap();
+    }
+    assert_eq!(stub.requests().len(), 2);
+  }
+}
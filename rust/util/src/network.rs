@@ -1,20 +1,488 @@
-use futures::StreamExt;
-use reqwest::{header, redirect, IntoUrl};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::pin;
+use std::result;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime};
 
-pub async fn resolve_redirect(url: &str) -> reqwest::Result<Option<String>> {
-  let rp = redirect::Policy::none();
-  let client = reqwest::Client::builder().redirect(rp).build()?;
-  let resp = client.head(url).send().await?.error_for_status()?;
-  let url = resp.headers().get(header::LOCATION);
-  Ok(url.and_then(|url| Some(url.to_str().ok()?.to_owned())))
+use bytes::Bytes;
+use futures::{stream, Stream, StreamExt};
+use reqwest::header::{self, HeaderMap, HeaderName};
+use reqwest::{multipart, redirect, Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use url::{Position, Url};
+
+pub use self::cache::Cache;
+
+pub mod cache;
+
+const USER_AGENT: &str = concat!("riamu/", env!("CARGO_PKG_VERSION"));
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const TIMEOUT: Duration = Duration::from_secs(30);
+// api responses are way smaller than this, downloads have to ask for more, see `Request::limit`
+const LIMIT: u64 = 16 << 20;
+const RETRIES: u32 = 3;
+const BACKOFF: Duration = Duration::from_millis(500);
+// someone is looking at "thinking…" in the meantime, it's better to just fail
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+pub type Result<T> = result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+  #[error(transparent)]
+  Http(#[from] reqwest::Error),
+  #[error(transparent)]
+  Json(#[from] serde_json::Error),
+  #[error("response is larger than {0}B")]
+  TooLarge(u64),
+}
+
+// shared by everything that talks to third party services, so that connections get reused,
+// and all of them get the same timeouts, retries, size limits and user agent,
+// it's also what lets requests be pointed somewhere else, e.g. `https://a.4cdn.org/g/catalog.json`
+// becomes `http://127.0.0.1:1234/a.4cdn.org/g/catalog.json` for a stub server in tests
+#[derive(Debug, Clone)]
+pub struct Http {
+  client: reqwest::Client,
+  // for looking at redirects instead of following them
+  no_redirects: reqwest::Client,
+  proxy: Option<Url>,
+  timeouts: HashMap<String, Duration>,
+  retries: u32,
+  backoff: Duration,
+  cache: Option<Arc<Cache>>,
+}
+
+#[derive(Debug)]
+pub struct Request {
+  builder: RequestBuilder,
+  limit: u64,
+  retries: u32,
+  // whether it's safe to send it again, which it is by default only for idempotent methods
+  retry: bool,
+  backoff: Duration,
+  cache: Option<Cached>,
+}
+
+#[derive(Debug)]
+pub struct Response {
+  body: Body,
+  limit: u64,
+  // where the body goes once it's read
+  cache: Option<Cached>,
+}
+
+#[derive(Debug)]
+enum Body {
+  Live(reqwest::Response),
+  Cached(Bytes),
+}
+
+#[derive(Debug)]
+struct Cached {
+  cache: Arc<Cache>,
+  // set by `Request::cached`, until then nothing gets cached
+  source: Option<(&'static str, Duration)>,
+  key: String,
+}
+
+impl Http {
+  pub fn new() -> Self {
+    let client = |policy| {
+      let builder = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .redirect(policy);
+      builder.build().unwrap()
+    };
+
+    Self {
+      client: client(redirect::Policy::default()),
+      no_redirects: client(redirect::Policy::none()),
+      proxy: None,
+      timeouts: HashMap::new(),
+      retries: RETRIES,
+      backoff: BACKOFF,
+      cache: None,
+    }
+  }
+
+  pub fn proxy(mut self, proxy: Url) -> Self {
+    self.proxy = Some(proxy);
+    self
+  }
+
+  // for services that are known to be slow, applies to the whole request, including the body
+  pub fn timeout(mut self, host: &str, timeout: Duration) -> Self {
+    self.timeouts.insert(host.to_owned(), timeout);
+    self
+  }
+
+  pub fn retries(mut self, retries: u32) -> Self {
+    self.retries = retries;
+    self
+  }
+
+  // the first wait, it doubles on every retry, unless the service says how long to wait
+  pub fn backoff(mut self, backoff: Duration) -> Self {
+    self.backoff = backoff;
+    self
+  }
+
+  pub fn cache(mut self, cache: Cache) -> Self {
+    self.cache = Some(Arc::new(cache));
+    self
+  }
+
+  pub fn cache_stats(&self) -> Vec<(&'static str, cache::Stats)> {
+    self.cache.as_ref().map(|cache| cache.stats()).unwrap_or_default()
+  }
+
+  // for results that don't come from a request made here, e.g. searches done by the python libraries,
+  // they share the cache with responses and are counted under `source` too, failures aren't cached
+  pub async fn memoize<T, E>(
+    &self,
+    source: &'static str,
+    key: &str,
+    ttl: Duration,
+    compute: impl Future<Output = result::Result<T, E>>,
+  ) -> result::Result<T, E>
+  where
+    T: Serialize + DeserializeOwned,
+  {
+    let Some(cache) = &self.cache else {
+      return compute.await;
+    };
+
+    let key = format!("{source} {key}");
+    let cached = cache.get(source, &key).await;
+    if let Some(value) = cached.and_then(|body| serde_json::from_slice(&body).ok()) {
+      return Ok(value);
+    }
+
+    let value = compute.await?;
+    match serde_json::to_vec(&value) {
+      Ok(body) => cache.put(source, key, Bytes::from(body), ttl).await,
+      Err(err) => tracing::warn!(source, key, "failed to cache a result: {err}"),
+    }
+    Ok(value)
+  }
+
+  pub fn get(&self, url: impl AsRef<str>) -> Request {
+    self.request(Method::GET, url)
+  }
+
+  pub fn post(&self, url: impl AsRef<str>) -> Request {
+    self.request(Method::POST, url)
+  }
+
+  pub fn request(&self, method: Method, url: impl AsRef<str>) -> Request {
+    self.request_with(&self.client, method, url.as_ref())
+  }
+
+  pub async fn resolve_redirect(&self, url: &str) -> Result<Option<String>> {
+    let req = self.request_with(&self.no_redirects, Method::HEAD, url);
+    let resp = req.send().await?.error_for_status()?;
+    let url = resp.headers().get(header::LOCATION);
+    Ok(url.and_then(|url| Some(url.to_str().ok()?.to_owned())))
+  }
+
+  pub async fn download(&self, url: &str, limit: u64) -> Result<Vec<u8>> {
+    let resp = self.get(url).limit(limit).send().await?.error_for_status()?;
+    Ok(resp.bytes().await?.into())
+  }
+
+  fn request_with(&self, client: &reqwest::Client, method: Method, url: &str) -> Request {
+    let parsed = Url::parse(url).ok();
+    let host = parsed.as_ref().and_then(Url::host_str);
+    let timeout = host.and_then(|host| self.timeouts.get(host)).copied();
+
+    // only plain requests can be told apart by their url
+    let cache = match (&self.cache, &parsed) {
+      (Some(cache), Some(url)) if method == Method::GET => Some(Cached {
+        cache: cache.clone(),
+        source: None,
+        key: cache::key(&method, url),
+      }),
+      _ => None,
+    };
+
+    let retry = method.is_idempotent();
+    let builder = match (&self.proxy, &parsed) {
+      (Some(proxy), Some(url)) => {
+        let proxy = proxy.as_str().trim_end_matches('/');
+        client.request(method, format!("{}/{}", proxy, &url[Position::BeforeHost..]))
+      }
+      // invalid urls are passed as is, so that reqwest reports them
+      _ => client.request(method, url),
+    };
+
+    Request {
+      builder: builder.timeout(timeout.unwrap_or(TIMEOUT)),
+      limit: LIMIT,
+      retries: self.retries,
+      retry,
+      backoff: self.backoff,
+      cache,
+    }
+  }
+}
+
+impl Default for Http {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Request {
+  pub fn header(mut self, key: HeaderName, value: impl AsRef<str>) -> Self {
+    self.builder = self.builder.header(key, value.as_ref());
+    self
+  }
+
+  pub fn form(mut self, form: &impl Serialize) -> Self {
+    self.builder = self.builder.form(form);
+    self
+  }
+
+  // streamed parts can't be sent twice, so such requests aren't retried
+  pub fn multipart(mut self, form: multipart::Form) -> Self {
+    self.builder = self.builder.multipart(form);
+    self
+  }
+
+  pub fn timeout(mut self, timeout: Duration) -> Self {
+    self.builder = self.builder.timeout(timeout);
+    self
+  }
+
+  // the most that will be read from the response body, in bytes
+  pub fn limit(mut self, limit: u64) -> Self {
+    self.limit = limit;
+    self
+  }
+
+  // for requests that aren't idempotent by their method, but are by what they do, e.g. lookups sent as POSTs,
+  // otherwise failing ones aren't sent again, since whatever they do could end up done twice
+  pub fn retry(mut self) -> Self {
+    self.retry = true;
+    self
+  }
+
+  // successful responses are reused for `ttl` from now on, if the client has a cache,
+  // `source` is what they're counted under, see `Http::cache_stats`,
+  // the body has to be read with `bytes`, `text` or `json` for it to be cached
+  pub fn cached(mut self, source: &'static str, ttl: Duration) -> Self {
+    if let Some(cached) = &mut self.cache {
+      cached.source = Some((source, ttl));
+    }
+    self
+  }
+
+  pub async fn send(self) -> Result<Response> {
+    let Self {
+      mut builder,
+      limit,
+      retries,
+      retry,
+      backoff,
+      cache,
+    } = self;
+    let retries = if retry { retries } else { 0 };
+
+    let cache = cache.filter(|cached| cached.source.is_some());
+    if let Some(body) = match &cache {
+      Some(cached) => cached.get().await,
+      None => None,
+    } {
+      let body = Body::Cached(body);
+      return Ok(Response {
+        body,
+        limit,
+        cache: None,
+      });
+    }
+
+    let mut attempt = 0;
+    loop {
+      let next = builder.try_clone().filter(|_| attempt < retries);
+      let result = builder.send().await;
+
+      match (next, retry_delay(&result, attempt, backoff)) {
+        (Some(next), Some(delay)) => {
+          tracing::debug!(attempt, ?delay, "retrying…");
+          tokio::time::sleep(delay).await;
+          (builder, attempt) = (next, attempt + 1);
+        }
+        _ => {
+          let body = Body::Live(result?);
+          return Ok(Response { body, limit, cache });
+        }
+      }
+    }
+  }
 }
 
-pub async fn download(url: impl IntoUrl) -> reqwest::Result<Vec<u8>> {
-  let resp = reqwest::get(url).await?.error_for_status()?;
-  let mut stream = resp.bytes_stream();
-  let mut bytes = Vec::new();
-  while let Some(chunk) = stream.next().await {
-    bytes.extend(chunk?);
+impl Response {
+  pub fn status(&self) -> StatusCode {
+    match &self.body {
+      Body::Live(resp) => resp.status(),
+      Body::Cached(_) => StatusCode::OK,
+    }
+  }
+
+  pub fn headers(&self) -> &HeaderMap {
+    static NONE: OnceLock<HeaderMap> = OnceLock::new();
+    match &self.body {
+      Body::Live(resp) => resp.headers(),
+      // they aren't kept
+      Body::Cached(_) => NONE.get_or_init(HeaderMap::new),
+    }
+  }
+
+  pub fn error_for_status(self) -> Result<Self> {
+    let body = match self.body {
+      Body::Live(resp) => Body::Live(resp.error_for_status()?),
+      body => body,
+    };
+    Ok(Self { body, ..self })
+  }
+
+  // streamed bodies don't get cached
+  pub fn bytes_stream(self) -> impl Stream<Item = Result<Bytes>> {
+    let resp = match self.body {
+      Body::Live(resp) => resp,
+      Body::Cached(body) => return stream::iter([Ok(body)]).left_stream(),
+    };
+
+    let limit = self.limit;
+    let too_large = resp.content_length().is_some_and(|len| len > limit);
+
+    // the body doesn't have to match `Content-Length`, so the chunks are counted as well
+    let mut read = 0;
+    let stream = resp.bytes_stream().map(move |chunk| {
+      read += chunk.as_ref().map_or(0, Bytes::len) as u64;
+      match too_large || read > limit {
+        true => Err(Error::TooLarge(limit)),
+        false => Ok(chunk?),
+      }
+    });
+    stream.right_stream()
+  }
+
+  pub async fn bytes(self) -> Result<Bytes> {
+    let (bytes, cache) = self.read().await?;
+    if let Some(cached) = cache {
+      cached.put(bytes.clone()).await;
+    }
+    Ok(bytes)
+  }
+
+  pub async fn text(self) -> Result<String> {
+    let bytes = self.bytes().await?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+  }
+
+  pub async fn json<T: DeserializeOwned>(self) -> Result<T> {
+    self.json_cached_if(|_| true).await
+  }
+
+  // for APIs that report errors with a successful status, only what `keep` accepts gets cached
+  pub async fn json_cached_if<T: DeserializeOwned>(self, keep: impl FnOnce(&T) -> bool) -> Result<T> {
+    let (bytes, cache) = self.read().await?;
+    let json = serde_json::from_slice(&bytes)?;
+    if let Some(cached) = cache.filter(|_| keep(&json)) {
+      cached.put(bytes).await;
+    }
+    Ok(json)
+  }
+
+  // the whole body, and where it goes if it's worth caching
+  async fn read(mut self) -> Result<(Bytes, Option<Cached>)> {
+    let cache = self.cache.take().filter(|_| self.status().is_success());
+
+    let mut stream = pin!(self.bytes_stream());
+    let mut acc = Vec::new();
+    while let Some(chunk) = stream.next().await {
+      acc.extend(chunk?);
+    }
+    Ok((Bytes::from(acc), cache))
+  }
+}
+
+impl Cached {
+  async fn get(&self) -> Option<Bytes> {
+    let (source, _) = self.source?;
+    self.cache.get(source, &self.key).await
+  }
+
+  async fn put(self, body: Bytes) {
+    if let Some((source, ttl)) = self.source {
+      self.cache.put(source, self.key, body, ttl).await;
+    }
+  }
+}
+
+impl Error {
+  pub fn status(&self) -> Option<StatusCode> {
+    match self {
+      Self::Http(err) => err.status(),
+      _ => None,
+    }
+  }
+}
+
+// ---
+
+// how long to wait before trying again, if it's worth trying again at all
+fn retry_delay(result: &reqwest::Result<reqwest::Response>, attempt: u32, backoff: Duration) -> Option<Duration> {
+  let exponential = backoff * 2u32.pow(attempt);
+  match result {
+    Err(err) => err.is_connect().then_some(exponential),
+    Ok(resp) => match resp.status().as_u16() {
+      429 | 500 | 502 | 503 | 504 => {
+        let delay = retry_after(resp.headers()).unwrap_or(exponential);
+        (delay <= MAX_BACKOFF).then_some(delay)
+      }
+      _ => None,
+    },
+  }
+}
+
+// either in seconds or as a date
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+  let value = headers.get(header::RETRY_AFTER)?.to_str().ok()?;
+  match value.parse() {
+    Ok(secs) => Some(Duration::from_secs(secs)),
+    Err(_) => {
+      let date = httpdate::parse_http_date(value).ok()?;
+      Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use reqwest::header::HeaderValue;
+
+  use super::*;
+
+  #[test]
+  fn retry_after() {
+    let parse = |value: &str| {
+      let mut headers = HeaderMap::new();
+      headers.insert(header::RETRY_AFTER, HeaderValue::from_str(value).unwrap());
+      super::retry_after(&headers)
+    };
+
+    assert_eq!(parse("5"), Some(Duration::from_secs(5)));
+    assert_eq!(parse("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+    assert_eq!(parse("soon"), None);
+
+    let later = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(60));
+    assert!(parse(&later).is_some_and(|d| d > Duration::from_secs(50)));
+    assert_eq!(super::retry_after(&HeaderMap::new()), None);
   }
-  Ok(bytes)
 }
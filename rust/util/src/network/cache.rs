@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt::Debug;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use lru::LruCache;
+use parking_lot::Mutex;
+use reqwest::Method;
+use url::Url;
+
+pub type StoreResult<T> = Result<T, Box<dyn error::Error + Send + Sync>>;
+
+// bodies of successful responses to requests that opted in with `Request::cached`,
+// kept in memory, and in the store too if there is one, so that they survive restarts
+#[derive(Debug)]
+pub struct Cache {
+  memory: Mutex<LruCache<String, Entry>>,
+  store: Option<Arc<dyn Store>>,
+  stats: Mutex<BTreeMap<&'static str, Stats>>,
+}
+
+pub trait Store: Debug + Send + Sync {
+  fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, StoreResult<Option<Entry>>>;
+  fn put<'a>(&'a self, key: &'a str, source: &'a str, entry: &'a Entry) -> BoxFuture<'a, StoreResult<()>>;
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+  pub body: Bytes,
+  pub expires: SystemTime,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stats {
+  pub hits: u64,
+  pub misses: u64,
+}
+
+impl Cache {
+  // `capacity` is the number of responses kept in memory
+  pub fn new(capacity: usize) -> Self {
+    let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+    Self {
+      memory: Mutex::new(LruCache::new(capacity)),
+      store: None,
+      stats: Mutex::new(BTreeMap::new()),
+    }
+  }
+
+  pub fn store(mut self, store: Arc<dyn Store>) -> Self {
+    self.store = Some(store);
+    self
+  }
+
+  // hits and misses per source since startup
+  pub fn stats(&self) -> Vec<(&'static str, Stats)> {
+    let stats = self.stats.lock();
+    stats.iter().map(|(&source, &stats)| (source, stats)).collect()
+  }
+
+  pub(super) async fn get(&self, source: &'static str, key: &str) -> Option<Bytes> {
+    let now = SystemTime::now();
+    let fresh = |entry: &Entry| entry.expires > now;
+
+    let mut entry = self.memory.lock().get(key).filter(|e| fresh(e)).cloned();
+    if let (None, Some(store)) = (&entry, &self.store) {
+      match store.get(key).await {
+        Ok(stored) => entry = stored.filter(fresh),
+        Err(err) => tracing::warn!(source, key, "failed to read a cached response: {err}"),
+      }
+      if let Some(entry) = &entry {
+        self.memory.lock().put(key.to_owned(), entry.clone());
+      }
+    }
+
+    let mut stats = self.stats.lock();
+    let stats = stats.entry(source).or_default();
+    match entry {
+      Some(entry) => {
+        stats.hits += 1;
+        Some(entry.body)
+      }
+      None => {
+        stats.misses += 1;
+        None
+      }
+    }
+  }
+
+  pub(super) async fn put(&self, source: &'static str, key: String, body: Bytes, ttl: Duration) {
+    let entry = Entry {
+      body,
+      expires: SystemTime::now() + ttl,
+    };
+    if let Some(store) = &self.store {
+      if let Err(err) = store.put(&key, source, &entry).await {
+        tracing::warn!(source, key, "failed to store a response: {err}");
+      }
+    }
+    self.memory.lock().put(key, entry);
+  }
+}
+
+// query params that carry credentials, they don't change the response and mustn't end up in the store or logs
+const CREDENTIALS: &[&str] = &["apikey", "api_key", "appid", "key", "token", "access_token"];
+
+// the same request written differently should end up with the same key
+pub(super) fn key(method: &Method, url: &Url) -> String {
+  let mut url = url.clone();
+  url.set_fragment(None);
+
+  let pairs = url.query_pairs().into_owned();
+  let pairs = pairs.filter(|(name, _)| !CREDENTIALS.contains(&name.to_ascii_lowercase().as_str()));
+  let mut pairs = pairs.collect::<Vec<_>>();
+  pairs.sort();
+  url.set_query(None);
+  if !pairs.is_empty() {
+    url.query_pairs_mut().extend_pairs(pairs);
+  }
+
+  format!("{method} {url}")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn keys() {
+    let key = |url| key(&Method::GET, &Url::parse(url).unwrap());
+    assert_eq!(
+      key("https://Example.com/a?b=2&a=1#top"),
+      "GET https://example.com/a?a=1&b=2"
+    );
+    assert_eq!(
+      key("https://example.com/a?a=1&b=2"),
+      key("https://example.com/a?b=2&a=1")
+    );
+    assert_eq!(key("https://example.com"), "GET https://example.com/");
+    assert_ne!(key("https://example.com/a?q=x+y"), key("https://example.com/a?q=x"));
+    assert_eq!(
+      key("https://example.com/a?apikey=secret&t=x&APPID=secret"),
+      "GET https://example.com/a?t=x"
+    );
+  }
+}
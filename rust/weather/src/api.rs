@@ -1,4 +1,7 @@
+use std::time::Duration;
+
 use url::Url;
+use util::network::{self, Http};
 
 pub use self::geo::Root as Geo;
 pub use self::onecall::Root as Onecall;
@@ -6,28 +9,36 @@ pub use self::onecall::Root as Onecall;
 pub mod geo;
 pub mod onecall;
 
+// places don't move
+const GEO_TTL: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+// the forecast is only updated every 10 minutes anyway
+const ONECALL_TTL: Duration = Duration::from_secs(10 * 60);
+
 pub struct Api {
+  http: Http,
   key: String,
 }
 
 impl Api {
-  pub fn new(key: impl ToString) -> Self {
-    Self { key: key.to_string() }
+  pub fn new(http: &Http, key: impl ToString) -> Self {
+    let (http, key) = (http.clone(), key.to_string());
+    Self { http, key }
   }
 
-  pub async fn geo(&self, query: &str) -> reqwest::Result<Geo> {
+  pub async fn geo(&self, query: &str) -> network::Result<Geo> {
     let appid = ("appid", &*self.key);
     let q = ("q", query);
 
     let url = "http://api.openweathermap.org/geo/1.0/direct";
     let url = Url::parse_with_params(url, &[appid, q]).unwrap();
 
-    let resp = reqwest::get(url).await?.error_for_status()?;
+    let get = self.http.get(url).cached("weather/geo", GEO_TTL);
+    let resp = get.send().await?.error_for_status()?;
     let json = resp.json().await?;
     Ok(json)
   }
 
-  pub async fn onecall(&self, lat: f64, lon: f64) -> reqwest::Result<Onecall> {
+  pub async fn onecall(&self, lat: f64, lon: f64) -> network::Result<Onecall> {
     let appid = ("appid", &*self.key);
     let lat = ("lat", &*lat.to_string());
     let lon = ("lon", &*lon.to_string());
@@ -36,8 +47,59 @@ impl Api {
     let url = "https://api.openweathermap.org/data/2.5/onecall";
     let url = Url::parse_with_params(url, &[appid, lat, lon, units]).unwrap();
 
-    let resp = reqwest::get(url).await?.error_for_status()?;
+    let get = self.http.get(url).cached("weather/onecall", ONECALL_TTL);
+    let resp = get.send().await?.error_for_status()?;
     let json = resp.json().await?;
     Ok(json)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use stub::Stub;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn geo() {
+    let stub = Stub::start();
+    stub.serve("api.openweathermap.org/geo/1.0/direct", "openweathermap/geo.json");
+
+    let api = Api::new(&stub.http(), "key");
+    let geo = api.geo("tokyo").await.unwrap();
+    assert_eq!(geo.len(), 2);
+    assert_eq!(
+      (&*geo[0].name, &*geo[0].country, geo[0].state.as_deref()),
+      ("Tokyo", "JP", None)
+    );
+    assert_eq!(geo[1].state.as_deref(), Some("Illinois"));
+    assert_eq!(geo[1].local_names.en, None);
+
+    let query = stub.requests()[0].query().map(ToOwned::to_owned);
+    assert_eq!(query.as_deref(), Some("appid=key&q=tokyo"));
+  }
+
+  #[tokio::test]
+  async fn onecall() {
+    let stub = Stub::start();
+    stub.serve("api.openweathermap.org/data/2.5/onecall", "openweathermap/onecall.json");
+
+    let api = Api::new(&stub.http(), "key");
+    let onecall = api.onecall(35.68, 139.76).await.unwrap();
+    assert_eq!((&*onecall.timezone, onecall.timezone_offset), ("Asia/Tokyo", 32400));
+    assert_eq!((onecall.hourly.len(), onecall.daily.len()), (48, 8));
+    assert!(onecall.hourly[0].rain.is_none());
+    assert!(onecall.hourly[30].rain.is_some());
+    assert_eq!(onecall.current.weather[0].main, "Clouds");
+  }
+
+  #[tokio::test]
+  async fn error() {
+    let stub = Stub::start();
+    stub.fail("api.openweathermap.org/geo/1.0/direct", 401);
+
+    let api = Api::new(&stub.http(), "key");
+    let err = api.geo("tokyo").await.unwrap_err();
+    assert_eq!(err.status().map(|s| s.as_u16()), Some(401));
+  }
+}